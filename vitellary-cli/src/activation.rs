@@ -0,0 +1,62 @@
+//! systemd socket activation: a socket unit listens on the WebSocket port and starts vitellary
+//! on the first connection, passing it the listening socket (see `sd_listen_fds(3)`).
+
+use anyhow::{bail, Context, Result};
+use std::net::TcpListener;
+
+/// The first file descriptor systemd passes.
+#[cfg(unix)]
+const FIRST_FD: std::os::unix::io::RawFd = 3;
+
+/// The listening socket systemd passed us, if it started us through a socket unit.
+///
+/// The variables systemd sets are removed either way, so commands we start (with `wrap`) don't
+/// take the socket as theirs.
+pub(crate) fn listener() -> Result<Option<TcpListener>> {
+    let pid = std::env::var("LISTEN_PID").ok();
+    let fds = std::env::var("LISTEN_FDS").ok();
+    for var in ["LISTEN_PID", "LISTEN_FDS", "LISTEN_FDNAMES"] {
+        std::env::remove_var(var);
+    }
+    let (Some(pid), Some(fds)) = (pid, fds) else {
+        return Ok(None);
+    };
+    if pid.parse::<u32>().ok() != Some(std::process::id()) {
+        return Ok(None);
+    }
+    match fds
+        .parse::<u32>()
+        .context("systemd passed an invalid LISTEN_FDS")?
+    {
+        0 => Ok(None),
+        1 => passed().map(Some),
+        n => bail!(
+            "systemd passed {} sockets, but vitellary takes one (for the WebSocket server)",
+            n
+        ),
+    }
+}
+
+#[cfg(unix)]
+fn passed() -> Result<TcpListener> {
+    use std::os::unix::io::FromRawFd;
+
+    // Unlike the descriptors we open, the one systemd passes isn't closed on exec, so mark it to
+    // be. This also checks it's open.
+    // SAFETY: fcntl doesn't touch memory.
+    if unsafe { libc::fcntl(FIRST_FD, libc::F_SETFD, libc::FD_CLOEXEC) } == -1 {
+        return Err(std::io::Error::last_os_error())
+            .context("LISTEN_FDS is set, but there's no socket where systemd passes it");
+    }
+    // SAFETY: the descriptor is open, and nothing else in the process uses it.
+    let listener = unsafe { TcpListener::from_raw_fd(FIRST_FD) };
+    listener
+        .local_addr()
+        .context("the socket systemd passed isn't a TCP socket (use ListenStream=PORT)")?;
+    Ok(listener)
+}
+
+#[cfg(not(unix))]
+fn passed() -> Result<TcpListener> {
+    bail!("socket activation is only supported on Unix")
+}
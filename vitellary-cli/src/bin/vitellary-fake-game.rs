@@ -0,0 +1,253 @@
+//! A stand-in for VVVVVV, for testing vitellary end to end without the game: it keeps a game
+//! object in a global, like the game does, and changes it as a script says. It's only built with
+//! the `fake-game` feature, which the end-to-end tests need.
+//!
+//! ```sh
+//! vitellary-fake-game [SCRIPT]
+//! ```
+//!
+//! On start, it prints where the game object is and a `fingerprints.toml` record for itself,
+//! followed by a blank line. With the record in the config directory, vitellary finds the game
+//! object by its offset; without it (on macOS), it has to scan for it, which the object is laid
+//! out for.
+//!
+//! The script is read from SCRIPT, or stdin if it isn't given, a line at a time, so a test can
+//! drive it step by step. The game starts on the title screen, and exits at the end of the script.
+//!
+//! - `room X Y`, `state N`, `gamestate N`, `gravitycontrol N`, `deathcounts N`: set a field
+//! - `time H:MM:SS+FF`: set the timer
+//! - `save TIME AREA`: set the last save's time and area, as loading a save does
+//! - `tick N`: count N frames on the timer, a thirtieth of a second apart
+//! - `wait MS`: do nothing for MS milliseconds
+//! - `poke OFFSET VALUE`: set the 32-bit word OFFSET bytes (in hexadecimal) into the game object,
+//!   for fields past the shared layout
+//! - `text OFFSET TEXT`: set the short `std::string` OFFSET bytes into the game object to TEXT
+//!
+//! Lines starting with `#` are ignored.
+
+use anyhow::{bail, ensure, Context, Result};
+use std::fs::File;
+use std::io::{BufRead, BufReader, Write};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::Duration;
+use vitellary_core::game;
+
+/// How much of the game object there is, including room for fields past the shared layout.
+const SIZE: usize = 0x1000;
+
+// Offsets of fields in the game object; see `GameObject` in vitellary-core.
+const ROOM_X: usize = 0x18;
+const ROOM_Y: usize = 0x1c;
+const FIELDS: [(&str, usize); 4] = [
+    ("state", 0x5c),
+    ("gamestate", 0x68),
+    ("gravitycontrol", 0x74),
+    ("deathcounts", 0xa0),
+];
+const GAMESTATE: usize = 0x68;
+const TIMER: usize = 0xa4;
+const SAVETIME: usize = 0xb8;
+const SAVEAREA: usize = 0xd0;
+
+/// The game's `TITLEMODE`.
+const TITLEMODE: u32 = 1;
+const FRAMES_PER_SECOND: u32 = 30;
+
+/// The game object, aligned like the game's, since the scan finds it by the word its signature
+/// starts in.
+#[repr(C, align(8))]
+struct GameObject([AtomicU32; SIZE / 4]);
+
+static GAME: GameObject = GameObject([const { AtomicU32::new(0) }; SIZE / 4]);
+
+fn main() -> Result<()> {
+    // The strings the scan looks for, as the game initializes them.
+    set_string(SAVETIME, "00:00");
+    set_string(SAVEAREA, "nowhere");
+    set(GAMESTATE, TITLEMODE);
+    allow_readers();
+    print_record()?;
+
+    let script: Box<dyn BufRead> = match std::env::args_os().nth(1) {
+        Some(path) => Box::new(BufReader::new(File::open(&path).with_context(|| {
+            format!("failed to open {}", std::path::Path::new(&path).display())
+        })?)),
+        None => Box::new(std::io::stdin().lock()),
+    };
+    for (i, line) in script.lines().enumerate() {
+        let line = line?;
+        run(&line).with_context(|| format!("line {}: {:?}", i + 1, line))?;
+    }
+    Ok(())
+}
+
+fn run(line: &str) -> Result<()> {
+    let mut words = line.split_whitespace();
+    let Some(command) = words.next().filter(|word| !word.starts_with('#')) else {
+        return Ok(());
+    };
+    if command == "save" {
+        let time = words.next().context("expected save TIME AREA")?;
+        set_string(SAVETIME, time);
+        set_string(SAVEAREA, &words.collect::<Vec<_>>().join(" "));
+        return Ok(());
+    }
+    match (command, words.collect::<Vec<_>>().as_slice()) {
+        ("room", [x, y]) => {
+            set(ROOM_X, parse(x)?);
+            set(ROOM_Y, parse(y)?);
+        }
+        ("time", [time]) => set_timer(parse_time(time)?),
+        ("tick", [frames]) => {
+            for _ in 0..parse(frames)? {
+                std::thread::sleep(Duration::from_secs(1) / FRAMES_PER_SECOND);
+                tick();
+            }
+        }
+        ("wait", [ms]) => std::thread::sleep(Duration::from_millis(parse(ms)?.into())),
+        ("poke", [offset, value]) => set(parse_offset(offset, 4)?, parse(value)?),
+        ("text", [offset, text]) => {
+            ensure!(text.len() < 23, "{:?} is too long for a short string", text);
+            set_string(parse_offset(offset, 24)?, text);
+        }
+        (field, [value]) => {
+            let (_, offset) = FIELDS
+                .iter()
+                .find(|(name, _)| *name == field)
+                .with_context(|| format!("unknown command {:?}", command))?;
+            set(*offset, parse(value)?);
+        }
+        _ => bail!(
+            "unknown command {:?}, or the wrong number of arguments",
+            command
+        ),
+    }
+    Ok(())
+}
+
+fn parse(s: &str) -> Result<u32> {
+    s.parse()
+        .with_context(|| format!("expected a number, got {:?}", s))
+}
+
+/// Parse an offset in hexadecimal into the game object, for `len` bytes starting at a word.
+fn parse_offset(s: &str, len: usize) -> Result<usize> {
+    let offset = usize::from_str_radix(s.trim_start_matches("0x"), 16)
+        .with_context(|| format!("invalid offset {:?}", s))?;
+    ensure!(
+        offset % 4 == 0 && offset + len <= SIZE,
+        "offset {:#x} isn't a word in the game object",
+        offset
+    );
+    Ok(offset)
+}
+
+/// Parse `H:MM:SS+FF` (or `MM:SS+FF`, or without the frames) into the timer's words.
+fn parse_time(s: &str) -> Result<[u32; 4]> {
+    let (clock, frames) = s.split_once('+').unwrap_or((s, "0"));
+    let mut timer = [parse(frames)?, 0, 0, 0];
+    for (i, part) in clock.rsplit(':').enumerate() {
+        ensure!(i < 3, "expected H:MM:SS+FF, got {:?}", s);
+        timer[i + 1] = parse(part)?;
+    }
+    Ok(timer)
+}
+
+fn set(offset: usize, value: u32) {
+    GAME.0[offset / 4].store(value, Ordering::SeqCst);
+}
+
+fn get(offset: usize) -> u32 {
+    GAME.0[offset / 4].load(Ordering::SeqCst)
+}
+
+/// Write a `std::string` as libc++ lays out a short one: its length (shifted past the is_long
+/// bit), then its characters.
+fn set_string(offset: usize, s: &str) {
+    let mut buf = [0; 24];
+    buf[0] = u8::try_from(s.len()).unwrap() << 1;
+    buf[1..=s.len()].copy_from_slice(s.as_bytes());
+    for (i, word) in buf.chunks(4).enumerate() {
+        set(offset + i * 4, u32::from_ne_bytes(word.try_into().unwrap()));
+    }
+}
+
+/// The timer's words: frames, seconds, minutes and hours.
+fn set_timer(timer: [u32; 4]) {
+    for (i, value) in timer.into_iter().enumerate() {
+        set(TIMER + i * 4, value);
+    }
+}
+
+/// Count a frame, rolling over into seconds, minutes and hours like the game does.
+fn tick() {
+    let mut timer = [0; 4];
+    for (i, value) in timer.iter_mut().enumerate() {
+        *value = get(TIMER + i * 4);
+    }
+    timer[0] += 1;
+    for (i, limit) in [FRAMES_PER_SECOND, 60, 60].into_iter().enumerate() {
+        if timer[i] >= limit {
+            timer[i] = 0;
+            timer[i + 1] += 1;
+        }
+    }
+    set_timer(timer);
+}
+
+/// Print the game object's address and a `fingerprints.toml` record for this executable.
+fn print_record() -> Result<()> {
+    let exe = std::env::current_exe().context("failed to find our executable")?;
+    let fingerprint =
+        game::fingerprint(&exe)?.context("our executable has no build ID to fingerprint")?;
+    let address = std::ptr::addr_of!(GAME) as usize;
+    let offset = address - image_base(address)?;
+
+    let mut stdout = std::io::stdout().lock();
+    writeln!(stdout, "# game object at {:#x}", address)?;
+    writeln!(stdout, "[[builds]]")?;
+    writeln!(stdout, "fingerprint = {:?}", fingerprint.id())?;
+    writeln!(stdout, "name = \"vitellary-fake-game\"")?;
+    writeln!(stdout, "offset = {:#x}", offset)?;
+    writeln!(stdout, "layout = \"2.3\"")?;
+    writeln!(stdout, "arch = {:?}", fingerprint.arch)?;
+    writeln!(stdout)?;
+    stdout.flush()?;
+    Ok(())
+}
+
+/// Where the executable image containing `address` starts.
+#[cfg(unix)]
+fn image_base(address: usize) -> Result<usize> {
+    let mut info = std::mem::MaybeUninit::<libc::Dl_info>::uninit();
+    // SAFETY: dladdr only writes to `info`.
+    if unsafe { libc::dladdr(address as *const libc::c_void, info.as_mut_ptr()) } == 0 {
+        bail!("dladdr could not find our executable image");
+    }
+    // SAFETY: dladdr filled it in.
+    Ok(unsafe { info.assume_init() }.dli_fbase as usize)
+}
+
+#[cfg(not(unix))]
+fn image_base(_address: usize) -> Result<usize> {
+    bail!("the fake game only runs on Unix")
+}
+
+/// Let any process read our memory, so vitellary can attach without starting us: where
+/// `kernel.yama.ptrace_scope` is 1 (the default on most distributions), only a process's
+/// ancestors can otherwise.
+fn allow_readers() {
+    #[cfg(target_os = "linux")]
+    {
+        /// `PR_SET_PTRACER_ANY`, which libc 0.2.139 doesn't have.
+        const ANY: libc::c_ulong = libc::c_ulong::MAX;
+        // SAFETY: prctl doesn't touch memory with PR_SET_PTRACER.
+        if unsafe { libc::prctl(libc::PR_SET_PTRACER, ANY) } == -1 {
+            let err = std::io::Error::last_os_error();
+            // Without Yama, there's nothing to allow.
+            if err.raw_os_error() != Some(libc::EINVAL) {
+                eprintln!("could not allow any process to read our memory: {}", err);
+            }
+        }
+    }
+}
@@ -0,0 +1,20 @@
+//! Reads VVVVVV's memory for vitellary, as a privileged helper installed with
+//! `vitellary install-helper`. See `vitellary_core::game::helper`.
+
+use read_process_memory::Pid;
+use std::process::ExitCode;
+
+fn main() -> ExitCode {
+    let mut args = std::env::args().skip(1);
+    let Some(Ok(pid)) = args.next().map(|pid| pid.parse::<Pid>()) else {
+        eprintln!("usage: vitellary-helper PID [OFFSET]");
+        return ExitCode::from(2);
+    };
+    let offset = args
+        .next()
+        .and_then(|offset| usize::from_str_radix(offset.trim_start_matches("0x"), 16).ok());
+    match vitellary_core::game::helper::serve(pid, offset) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(_) => ExitCode::FAILURE,
+    }
+}
@@ -0,0 +1,132 @@
+use anyhow::Result;
+use vitellary_core::game::Category;
+
+/// The local preset for a speedrun.com category or variable value, whose label names it ignoring
+/// case and punctuation: "Flip Mode" is the flip-mode preset.
+#[cfg_attr(not(feature = "speedrun"), allow(dead_code))]
+fn preset(label: &str) -> Option<Category> {
+    fn key(name: &str) -> String {
+        name.chars()
+            .filter(char::is_ascii_alphanumeric)
+            .map(|c| c.to_ascii_lowercase())
+            .collect()
+    }
+
+    let label = key(label);
+    Category::ALL
+        .into_iter()
+        .find(|category| key(&category.to_string()) == label)
+}
+
+/// Fetch VVVVVV's full-game categories and their variables from speedrun.com and print them
+/// alongside the local preset each one maps to: a variable value's own preset if its label names
+/// one (like a "Flip Mode" subcategory), or else its category's.
+#[cfg(feature = "speedrun")]
+pub(crate) fn list() -> Result<()> {
+    use anyhow::Context;
+    use serde::Deserialize;
+    use std::collections::BTreeMap;
+
+    #[derive(Deserialize)]
+    struct Data<T> {
+        data: T,
+    }
+
+    #[derive(Deserialize)]
+    struct Game {
+        id: String,
+    }
+
+    #[derive(Deserialize)]
+    struct Leaderboard {
+        id: String,
+        name: String,
+        #[serde(rename = "type")]
+        kind: String,
+        variables: Data<Vec<Variable>>,
+    }
+
+    #[derive(Deserialize)]
+    struct Variable {
+        name: String,
+        values: Values,
+    }
+
+    #[derive(Deserialize)]
+    struct Values {
+        values: BTreeMap<String, Value>,
+    }
+
+    #[derive(Deserialize)]
+    struct Value {
+        label: String,
+    }
+
+    fn get<T: serde::de::DeserializeOwned>(url: &str) -> Result<T> {
+        Ok(ureq::get(url)
+            .call()
+            .with_context(|| format!("failed to fetch {}", url))?
+            .into_json::<Data<T>>()
+            .with_context(|| format!("failed to parse response from {}", url))?
+            .data)
+    }
+
+    let games: Vec<Game> = get("https://www.speedrun.com/api/v1/games?abbreviation=vvvvvv")?;
+    let game = games
+        .first()
+        .context("speedrun.com has no game with abbreviation vvvvvv")?;
+    let categories: Vec<Leaderboard> = get(&format!(
+        "https://www.speedrun.com/api/v1/games/{}/categories?embed=variables",
+        game.id
+    ))?;
+
+    let describe = |preset: Option<Category>| {
+        preset.map_or_else(
+            || "no local preset".to_owned(),
+            |preset| format!("preset {}", preset.preset()),
+        )
+    };
+    for category in categories.iter().filter(|c| c.kind == "per-game") {
+        let category_preset = preset(&category.name);
+        println!(
+            "{} ({}): {}",
+            category.name,
+            category.id,
+            describe(category_preset)
+        );
+        for variable in &category.variables.data {
+            let values: Vec<String> = variable
+                .values
+                .values
+                .values()
+                .map(|v| {
+                    let preset = preset(&v.label).or(category_preset);
+                    format!("{} ({})", v.label, describe(preset))
+                })
+                .collect();
+            println!("    {}: {}", variable.name, values.join(", "));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(feature = "speedrun"))]
+pub(crate) fn list() -> Result<()> {
+    anyhow::bail!(
+        "this build of vitellary does not support speedrun.com lookups (enable the `speedrun` \
+         feature)"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_labels_to_presets() {
+        assert_eq!(preset("Any%"), Some(Category::AnyPercent));
+        assert_eq!(preset("Flip Mode"), Some(Category::FlipMode));
+        assert_eq!(preset("flip-mode"), Some(Category::FlipMode));
+        assert_eq!(preset("100%"), None);
+    }
+}
@@ -0,0 +1,77 @@
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+/// The directory vitellary reads split profiles and other configuration from: `VITELLARY_CONFIG`
+/// if it's set, otherwise e.g. `~/.config/vitellary` on Linux or
+/// `~/Library/Application Support/vitellary` on macOS.
+pub(crate) fn dir() -> Result<PathBuf> {
+    if let Some(dir) = std::env::var_os("VITELLARY_CONFIG") {
+        return Ok(dir.into());
+    }
+    Ok(dirs::config_dir()
+        .context("could not find the configuration directory")?
+        .join("vitellary"))
+}
+
+/// The outputs to serve, from `sinks.toml` in the configuration directory, so a setup that runs
+/// several at once doesn't need them all on the command line. Each key is the name of the
+/// matching option, which wins when both are given.
+///
+/// They can also be given as environment variables named after the option (`VITELLARY_BIND`,
+/// `VITELLARY_LIVESPLIT_SERVER`, `VITELLARY_HTTP`, `VITELLARY_DASHBOARD`, and `VITELLARY_STDOUT`),
+/// which come between the command line and `sinks.toml`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub(crate) struct Sinks {
+    pub(crate) bind: Option<SocketAddr>,
+    pub(crate) livesplit_server: Option<String>,
+    pub(crate) http: Option<SocketAddr>,
+    pub(crate) dashboard: Option<SocketAddr>,
+    pub(crate) stdout: bool,
+}
+
+impl Sinks {
+    /// Load `sinks.toml`, if there is one.
+    pub(crate) fn load() -> Result<Sinks> {
+        let path = dir()?.join("sinks.toml");
+        match std::fs::read_to_string(&path) {
+            Ok(data) => {
+                toml::from_str(&data).with_context(|| format!("failed to parse {}", path.display()))
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Sinks::default()),
+            Err(err) => Err(err).with_context(|| format!("failed to read {}", path.display())),
+        }
+    }
+
+    /// Read the `VITELLARY_*` environment variables.
+    pub(crate) fn from_env() -> Result<Sinks> {
+        Ok(Sinks {
+            bind: var("VITELLARY_BIND")?,
+            livesplit_server: var("VITELLARY_LIVESPLIT_SERVER")?,
+            http: var("VITELLARY_HTTP")?,
+            dashboard: var("VITELLARY_DASHBOARD")?,
+            stdout: match std::env::var("VITELLARY_STDOUT").as_deref() {
+                Err(_) | Ok("" | "0" | "false") => false,
+                Ok("1" | "true") => true,
+                Ok(value) => bail!("VITELLARY_STDOUT is {:?}, not 1 or 0", value),
+            },
+        })
+    }
+}
+
+/// Parse the environment variable `name`, if it's set.
+fn var<T: FromStr>(name: &str) -> Result<Option<T>>
+where
+    T::Err: std::error::Error + Send + Sync + 'static,
+{
+    match std::env::var(name) {
+        Ok(value) if !value.is_empty() => value
+            .parse()
+            .map(Some)
+            .with_context(|| format!("invalid {} {:?}", name, value)),
+        _ => Ok(None),
+    }
+}
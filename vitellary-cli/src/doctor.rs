@@ -0,0 +1,135 @@
+use anyhow::{bail, Result};
+use crossterm::style::Stylize;
+use read_process_memory::Pid;
+use std::io::IsTerminal;
+use std::net::{SocketAddr, TcpListener};
+use vitellary_core::game::{AccessError, Game};
+
+const BIND_HINT: &str =
+    "another program (or another vitellary) may be using it; pick another address with --bind or \
+     --rpc";
+
+/// The results of each check, printed as they're made.
+struct Report {
+    color: bool,
+    failed: usize,
+}
+
+impl Report {
+    fn ok(&self, check: &str, detail: &str) {
+        let status = if self.color {
+            " ok ".green().to_string()
+        } else {
+            " ok ".to_owned()
+        };
+        println!("[{}] {}: {}", status, check, detail);
+    }
+
+    fn fail(&mut self, check: &str, err: &anyhow::Error, hints: &[String]) {
+        self.failed += 1;
+        let status = if self.color {
+            "FAIL".red().to_string()
+        } else {
+            "FAIL".to_owned()
+        };
+        println!("[{}] {}: {:#}", status, check, err);
+        for hint in hints {
+            println!("       hint: {}", hint);
+        }
+    }
+
+    fn skip(&self, check: &str, reason: &str) {
+        let status = if self.color {
+            "skip".dark_grey().to_string()
+        } else {
+            "skip".to_owned()
+        };
+        println!("[{}] {}: {}", status, check, reason);
+    }
+}
+
+/// Check each step of getting vitellary running: finding the game process (`pid`, or `None` if
+/// the backend doesn't use one), attaching to it, reading a sane game object, and binding the
+/// server `addresses`. Fails if any check did.
+pub(crate) fn run(
+    pid: Option<Result<Pid>>,
+    attach: impl FnOnce() -> Result<Game>,
+    addresses: &[(&str, SocketAddr)],
+) -> Result<()> {
+    let mut report = Report {
+        color: std::io::stdout().is_terminal(),
+        failed: 0,
+    };
+
+    match pid {
+        Some(Ok(pid)) => report.ok("process", &format!("found VVVVVV (pid {})", pid)),
+        Some(Err(err)) => report.fail(
+            "process",
+            &err,
+            &["start VVVVVV first, or pass its pid or --window".to_owned()],
+        ),
+        None => report.skip("process", "not needed for this backend"),
+    }
+
+    let game = if report.failed > 0 {
+        report.skip("attach", "no process to attach to");
+        None
+    } else {
+        match attach() {
+            Ok(game) => {
+                report.ok("attach", &format!("game object at {:#x}", game.address()));
+                Some(game)
+            }
+            Err(err) => {
+                report.fail("attach", &err, &hints(&err));
+                None
+            }
+        }
+    };
+
+    match game {
+        Some(game) => match game.validate() {
+            Ok(state) => report.ok(
+                "game object",
+                &format!(
+                    "gamestate {}, state {}, room {:?}",
+                    state.gamestate, state.state, state.room
+                ),
+            ),
+            Err(err) => {
+                let hints = if err.downcast_ref::<AccessError>().is_some() {
+                    hints(&err)
+                } else {
+                    vec![
+                        "this build may keep the game object somewhere else; add its \
+                         fingerprint to fingerprints.toml"
+                            .to_owned(),
+                    ]
+                };
+                report.fail("game object", &err, &hints);
+            }
+        },
+        None => report.skip("game object", "not attached"),
+    }
+
+    for (name, address) in addresses {
+        let check = format!("bind {}", name);
+        match TcpListener::bind(address) {
+            Ok(_) => report.ok(&check, &format!("{} is free", address)),
+            Err(err) => report.fail(&check, &err.into(), &[BIND_HINT.to_owned()]),
+        }
+    }
+
+    if report.failed > 0 {
+        bail!("{} of the checks failed", report.failed);
+    }
+    println!("everything looks good");
+    Ok(())
+}
+
+/// The hint for an error, if it's one we know what to do about.
+fn hints(err: &anyhow::Error) -> Vec<String> {
+    err.downcast_ref::<AccessError>()
+        .map(|err| vec![err.hint().to_owned()])
+        .unwrap_or_default()
+}
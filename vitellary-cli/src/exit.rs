@@ -0,0 +1,72 @@
+use serde_json::json;
+use std::net::SocketAddr;
+use std::process::ExitCode;
+use thiserror::Error;
+use vitellary_core::game::AccessError;
+
+/// Failures found by the command itself that scripts may want to react to.
+#[derive(Debug, Error)]
+pub(crate) enum Failure {
+    #[error("no VVVVVV process found")]
+    NoProcess,
+    #[error("could not find a process for window {title:?}: {detail}")]
+    NoWindow { title: String, detail: String },
+    #[error("failed to bind {what} address {address}")]
+    Bind {
+        what: &'static str,
+        address: SocketAddr,
+        source: std::io::Error,
+    },
+}
+
+/// The exit status for an error and a name for its kind. These are stable, so scripts can rely
+/// on them; they're listed in `--help`.
+fn classify(err: &anyhow::Error) -> (u8, &'static str) {
+    if let Some(failure) = err.downcast_ref::<Failure>() {
+        return match failure {
+            Failure::NoProcess | Failure::NoWindow { .. } => (2, "no-process"),
+            Failure::Bind { .. } => (3, "bind-failed"),
+        };
+    }
+    match err.downcast_ref::<AccessError>() {
+        Some(AccessError::PermissionDenied { .. }) => (10, "attach-denied"),
+        Some(AccessError::PtraceScope { .. }) => (11, "attach-denied"),
+        Some(AccessError::PtraceDisabled { .. }) => (12, "attach-denied"),
+        Some(AccessError::TaskForPid { .. }) => (13, "attach-denied"),
+        Some(AccessError::Protected { .. }) => (14, "attach-denied"),
+        Some(AccessError::ProcessGone { .. }) => (15, "game-exited"),
+        None => (1, "error"),
+    }
+}
+
+/// Whether `err` is the game exiting.
+pub(crate) fn game_exited(err: &anyhow::Error) -> bool {
+    matches!(
+        err.downcast_ref::<AccessError>(),
+        Some(AccessError::ProcessGone { .. })
+    )
+}
+
+/// Print `err` (as a JSON object on one line if `json`) and return the exit status for it.
+pub(crate) fn report(err: &anyhow::Error, json: bool) -> ExitCode {
+    let (code, kind) = classify(err);
+    let hint = err.downcast_ref::<AccessError>().map(AccessError::hint);
+    if json {
+        eprintln!(
+            "{}",
+            json!({
+                "error": kind,
+                "code": code,
+                "message": format!("{:#}", err),
+                "causes": err.chain().map(ToString::to_string).collect::<Vec<_>>(),
+                "hint": hint,
+            })
+        );
+    } else {
+        eprintln!("Error: {:?}", err);
+        if let Some(hint) = hint {
+            eprintln!("hint: {}", hint);
+        }
+    }
+    ExitCode::from(code)
+}
@@ -0,0 +1,90 @@
+use anyhow::{bail, Context, Result};
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+use std::process::Command;
+use vitellary_core::game::AccessError;
+
+/// Where `install-helper` puts the helper, and where we look for it.
+pub(crate) const INSTALLED: &str = "/usr/local/libexec/vitellary-helper";
+
+/// Whether to retry attaching through the helper after `err`.
+pub(crate) fn should_use(err: &anyhow::Error) -> bool {
+    matches!(
+        err.downcast_ref::<AccessError>(),
+        Some(
+            AccessError::TaskForPid { .. }
+                | AccessError::PtraceScope { .. }
+                | AccessError::PermissionDenied { .. }
+        )
+    ) && Path::new(INSTALLED).exists()
+}
+
+/// Where `install-helper` puts the polkit policy that lets `pkexec` run the helper.
+#[cfg(target_os = "linux")]
+const POLICY_PATH: &str = "/usr/share/polkit-1/actions/org.vitellary.helper.policy";
+
+#[cfg(target_os = "linux")]
+const POLICY: &str = include_str!("../polkit/org.vitellary.helper.policy");
+
+/// The command that runs the installed helper. On Linux that's through `pkexec`, which asks the
+/// user to authenticate (once per session) instead of the helper being setuid.
+#[cfg(target_os = "linux")]
+pub(crate) fn command() -> Command {
+    let mut command = Command::new("pkexec");
+    command.arg(INSTALLED);
+    command
+}
+
+/// The command that runs the installed helper.
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn command() -> Command {
+    Command::new(INSTALLED)
+}
+
+/// Install the helper built alongside this program so that reading the game's memory is the only
+/// thing that needs root: on Linux with a polkit policy allowing `pkexec` to run it, and on macOS
+/// as a setuid-root executable.
+pub(crate) fn install() -> Result<()> {
+    #[cfg(target_os = "linux")]
+    const MODE: u32 = 0o755;
+    #[cfg(not(target_os = "linux"))]
+    const MODE: u32 = 0o4755;
+    let source = std::env::current_exe()
+        .context("could not find this program")?
+        .with_file_name("vitellary-helper");
+    if !source.exists() {
+        bail!(
+            "{} doesn't exist; build it with `cargo build --bin vitellary-helper`",
+            source.display()
+        );
+    }
+    let dest = Path::new(INSTALLED);
+    let result = (|| -> std::io::Result<()> {
+        if let Some(dir) = dest.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::copy(&source, dest)?;
+        std::os::unix::fs::chown(dest, Some(0), Some(0))?;
+        std::fs::set_permissions(dest, std::fs::Permissions::from_mode(MODE))?;
+        #[cfg(target_os = "linux")]
+        std::fs::write(POLICY_PATH, POLICY)?;
+        Ok(())
+    })();
+    match result {
+        Ok(()) => {
+            #[cfg(target_os = "linux")]
+            println!(
+                "installed {} and its polkit policy {}",
+                dest.display(),
+                POLICY_PATH
+            );
+            #[cfg(not(target_os = "linux"))]
+            println!("installed {} (setuid root)", dest.display());
+            Ok(())
+        }
+        Err(err) if err.kind() == std::io::ErrorKind::PermissionDenied => {
+            bail!("installing the helper needs root; run `sudo vitellary install-helper`")
+        }
+        Err(err) => Err(err).with_context(|| format!("failed to install {}", dest.display())),
+    }
+}
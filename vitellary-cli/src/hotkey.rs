@@ -0,0 +1,69 @@
+use anyhow::Result;
+use crossbeam_channel::Receiver;
+use std::str::FromStr;
+use vitellary_core::game::Command;
+
+/// A global hotkey binding, given on the command line as `COMMAND=KEY`.
+#[derive(Debug, Clone)]
+#[cfg_attr(not(feature = "hotkeys"), allow(dead_code))]
+pub(crate) struct Binding {
+    command: Command,
+    key: String,
+}
+
+impl FromStr for Binding {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Binding, String> {
+        let (command, key) = s
+            .split_once('=')
+            .ok_or_else(|| format!("expected COMMAND=KEY, got {:?}", s))?;
+        Ok(Binding {
+            command: command.parse()?,
+            key: key.to_owned(),
+        })
+    }
+}
+
+/// Start listening for global hotkeys, returning a channel of the commands they trigger.
+///
+/// Key names are matched against `rdev::Key` variant names (e.g. `Kp1`, `F9`, `KeyS`),
+/// case-insensitively. Listening requires an X11 session on Linux and accessibility permissions
+/// on macOS.
+#[cfg(feature = "hotkeys")]
+#[allow(clippy::unnecessary_wraps)] // matches the signature without the feature
+pub(crate) fn listen(bindings: &[Binding]) -> Result<Receiver<Command>> {
+    if bindings.is_empty() {
+        return Ok(crossbeam_channel::never());
+    }
+
+    let bindings = bindings.to_vec();
+    let (sender, receiver) = crossbeam_channel::unbounded();
+    std::thread::spawn(move || {
+        let result = rdev::listen(move |event| {
+            if let rdev::EventType::KeyPress(key) = event.event_type {
+                let name = format!("{:?}", key);
+                for binding in &bindings {
+                    if binding.key.eq_ignore_ascii_case(&name) {
+                        sender.send(binding.command).ok();
+                    }
+                }
+            }
+        });
+        if let Err(err) = result {
+            log::error!("failed to listen for hotkeys: {:?}", err);
+        }
+    });
+    Ok(receiver)
+}
+
+#[cfg(not(feature = "hotkeys"))]
+pub(crate) fn listen(bindings: &[Binding]) -> Result<Receiver<Command>> {
+    if bindings.is_empty() {
+        Ok(crossbeam_channel::never())
+    } else {
+        anyhow::bail!(
+            "this build of vitellary does not support hotkeys (enable the `hotkeys` feature)"
+        )
+    }
+}
@@ -0,0 +1,23 @@
+use anyhow::{Context, Result};
+use std::net::{IpAddr, SocketAddr, UdpSocket};
+
+/// The address other devices on the local network can reach us at: the source address the kernel
+/// picks for a route off this machine. Connecting a UDP socket doesn't send anything.
+pub(crate) fn address() -> Result<IpAddr> {
+    let socket = UdpSocket::bind(("0.0.0.0", 0)).context("failed to open a UDP socket")?;
+    socket
+        .connect(("192.0.2.1", 9))
+        .context("could not find a LAN address (is the network connected?)")?;
+    Ok(socket.local_addr()?.ip())
+}
+
+/// The URL other devices should connect to for a server bound to `bind`, or `None` if it's only
+/// reachable from this machine.
+pub(crate) fn url(bind: SocketAddr) -> Result<Option<String>> {
+    let ip = match bind.ip() {
+        ip if ip.is_loopback() => return Ok(None),
+        ip if ip.is_unspecified() => address()?,
+        ip => ip,
+    };
+    Ok(Some(format!("ws://{}", SocketAddr::new(ip, bind.port()))))
+}
@@ -0,0 +1,1483 @@
+#![warn(clippy::pedantic)]
+#![allow(
+    clippy::assertions_on_constants,
+    clippy::doc_markdown,
+    clippy::struct_field_names,
+    clippy::uninlined_format_args
+)]
+
+mod activation;
+mod categories;
+mod config;
+mod doctor;
+mod exit;
+mod helper;
+mod hotkey;
+mod lan;
+mod process;
+mod profiles;
+mod qr;
+mod rpc;
+mod tui;
+mod verbosity;
+mod watchdog;
+mod wrap;
+
+use anyhow::{bail, Context, Result};
+use argh::FromArgs;
+use crossbeam_channel::{Receiver, Sender, TryRecvError};
+use env_logger::{Env, Target};
+use read_process_memory::Pid;
+use rpc::Control;
+use serde_json::{json, Value};
+use std::io::ErrorKind;
+use std::net::{SocketAddr, TcpListener};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use vitellary_core::game::scan::parse_range;
+use vitellary_core::game::{self, BackendKind, Command, Event, Game, Message};
+use vitellary_protocols::{
+    ghost, goals, history, http, livesplit, markers, race, racetime, randomizer, route, server,
+    state, stats, stdout, trace, twitch, verification,
+};
+
+#[derive(FromArgs)]
+#[allow(clippy::struct_excessive_bools)] // they're all switches
+/// Attach to a VVVVVV process and provide a LiveSplit One server.
+#[argh(
+    note = "Environment: VITELLARY_BIND, VITELLARY_LIVESPLIT_SERVER, VITELLARY_HTTP, \
+            VITELLARY_DASHBOARD and VITELLARY_STDOUT set the matching options when they aren't \
+            given; VITELLARY_CONFIG is the configuration directory; VITELLARY_LOG filters the \
+            log like RUST_LOG. Send SIGUSR1 to cycle vitellary's logging between the filter, \
+            debug and trace.",
+    error_code(1, "any other error"),
+    error_code(2, "no VVVVVV process found"),
+    error_code(3, "failed to bind the WebSocket server or control API address"),
+    error_code(10, "permission denied reading the game's memory"),
+    error_code(11, "kernel.yama.ptrace_scope stops us reading the game's memory"),
+    error_code(
+        12,
+        "kernel.yama.ptrace_scope is 3 (no process may read another's memory)"
+    ),
+    error_code(13, "task_for_pid denied because we're not root (macOS)"),
+    error_code(
+        14,
+        "task_for_pid denied even to root (macOS System Integrity Protection)"
+    ),
+    error_code(15, "the game exited")
+)]
+struct Args {
+    /// enable verbose logging output
+    #[argh(switch, short = 'v')]
+    verbose: bool,
+
+    /// on failure, print a JSON object with the error's kind, exit status, message, causes, and
+    /// hint to stderr instead of the usual message
+    #[argh(switch)]
+    json_errors: bool,
+
+    /// bind address for WebSocket (default: 127.0.0.1:5555; port 0 picks any free port); for any
+    /// address other devices can reach, the URL to connect to is printed with a QR code
+    #[argh(option)]
+    bind: Option<SocketAddr>,
+
+    /// if the WebSocket port is taken, try the next few ports and then any free port, instead of
+    /// failing
+    #[argh(switch)]
+    port_fallback: bool,
+
+    /// serve one WebSocket client over stdin and stdout instead of listening, as inetd (or a
+    /// command run over SSH) would start us, and exit when it disconnects; when started by a
+    /// systemd socket unit, the socket it passes is used without asking
+    #[argh(switch)]
+    inetd: bool,
+
+    /// show a live status display in the terminal instead of log output
+    #[argh(switch)]
+    tui: bool,
+
+    /// global hotkey for a manual command, as COMMAND=KEY (commands: split, skipsplit, undo,
+    /// reset; keys: rdev key names like Kp1 or F9); may be repeated
+    #[argh(option)]
+    hotkey: Vec<hotkey::Binding>,
+
+    /// minimum time between two firings of a split that may --repeat, as EVENT=SECONDS; may be
+    /// repeated
+    #[argh(option)]
+    cooldown: Vec<game::Cooldown>,
+
+    /// let a split fire again each time its states come back in the same run, instead of only
+    /// once (subject to --cooldown); may be repeated
+    #[argh(option)]
+    repeat: Vec<game::Event>,
+
+    /// only reset the run once the game has stayed out of a playing state for this many seconds,
+    /// pausing the timer in the meantime, so a quick trip to the menu doesn't end the run
+    #[argh(option)]
+    reset_grace: Option<f64>,
+
+    /// if the game crashes or closes mid-run, wait this many seconds for it to restart instead of
+    /// exiting, and resume the run if a save is loaded (the history records the crash either way)
+    #[argh(option)]
+    crash_resume: Option<f64>,
+
+    /// frames per second counted by the game's timer (default: 30; read from the build's
+    /// frame-rate field if it's mapped, or raised automatically if the timer counts faster)
+    #[argh(option, default = "30")]
+    frame_rate: u32,
+
+    /// interpolate the game time between the game's logic frames (which it steps about 30 times a
+    /// second, even when drawing faster), so timers show it counting smoothly
+    #[argh(switch)]
+    interpolate: bool,
+
+    /// practice a single segment, named by the split that ends it: every loaded save starts an
+    /// attempt, and only that split fires
+    #[argh(option)]
+    practice: Option<game::Event>,
+
+    /// compatibility mode for practice forks with savestates: ignore the jumps in state and room
+    /// they cause, and start a new practice attempt on each one
+    #[argh(switch)]
+    practice_fork: bool,
+
+    /// file to record practice attempts (with --practice) and Super Gravitron attempts to, as
+    /// JSON lines
+    #[argh(option)]
+    history: Option<PathBuf>,
+
+    /// when a run is completed, write the rooms visited and when to route-RUN_ID.json (or .csv)
+    /// in this directory
+    #[argh(option)]
+    route_dir: Option<PathBuf>,
+
+    /// format for --route-dir: json (default) or csv
+    #[argh(option, default = "route::Format::Json")]
+    route_format: route::Format,
+
+    /// with --route-dir, also draw a heatmap of the time spent in each room as route-RUN_ID.svg
+    #[argh(switch)]
+    route_heatmap: bool,
+
+    /// compress WebSocket messages for clients that offer permessage-deflate, for timers on slow
+    /// links
+    #[argh(switch)]
+    ws_compression: bool,
+
+    /// add half the measured round trip time to the game time sent to LiveSplit One while the
+    /// timer is running, for timers on another device
+    #[argh(switch)]
+    compensate_latency: bool,
+
+    /// also split whenever a teleporter is used
+    #[argh(switch)]
+    split_on_teleport: bool,
+
+    /// how many splits LiveSplit One should have, to warn when one connects with a different
+    /// number (defaults to the number of built-in splits, or of the --level profile's splits;
+    /// not checked with --split-on-teleport unless given)
+    #[argh(option)]
+    split_count: Option<usize>,
+
+    /// how long to keep events that happen while no WebSocket client is connected, in seconds,
+    /// to send to the next one that connects (default 60; 0 to not keep them)
+    #[argh(option, default = "60")]
+    replay_window: u64,
+
+    /// also drive the LiveSplit Server component at this address (HOST:PORT, usually port 16834)
+    #[argh(option)]
+    livesplit_server: Option<String>,
+
+    /// also serve the run state as JSON over HTTP on this address
+    #[argh(option)]
+    http: Option<SocketAddr>,
+
+    /// also serve a web dashboard with the run state, segment charts from --history, and
+    /// controls on this address
+    #[argh(option)]
+    dashboard: Option<SocketAddr>,
+
+    /// also print every message to stdout as a line of JSON
+    #[argh(switch)]
+    stdout: bool,
+
+    /// create Twitch stream markers on every event, using credentials from this JSON file
+    #[argh(option)]
+    twitch_credentials: Option<PathBuf>,
+
+    /// record a tamper-evident log of game state samples and events to this new file, for
+    /// leaderboard moderation (check it with `vitellary verify`)
+    #[argh(option)]
+    verification_log: Option<PathBuf>,
+
+    /// race another vitellary instance: follow its JSON feed at this URL (ws://HOST:PORT/json)
+    /// and serve a live comparison of both runners' splits at /race
+    #[argh(option)]
+    race: Option<String>,
+
+    /// append every state read from the game to this CSV file, for analysis in a spreadsheet
+    #[argh(option)]
+    trace_states: Option<PathBuf>,
+
+    /// append a marker (wall clock time, timecode, event and game time) to this CSV file for
+    /// every start, split and reset, for lining up recordings with the run
+    #[argh(option)]
+    markers: Option<PathBuf>,
+
+    /// the frame rate of the timecodes in --markers (default 30)
+    #[argh(option, default = "30")]
+    marker_fps: u32,
+
+    /// compare each room transition against the fastest completed run in this verification log,
+    /// logging how far ahead or behind you are and serving the deltas at /ghost
+    #[argh(option)]
+    ghost: Option<PathBuf>,
+
+    /// track the goals in this file (like a bingo board's squares) through each run, logging each
+    /// one completed and serving them at /goals
+    #[argh(option)]
+    goals: Option<PathBuf>,
+
+    /// serve the rooms entered, trinkets collected and crewmates rescued in each run at
+    /// /randomizer, for multiworld and randomizer clients
+    #[argh(switch)]
+    randomizer: bool,
+
+    /// racetime.gg race room to mark yourself done in when the game is completed (or to forfeit
+    /// when a run is reset), as CATEGORY/SLUG; needs --racetime-credentials
+    #[argh(option)]
+    racetime: Option<String>,
+
+    /// JSON file with a racetime.gg OAuth2 `token` that has the race_action scope
+    #[argh(option)]
+    racetime_credentials: Option<PathBuf>,
+
+    /// serve a JSON-RPC 2.0 control API on this address, one request per line over TCP (e.g.
+    /// 127.0.0.1:5556); methods: pause, resume, split, skipsplit, undo, reset, reattach,
+    /// switch_profile, and state
+    #[argh(option)]
+    rpc: Option<SocketAddr>,
+
+    /// split using the profile for this custom level (levels/LEVEL.toml in the config directory)
+    /// instead of the campaign's splits; if the build's custom-mode field is mapped, only while a
+    /// custom level is being played (default: the profile named after the level being played, if
+    /// the build's custom-level field is mapped)
+    #[argh(option)]
+    level: Option<String>,
+
+    /// also follow the splits of the profile for this category (levels/NAME.toml in the config
+    /// directory) alongside the run's own, sending them as events namespaced by NAME; a LiveSplit
+    /// One connecting to /category/NAME follows them (repeatable)
+    #[argh(option)]
+    track_category: Vec<String>,
+
+    /// refuse to split with a profile that has problems (empty or overlapping state ranges,
+    /// duplicate split names, or practice and debounce options naming splits it doesn't have)
+    /// instead of warning about them
+    #[argh(switch)]
+    strict_profiles: bool,
+
+    /// address range to scan for the game object on macOS, as START..END in hexadecimal (default:
+    /// 0x1_0000_0000..0x1_4000_0000, widening if nothing is found)
+    #[argh(option, from_str_fn(parse_range))]
+    scan_range: Option<Range<usize>>,
+
+    /// how to read the game: native (default), proton (the Windows build under Proton or Wine,
+    /// Linux only), dump:PATH (a file containing the game object), replay:PATH (the samples in a
+    /// verification log, played back), remote:ADDR (a `vitellary agent` on another machine),
+    /// ssh:HOST (a `vitellary agent --stdio` run on another machine over SSH), or mock (a game
+    /// that never leaves the title screen)
+    #[argh(option)]
+    backend: Option<BackendKind>,
+
+    /// comma-separated gamestates that count as playing, for forks and mods that add gamemodes
+    /// (default: from the build's fingerprint record, or 0,4,5)
+    #[argh(option)]
+    playing_states: Option<game::Gamestates>,
+
+    /// refuse to attach to a game whose version isn't this one (e.g. 2.3.6), or whose version
+    /// can't be detected
+    #[argh(option)]
+    expected_version: Option<game::Version>,
+
+    /// with --expected-version, only warn about a mismatch instead of refusing to attach
+    #[argh(switch)]
+    warn_version_mismatch: bool,
+
+    /// tag runs as this category preset (any% or flip-mode), warning when the game's settings
+    /// disagree (default: whichever the settings make each run, if flip mode can be read)
+    #[argh(option)]
+    category: Option<game::Category>,
+
+    /// offsets of game object fields the build's fingerprint record doesn't map, as comma-separated
+    /// FIELD=OFFSET pairs in hexadecimal: slowdown (the accessibility game speed), invincibility,
+    /// glitchrunner, ingame-timer, flip-mode, time-trial-level, time-trial-results,
+    /// gravitron-mode, gravitron-timer, gravitron-record, gravitron-game, telesave, quicksave,
+    /// frame-rate, custom-mode, and custom-level
+    #[argh(option)]
+    fields: Option<game::Fields>,
+
+    /// with --backend replay:PATH, how fast to play back the log: a multiple of real time
+    /// (default 1), or max to feed every sample to the splitter as fast as it can take them
+    #[argh(option, default = "verification::Speed::Factor(1.0)")]
+    replay_speed: verification::Speed,
+
+    /// with --backend replay:PATH, start playing back this many seconds into the recording
+    #[argh(option, default = "0.0")]
+    replay_from: f64,
+
+    /// attach to the process owning the window with this title, or the focused window if
+    /// "active"
+    #[argh(option)]
+    window: Option<String>,
+
+    /// set up for a Steam Deck: attach to VVVVVV whether it runs natively or under Proton, listen
+    /// on all interfaces (unless --bind is given), and print the LAN URL with a QR code
+    #[argh(switch)]
+    steam_deck: bool,
+
+    /// wait for VVVVVV to launch instead of exiting when it isn't running, and go back to waiting
+    /// when it exits, so vitellary can be started at login
+    #[argh(switch)]
+    wait_for_game: bool,
+
+    #[argh(subcommand)]
+    command: Option<Subcommand>,
+
+    /// process ID of a specific VVVVVV process
+    #[argh(positional)]
+    pid: Option<Pid>,
+}
+
+#[derive(FromArgs)]
+#[argh(subcommand)]
+enum Subcommand {
+    Agent(Agent),
+    Categories(Categories),
+    Doctor(Doctor),
+    InstallHelper(InstallHelper),
+    Launch(Launch),
+    Profiles(Profiles),
+    Serve(Serve),
+    Stats(Stats),
+    Verify(Verify),
+    Wrap(Wrap),
+}
+
+#[derive(FromArgs)]
+/// Only read the game, for a splitter on another machine (`vitellary serve --remote`) to run the
+/// timer logic and outputs: wait for VVVVVV to launch, answer one splitter at a time, and wait for
+/// the game again when it exits.
+#[argh(subcommand, name = "agent")]
+struct Agent {
+    /// address to listen for the splitter on (default: 127.0.0.1:5557, which only a splitter on
+    /// this machine or through a tunnel can reach; the agent has no authentication, so only listen
+    /// on a network you trust, like 0.0.0.0:5557 on a home LAN)
+    #[argh(option)]
+    listen: Option<SocketAddr>,
+
+    /// answer the splitter that ran us on stdin and stdout instead of listening (as `vitellary
+    /// serve --ssh` does over SSH), exiting when it disconnects or the game exits
+    #[argh(switch)]
+    stdio: bool,
+}
+
+#[derive(FromArgs)]
+/// List speedrun.com categories and the local presets they map to.
+#[argh(subcommand, name = "categories")]
+struct Categories {}
+
+#[derive(FromArgs)]
+/// Check that vitellary can find and read the game and bind its addresses, using the same options
+/// as a normal run.
+#[argh(subcommand, name = "doctor")]
+struct Doctor {}
+
+#[derive(FromArgs)]
+/// Check a verification log's hash chain and recompute the final time of each completed run.
+#[argh(subcommand, name = "verify")]
+struct Verify {
+    /// the log to check
+    #[argh(positional)]
+    file: PathBuf,
+}
+
+#[derive(FromArgs)]
+/// Summarize every session in a history file: attempts, resets and times by segment, and
+/// personal best progression.
+#[argh(subcommand, name = "stats")]
+struct Stats {
+    /// the history file (as written by --history)
+    #[argh(positional)]
+    file: PathBuf,
+
+    /// output format: text (default), json, or csv (personal best progression, for charts)
+    #[argh(option, default = "stats::Format::Text")]
+    format: stats::Format,
+}
+
+#[derive(FromArgs)]
+/// Install vitellary-helper, which reads the game's memory (run with sudo), so vitellary itself
+/// doesn't need root: on Linux along with a polkit policy so it runs through pkexec, and on macOS
+/// as a setuid-root program.
+#[argh(subcommand, name = "install-helper")]
+struct InstallHelper {}
+
+#[derive(FromArgs)]
+/// Launch the game and split it until it exits, then exit with its status.
+#[argh(subcommand, name = "launch")]
+struct Launch {
+    /// the game's executable, or the directory (or macOS app bundle) it's in
+    #[argh(positional)]
+    game: PathBuf,
+
+    /// arguments for the game
+    #[argh(positional, greedy)]
+    args: Vec<String>,
+}
+
+#[derive(FromArgs)]
+/// Run the game and split it until it exits, then exit with its status, as in Steam launch
+/// options: `vitellary wrap -- %command%`. Signals vitellary gets are passed on to the game.
+#[argh(subcommand, name = "wrap")]
+struct Wrap {
+    /// the command that runs the game
+    #[argh(positional, greedy)]
+    command: Vec<String>,
+}
+
+#[derive(FromArgs)]
+/// Split the game, as with no subcommand.
+#[argh(subcommand, name = "serve")]
+struct Serve {
+    /// read the game through the `vitellary agent` at this address (HOST or HOST:PORT) instead of
+    /// attaching to it here, the same as --backend remote:ADDR; add --wait-for-game to keep waiting
+    /// for the game when it exits
+    #[argh(option)]
+    remote: Option<String>,
+
+    /// read the game through `vitellary agent --stdio` run on this host over SSH (which must
+    /// find vitellary on its PATH), the same as --backend ssh:HOST, for when the game's machine
+    /// can't take connections
+    #[argh(option)]
+    ssh: Option<String>,
+}
+
+impl Serve {
+    /// The backend `--remote` or `--ssh` asks for, if either is given.
+    fn backend(self) -> Option<BackendKind> {
+        self.remote
+            .map(BackendKind::Remote)
+            .or(self.ssh.map(BackendKind::Ssh))
+    }
+}
+
+#[derive(FromArgs)]
+/// Manage split profiles.
+#[argh(subcommand, name = "profiles")]
+struct Profiles {
+    #[argh(subcommand)]
+    command: ProfilesCommand,
+}
+
+#[derive(FromArgs)]
+#[argh(subcommand)]
+enum ProfilesCommand {
+    Update(ProfilesUpdate),
+}
+
+#[derive(FromArgs)]
+/// Download the curated split profiles into the config directory.
+#[argh(subcommand, name = "update")]
+struct ProfilesUpdate {
+    /// base URL of the profile repository (default: this project's profiles directory)
+    #[argh(option)]
+    source: Option<String>,
+}
+
+fn main() -> ExitCode {
+    let args: Args = argh::from_env();
+    let json_errors = args.json_errors;
+    match run(args) {
+        Ok(code) => code,
+        Err(err) => exit::report(&err, json_errors),
+    }
+}
+
+/// Set up logging, to the returned buffer for the TUI if it's on, or to stderr.
+fn init_logging(args: &Args) -> tui::LogBuffer {
+    let log = tui::LogBuffer::default();
+    let filter = if args.verbose {
+        "vitellary=debug"
+    } else {
+        "vitellary=info"
+    };
+    verbosity::init(|| {
+        // VITELLARY_LOG takes the place of RUST_LOG, for deployments that set it for other
+        // programs.
+        let env = if std::env::var_os("VITELLARY_LOG").is_some() {
+            Env::new().filter("VITELLARY_LOG")
+        } else {
+            Env::default()
+        };
+        let mut builder = env_logger::Builder::from_env(env.default_filter_or(filter));
+        if args.tui {
+            builder.target(Target::Pipe(Box::new(log.clone())));
+        }
+        builder
+    });
+    log
+}
+
+fn run(mut args: Args) -> Result<ExitCode> {
+    let log = init_logging(&args);
+    ctrlc::set_handler(|| {
+        if !game::scan::cancel() {
+            std::process::exit(130);
+        }
+    })
+    .context("failed to set Ctrl-C handler")?;
+
+    let mut child = match args.command.take() {
+        Some(Subcommand::Wrap(Wrap { command })) => Some(wrap::spawn(&command)?),
+        Some(Subcommand::Launch(Launch {
+            game,
+            args: game_args,
+        })) => {
+            let (child, pid) = wrap::launch(&game, &game_args)?;
+            args.pid = Some(pid);
+            Some(child)
+        }
+        Some(Subcommand::Serve(serve)) => {
+            args.backend = serve.backend().or(args.backend.take());
+            None
+        }
+        Some(command) => return subcommand(&args, &command).map(|()| ExitCode::SUCCESS),
+        None => None,
+    };
+    load_sinks(&mut args)?;
+
+    let game = if let Some(child) = &mut child {
+        if args.pid.is_none() {
+            args.pid = Some(wrap::find_game(child)?);
+        }
+        wrap::attach(child, || setup(&args, args.level.as_deref()))?
+    } else if args.wait_for_game {
+        wait_for_game(|| setup(&args, args.level.as_deref()))
+    } else {
+        setup(&args, args.level.as_deref())?
+    };
+    let pid = game.pid();
+    // A pid given on the command line is gone once the game restarts, so attaching again looks for
+    // the game by name. A launched or wrapped game is still found by its own pid, since the session
+    // ends when it exits.
+    if child.is_none() {
+        args.pid = None;
+    }
+    let commands = hotkey::listen(&args.hotkey)?;
+    let (control_sender, controls) = listen_rpc(&args)?;
+
+    let (server, bind) = listen(&args)?;
+    let url = bind.map_or_else(
+        || "stdio".to_owned(),
+        |bind| server_url(bind, !args.tui && !args.stdout),
+    );
+    let outputs = Arc::new(watchdog::Outputs::new());
+    let server = spawn_server(&args, server, &outputs)?;
+    spawn_sinks(&args, &outputs, &control_sender)?;
+    let tui_receiver = if args.tui {
+        let (tui_sender, tui_receiver) = crossbeam_channel::bounded::<Message>(10);
+        outputs.add("tui", tui_sender);
+        Some(tui_receiver)
+    } else {
+        None
+    };
+    watchdog::spawn(Arc::clone(&outputs), server, control_sender);
+    let level = args.level.clone();
+    let replay = matches!(args.backend, Some(BackendKind::Replay(_)));
+    let run = move || {
+        let mut game = game;
+        let result = loop {
+            let result = poll(
+                game,
+                level.clone(),
+                &commands,
+                &controls,
+                &outputs,
+                &url,
+                |level: Option<&str>| setup(&args, level),
+            );
+            // A replay ends by running out of samples, which isn't a failure.
+            break match result {
+                Err(err) if exit::game_exited(&err) && child.is_some() => {
+                    wrap::wait(child.take().expect("checked above"))
+                }
+                Err(err) if args.wait_for_game && exit::game_exited(&err) => {
+                    game = wait_for_game(|| setup(&args, level.as_deref()));
+                    continue;
+                }
+                Err(err) if replay && exit::game_exited(&err) => Ok(ExitCode::SUCCESS),
+                result => result.map(|()| ExitCode::SUCCESS),
+            };
+        };
+        // Let the outputs (and the TUI) see that we're done, and wait for them to write the last
+        // messages (like why we detached) before exiting.
+        outputs.close();
+        result
+    };
+
+    if let Some(tui_receiver) = tui_receiver {
+        let poller = std::thread::spawn(run);
+        let rooms = game::RoomNames::load(config::dir().ok().as_deref())?;
+        let bind = bind.expect("--tui can't be used with --inetd");
+        tui::run(pid, bind, &tui_receiver, &log, &rooms)?;
+        if let Err(TryRecvError::Disconnected) = tui_receiver.try_recv() {
+            return poller.join().expect("poll thread panicked");
+        }
+        Ok(ExitCode::SUCCESS)
+    } else {
+        run()
+    }
+}
+
+/// Start the WebSocket server on `server`, with the race, ghost, goal and randomizer feeds if they
+/// were asked for, as an output. The watchdog starts it again on the same address if it gets
+/// stuck (which can't be done for a client on stdin and stdout); the returned status is always the
+/// latest server's.
+fn spawn_server(
+    args: &Args,
+    server: server::Listener,
+    outputs: &watchdog::Outputs,
+) -> Result<Arc<Mutex<server::Status>>> {
+    let bind = match &server {
+        server::Listener::Tcp(listener) => Some(listener.local_addr()?),
+        server::Listener::Stdio => None,
+    };
+    let server = Mutex::new(Some(server));
+    let status = Arc::new(Mutex::new(server::Status::default()));
+    let race = args.race.clone();
+    let ghost = args.ghost.clone();
+    let goals = args.goals.clone();
+    let randomizer = args.randomizer;
+    let options = server::Options {
+        split_on_teleport: args.split_on_teleport,
+        compress: args.ws_compression,
+        compensate_latency: args.compensate_latency,
+        split_count: split_count(args)?,
+        replay_window: Duration::from_secs(args.replay_window),
+    };
+    outputs.start("server", {
+        let status = Arc::clone(&status);
+        move || {
+            let server = match (server.lock().unwrap().take(), bind) {
+                (Some(server), _) => server,
+                (None, Some(bind)) => {
+                    server::Listener::Tcp(TcpListener::bind(bind).map_err(|source| {
+                        exit::Failure::Bind {
+                            what: "WebSocket",
+                            address: bind,
+                            source,
+                        }
+                    })?)
+                }
+                (None, None) => bail!("the client on stdin and stdout can't be served again"),
+            };
+            let (sender, receiver) = crossbeam_channel::bounded::<Message>(10);
+            let mut senders = vec![sender];
+            let race = race.clone().map(|peer| {
+                let (race_sender, race_receiver) = crossbeam_channel::bounded::<Message>(10);
+                senders.push(race_sender);
+                race::spawn(peer, race_receiver)
+            });
+            let ghost = match &ghost {
+                Some(path) => {
+                    let ghost = ghost::Ghost::load(path)?;
+                    let (ghost_sender, ghost_receiver) = crossbeam_channel::bounded::<Message>(10);
+                    senders.push(ghost_sender);
+                    let rooms = game::RoomNames::load(config::dir().ok().as_deref())?;
+                    Some(ghost::spawn(ghost, rooms, ghost_receiver))
+                }
+                None => None,
+            };
+            let goals = match &goals {
+                Some(path) => {
+                    let goals = goals::Goals::load(path)?;
+                    let (goals_sender, goals_receiver) = crossbeam_channel::bounded::<Message>(10);
+                    senders.push(goals_sender);
+                    Some(goals::spawn(goals, goals_receiver))
+                }
+                None => None,
+            };
+            let randomizer = randomizer.then(|| {
+                let (randomizer_sender, randomizer_receiver) =
+                    crossbeam_channel::bounded::<Message>(10);
+                senders.push(randomizer_sender);
+                randomizer::spawn(randomizer_receiver)
+            });
+            let feeds = server::Feeds {
+                race,
+                ghost,
+                goals,
+                randomizer,
+            };
+            *status.lock().unwrap() = server::spawn(server, receiver, feeds, options);
+            Ok(senders)
+        }
+    })?;
+    Ok(status)
+}
+
+/// Start the control API, if it was asked for, returning the channel it sends requests on (which
+/// the watchdog also uses) and where the poll loop receives them.
+fn listen_rpc(args: &Args) -> Result<(Sender<rpc::Request>, Receiver<rpc::Request>)> {
+    let (sender, receiver) = crossbeam_channel::bounded(10);
+    if let Some(addr) = args.rpc {
+        let listener = TcpListener::bind(addr).map_err(|source| exit::Failure::Bind {
+            what: "RPC",
+            address: addr,
+            source,
+        })?;
+        log::info!("control API listening on {}", addr);
+        rpc::spawn(listener, sender.clone());
+    }
+    Ok((sender, receiver))
+}
+
+/// Fill in the outputs from the environment and `sinks.toml` that weren't given on the command
+/// line.
+fn load_sinks(args: &mut Args) -> Result<()> {
+    let env = config::Sinks::from_env()?;
+    let sinks = config::Sinks::load()?;
+    args.bind = args.bind.or(env.bind).or(sinks.bind);
+    args.livesplit_server = args
+        .livesplit_server
+        .take()
+        .or(env.livesplit_server)
+        .or(sinks.livesplit_server);
+    args.http = args.http.or(env.http).or(sinks.http);
+    args.dashboard = args.dashboard.or(env.dashboard).or(sinks.dashboard);
+    args.stdout |= env.stdout || sinks.stdout;
+    if args.stdout && args.tui {
+        bail!("--stdout and --tui can't be used together");
+    }
+    if args.inetd && (args.stdout || args.tui) {
+        bail!("--inetd serves over stdout, so it can't be used with --stdout or --tui");
+    }
+    Ok(())
+}
+
+/// Start the optional outputs given on the command line, adding each to `outputs`. The dashboard
+/// sends its controls to `controls`.
+fn spawn_sinks(
+    args: &Args,
+    outputs: &watchdog::Outputs,
+    controls: &Sender<rpc::Request>,
+) -> Result<()> {
+    if let Some(path) = &args.history {
+        let (path, practice) = (path.clone(), args.practice);
+        outputs.start_sink("history", move || {
+            let (history_sender, history_receiver) = crossbeam_channel::bounded::<Message>(10);
+            let thread = history::spawn(&path, practice, history_receiver)?;
+            Ok((history_sender, thread))
+        })?;
+    }
+    if let Some(path) = &args.verification_log {
+        let (path, frame_rate) = (path.clone(), args.frame_rate);
+        outputs.start_sink("verification-log", move || {
+            let (verification_sender, verification_receiver) =
+                crossbeam_channel::bounded::<Message>(10);
+            let thread = verification::spawn(&path, frame_rate, verification_receiver)?;
+            Ok((verification_sender, thread))
+        })?;
+    }
+    if let Some(path) = &args.markers {
+        let (path, fps, split_on_teleport) =
+            (path.clone(), args.marker_fps, args.split_on_teleport);
+        outputs.start_sink("markers", move || {
+            let (markers_sender, markers_receiver) = crossbeam_channel::bounded::<Message>(10);
+            let thread = markers::spawn(&path, fps, split_on_teleport, markers_receiver)?;
+            Ok((markers_sender, thread))
+        })?;
+    }
+    if let Some(path) = &args.trace_states {
+        let path = path.clone();
+        outputs.start_sink("trace", move || {
+            let (trace_sender, trace_receiver) = crossbeam_channel::bounded::<Message>(10);
+            let thread = trace::spawn(&path, trace_receiver)?;
+            Ok((trace_sender, thread))
+        })?;
+    }
+    if let Some(dir) = &args.route_dir {
+        let (dir, format, heatmap) = (dir.clone(), args.route_format, args.route_heatmap);
+        outputs.start_sink("route", move || {
+            let (route_sender, route_receiver) = crossbeam_channel::bounded::<Message>(10);
+            let rooms = game::RoomNames::load(config::dir().ok().as_deref())?;
+            let thread = route::spawn(&dir, format, heatmap, rooms, route_receiver)?;
+            Ok((route_sender, thread))
+        })?;
+    }
+    spawn_state(args, outputs, controls)?;
+    if args.stdout {
+        outputs.start_sink("stdout", || {
+            let (stdout_sender, stdout_receiver) = crossbeam_channel::bounded::<Message>(10);
+            Ok((stdout_sender, stdout::spawn(stdout_receiver)))
+        })?;
+    }
+    if let Some(credentials) = &args.twitch_credentials {
+        let credentials = credentials.clone();
+        outputs.start_sink("twitch", move || {
+            let (twitch_sender, twitch_receiver) = crossbeam_channel::bounded::<Message>(10);
+            let thread = twitch::spawn(&credentials, twitch_receiver)?;
+            Ok((twitch_sender, thread))
+        })?;
+    }
+    if let Some(room) = &args.racetime {
+        let credentials = args
+            .racetime_credentials
+            .clone()
+            .context("--racetime needs --racetime-credentials")?;
+        let room = room.clone();
+        outputs.start_sink("racetime", move || {
+            let (racetime_sender, racetime_receiver) = crossbeam_channel::bounded::<Message>(10);
+            let thread = racetime::spawn(&room, &credentials, racetime_receiver)?;
+            Ok((racetime_sender, thread))
+        })?;
+    }
+    Ok(())
+}
+
+/// Start the outputs that follow the run state: the LiveSplit Server component, the HTTP status
+/// server, and the dashboard, which sends its controls to `controls`.
+fn spawn_state(
+    args: &Args,
+    outputs: &watchdog::Outputs,
+    controls: &Sender<rpc::Request>,
+) -> Result<()> {
+    if args.livesplit_server.is_none() && args.http.is_none() && args.dashboard.is_none() {
+        return Ok(());
+    }
+    let (state_sender, state_receiver) = crossbeam_channel::bounded::<Message>(10);
+    outputs.add("state", state_sender);
+    let state = state::spawn(args.split_on_teleport, state_receiver);
+    if let Some(address) = &args.livesplit_server {
+        let (address, state) = (address.clone(), state.clone());
+        let split_on_teleport = args.split_on_teleport;
+        outputs.start("livesplit", move || {
+            let (livesplit_sender, livesplit_receiver) = crossbeam_channel::bounded::<Message>(10);
+            livesplit::spawn(
+                address.clone(),
+                state.clone(),
+                split_on_teleport,
+                livesplit_receiver,
+            );
+            Ok(vec![livesplit_sender])
+        })?;
+    }
+    if let Some(addr) = args.http {
+        let listener = TcpListener::bind(addr).map_err(|source| exit::Failure::Bind {
+            what: "HTTP",
+            address: addr,
+            source,
+        })?;
+        log::info!("serving the run state on http://{}", listener.local_addr()?);
+        http::spawn(listener, state.clone(), None);
+    }
+    if let Some(addr) = args.dashboard {
+        let listener = TcpListener::bind(addr).map_err(|source| exit::Failure::Bind {
+            what: "dashboard",
+            address: addr,
+            source,
+        })?;
+        log::info!("serving the dashboard on http://{}", listener.local_addr()?);
+        let controls = controls.clone();
+        let dashboard = http::Dashboard {
+            history: args.history.clone(),
+            control: Box::new(move |call| rpc::respond(call, &controls)),
+        };
+        http::spawn(listener, state, Some(dashboard));
+    }
+    Ok(())
+}
+
+/// The URL to give out for the server bound to `bind`: its LAN address if there is one (printed
+/// as a QR code if `qr`), or `bind` itself.
+fn server_url(bind: SocketAddr, qr: bool) -> String {
+    match lan::url(bind) {
+        Ok(Some(url)) => {
+            log::info!("other devices can connect to {}", url);
+            if qr {
+                println!("{}", qr::render(&url));
+            }
+            url
+        }
+        Ok(None) => format!("ws://{}", bind),
+        Err(err) => {
+            log::warn!("{:#}", err);
+            format!("ws://{}", bind)
+        }
+    }
+}
+
+/// How many splits a run will make with the options given, if that's known.
+fn split_count(args: &Args) -> Result<Option<usize>> {
+    Ok(if let Some(count) = args.split_count {
+        Some(count)
+    } else if let Some(level) = &args.level {
+        Some(game::Profile::load(&config::dir()?, level)?.splits.len())
+    } else if args.split_on_teleport {
+        None
+    } else {
+        Some(game::BUILTIN_SPLIT_COUNT)
+    })
+}
+
+/// The WebSocket server's address.
+fn bind_address(args: &Args) -> SocketAddr {
+    args.bind.unwrap_or_else(|| {
+        if args.steam_deck {
+            ([0, 0, 0, 0], 5555).into()
+        } else {
+            ([127, 0, 0, 1], 5555).into()
+        }
+    })
+}
+
+/// Where WebSocket clients come from: one over stdin and stdout with `--inetd`, or else the socket
+/// systemd passed us, or else the one we bind. Returns the address it's listening on, if any.
+fn listen(args: &Args) -> Result<(server::Listener, Option<SocketAddr>)> {
+    if args.inetd {
+        log::info!("serving a WebSocket client on stdin and stdout");
+        return Ok((server::Listener::Stdio, None));
+    }
+    let listener = match activation::listener()? {
+        Some(listener) => {
+            log::info!("using the socket systemd passed");
+            listener
+        }
+        None => bind_server(args)?,
+    };
+    let bind = listener.local_addr()?;
+    log::info!("listening on ws://{}", bind);
+    Ok((server::Listener::Tcp(listener), Some(bind)))
+}
+
+/// How many ports after the requested one `--port-fallback` tries before any free port.
+const PORT_FALLBACKS: u16 = 10;
+
+/// Bind the WebSocket server, falling back to later ports and then any free port if
+/// `--port-fallback` is given and the requested one is taken.
+fn bind_server(args: &Args) -> Result<TcpListener> {
+    let bind = bind_address(args);
+    let mut err = match TcpListener::bind(bind) {
+        Ok(listener) => return Ok(listener),
+        Err(err) => err,
+    };
+    if args.port_fallback && bind.port() != 0 && err.kind() == ErrorKind::AddrInUse {
+        let ports = (1..=PORT_FALLBACKS)
+            .filter_map(|n| bind.port().checked_add(n))
+            .chain([0]);
+        for port in ports {
+            match TcpListener::bind(SocketAddr::new(bind.ip(), port)) {
+                Ok(listener) => {
+                    log::warn!(
+                        "{} is in use; listening on port {} instead",
+                        bind,
+                        listener.local_addr()?.port()
+                    );
+                    return Ok(listener);
+                }
+                Err(next) if next.kind() == ErrorKind::AddrInUse => {}
+                Err(next) => {
+                    err = next;
+                    break;
+                }
+            }
+        }
+    }
+    Err(exit::Failure::Bind {
+        what: "WebSocket",
+        address: bind,
+        source: err,
+    }
+    .into())
+}
+
+/// Run a subcommand instead of attaching to the game.
+fn subcommand(args: &Args, command: &Subcommand) -> Result<()> {
+    match command {
+        Subcommand::Agent(Agent { stdio: true, .. }) => agent_stdio(args),
+        Subcommand::Agent(agent) => self::agent(
+            args,
+            agent
+                .listen
+                .unwrap_or(([127, 0, 0, 1], game::remote::DEFAULT_PORT).into()),
+        ),
+        Subcommand::Categories(_) => categories::list(),
+        Subcommand::Doctor(_) => {
+            let pid = uses_process(args).then(|| find_pid(args));
+            let mut addresses = vec![("WebSocket server", bind_address(args))];
+            if let Some(rpc) = args.rpc {
+                addresses.push(("control API", rpc));
+            }
+            doctor::run(pid, || attach(args), &addresses)
+        }
+        Subcommand::Profiles(Profiles {
+            command: ProfilesCommand::Update(update),
+        }) => profiles::update(
+            update.source.as_deref().unwrap_or(profiles::DEFAULT_SOURCE),
+            &config::dir()?,
+        ),
+        Subcommand::Stats(stats) => {
+            print!("{}", stats::report(&stats.file)?.render(stats.format)?);
+            Ok(())
+        }
+        Subcommand::Verify(verify) => self::verify(&verify.file),
+        Subcommand::InstallHelper(_) => helper::install(),
+        Subcommand::Launch(_) | Subcommand::Serve(_) | Subcommand::Wrap(_) => {
+            unreachable!("these run the splitter")
+        }
+    }
+}
+
+/// Print what a verification log says about its runs.
+fn verify(path: &Path) -> Result<()> {
+    let report = verification::verify(path)?;
+    println!("hash chain intact ({} entries)", report.entries);
+    for (run_id, time) in &report.completed {
+        println!(
+            "run {} completed in {}",
+            run_id,
+            vitellary_core::format_time(*time)
+        );
+    }
+    if report.completed.is_empty() {
+        println!("no completed runs");
+    }
+    for warning in &report.warnings {
+        println!("warning: {}", warning);
+    }
+    Ok(())
+}
+
+/// The record to log and send after an update whose `event` completes a run, a time trial, or a
+/// Super Gravitron attempt.
+fn completion(game: &Game, event: Event) -> Option<Message> {
+    match event {
+        Event::GameComplete => {
+            let summary = game.summary();
+            log_summary(&summary);
+            Some(Message::Summary(summary))
+        }
+        Event::TimeTrialComplete => {
+            let trial = game.time_trial()?.clone();
+            log::info!(
+                "time trial{} complete in {}: {} deaths{}{}",
+                trial
+                    .level
+                    .map_or_else(String::new, |level| format!(" {}", level)),
+                vitellary_core::format_time(trial.time),
+                trial.deaths,
+                trial.trinkets.map_or_else(String::new, |trinkets| format!(
+                    ", {}/{} trinkets",
+                    trinkets,
+                    trial.trinket_target.unwrap_or_default()
+                )),
+                trial
+                    .rank
+                    .map_or_else(String::new, |rank| format!(", rank {:?}", rank))
+            );
+            Some(Message::TimeTrial(trial))
+        }
+        Event::GravitronAttempt => {
+            let attempt = game.gravitron()?.clone();
+            log::info!(
+                "super gravitron: survived {}{}",
+                vitellary_core::format_time(attempt.survived),
+                if attempt.personal_best == Some(true) {
+                    " (new personal best!)"
+                } else {
+                    ""
+                }
+            );
+            Some(Message::Gravitron(attempt))
+        }
+        _ => None,
+    }
+}
+
+/// Log the summary of a completed run.
+fn log_summary(summary: &game::Summary) {
+    log::info!(
+        "{}run {} complete in {}: {} flips, {} deaths{}",
+        summary
+            .category
+            .map_or_else(String::new, |category| format!("{} ", category)),
+        summary.run_id,
+        vitellary_core::format_time(summary.time),
+        summary.flips,
+        summary.deaths,
+        if summary.final_level_deathless == Some(true) {
+            " (final level deathless)"
+        } else {
+            ""
+        }
+    );
+    if summary.invincibility {
+        log::warn!(
+            "run {} was played with invincibility on; it isn't eligible for the leaderboards",
+            summary.run_id
+        );
+    }
+    if let Some(slowdown) = summary.slowdown {
+        log::warn!(
+            "run {} was played at {}/{} speed at its slowest; its time isn't comparable with \
+             full-speed runs",
+            summary.run_id,
+            slowdown,
+            game::FULL_SPEED
+        );
+    }
+}
+
+/// Attach to the game and apply the splitting options given on the command line, splitting with
+/// the profile for `level` if given.
+fn setup(args: &Args, level: Option<&str>) -> Result<Game> {
+    let mut game = attach(args)?;
+    check_version(args, game.target())?;
+    game.set_room_names(game::RoomNames::load(config::dir().ok().as_deref())?);
+    game.set_quirks(&game::Quirks::load(config::dir().ok().as_deref())?);
+    if let Some(gamestates) = &args.playing_states {
+        game.set_gamestates(gamestates.clone());
+    }
+    if let Some(fields) = &args.fields {
+        game.set_fields(fields.clone());
+    }
+    if let Some(category) = args.category {
+        game.set_category(category);
+    }
+    game.set_frame_rate(args.frame_rate);
+    game.set_interpolation(args.interpolate);
+    if let Some(segment) = args.practice {
+        game.set_practice(segment);
+    }
+    game.set_practice_fork(args.practice_fork);
+    if let Some(grace) = args.reset_grace {
+        game.set_reset_grace(Duration::try_from_secs_f64(grace).context("invalid --reset-grace")?);
+    }
+    if let Some(window) = args.crash_resume {
+        game.set_crash_resume(
+            Duration::try_from_secs_f64(window).context("invalid --crash-resume")?,
+        );
+    }
+    for cooldown in &args.cooldown {
+        game.debounce(cooldown.event).cooldown = Some(cooldown.duration);
+    }
+    for event in &args.repeat {
+        game.debounce(*event).repeat = true;
+    }
+    game.set_strict_profiles(args.strict_profiles);
+    if let Some(level) = level {
+        game.set_profile(Some(game::Profile::load(&config::dir()?, level)?))?;
+    } else if let Ok(dir) = config::dir() {
+        game.find_profiles(dir);
+    }
+    for category in &args.track_category {
+        let profile = game::Profile::load(&config::dir()?, category)?;
+        game.track_category(category.clone(), profile)?;
+    }
+    Ok(game)
+}
+
+/// Check the game's version against `--expected-version`, if given.
+fn check_version(args: &Args, target: &game::Target) -> Result<()> {
+    let Some(expected) = args.expected_version else {
+        return Ok(());
+    };
+    // An agent reports the target of the game it reads, but other backends without a process
+    // have none.
+    if !uses_process(args)
+        && !matches!(
+            args.backend,
+            Some(BackendKind::Remote(_) | BackendKind::Ssh(_))
+        )
+    {
+        return Ok(());
+    }
+    let problem = match target.version {
+        Some(version) if version == expected => return Ok(()),
+        Some(version) => format!("the game is VVVVVV {}, not {}", version, expected),
+        None => format!(
+            "could not detect the game's version to check it's {}",
+            expected
+        ),
+    };
+    if args.warn_version_mismatch {
+        log::warn!("WRONG GAME VERSION: {}", problem);
+        Ok(())
+    } else {
+        bail!("{} (see --expected-version)", problem)
+    }
+}
+
+/// Whether the backend given on the command line reads a game process.
+fn uses_process(args: &Args) -> bool {
+    !matches!(
+        args.backend,
+        Some(
+            BackendKind::Dump(_)
+                | BackendKind::Mock
+                | BackendKind::Replay(_)
+                | BackendKind::Remote(_)
+                | BackendKind::Ssh(_)
+        )
+    )
+}
+
+/// Find the game process given on the command line.
+fn find_pid(args: &Args) -> Result<Pid> {
+    if let Some(pid) = args.pid {
+        process::translate_namespace_pid(pid)
+    } else if let Some(title) = &args.window {
+        process::translate_namespace_pid(process::find_by_window(title)?)
+    } else {
+        // Under pressure-vessel the game may be in its own PID namespace, but pgrep on the host
+        // still sees it by its host PID, which is the one we need.
+        process::find_newest()
+    }
+}
+
+/// Find the game with the backend given on the command line.
+fn attach(args: &Args) -> Result<Game> {
+    let find_pid = || find_pid(args);
+    let fingerprints = || game::Fingerprints::load(config::dir().ok().as_deref());
+
+    match args.backend.clone().unwrap_or(BackendKind::Native) {
+        BackendKind::Native => {
+            let pid = find_pid()?;
+            #[cfg(target_os = "linux")]
+            if args.steam_deck && process::is_wine(pid) {
+                log::info!("pid {} is running under Proton", pid);
+                return Game::attach_proton(pid, &fingerprints()?);
+            }
+            match Game::attach(pid, args.scan_range.clone(), &fingerprints()?) {
+                Err(err) if helper::should_use(&err) => {
+                    log::info!("{:#}; reading it through {}", err, helper::INSTALLED);
+                    Game::attach_helper(pid, helper::command(), &fingerprints()?)
+                }
+                result => result,
+            }
+        }
+        #[cfg(target_os = "linux")]
+        BackendKind::Proton => Game::attach_proton(find_pid()?, &fingerprints()?),
+        BackendKind::Dump(path) => Ok(Game::from_dump(path)),
+        BackendKind::Mock => Ok(Game::with_backend(0, Box::new(game::Mock::new()))),
+        BackendKind::Replay(path) => Ok(Game::with_backend(
+            0,
+            Box::new(verification::Replay::open(
+                &path,
+                args.replay_speed,
+                args.replay_from,
+            )?),
+        )),
+        BackendKind::Remote(addr) => Game::attach_remote(&addr),
+        BackendKind::Ssh(host) => Game::attach_remote_command(ssh_agent(&host)),
+    }
+}
+
+/// The command that runs an agent on `host` over SSH, answering on its stdin and stdout.
+fn ssh_agent(host: &str) -> std::process::Command {
+    let mut command = std::process::Command::new("ssh");
+    // Without a terminal, so the connection passes the agent's lines through untouched.
+    command.args(["-T", "--", host, "vitellary", "agent", "--stdio"]);
+    command
+}
+
+/// How long `--wait-for-game` and the agent wait between attempts to attach to the game.
+const AGENT_RETRY: Duration = Duration::from_secs(2);
+
+/// Attach to the game with `setup`, waiting for it to launch (and, since it may not have set
+/// itself up yet, retrying any other failure) for as long as it takes.
+fn wait_for_game(setup: impl Fn() -> Result<Game>) -> Game {
+    log::info!("waiting for VVVVVV to launch");
+    loop {
+        match setup() {
+            Ok(game) => return game,
+            Err(err) => {
+                if err.downcast_ref::<exit::Failure>().is_none() {
+                    log::warn!("{:#}; retrying", err);
+                }
+                std::thread::sleep(AGENT_RETRY);
+            }
+        }
+    }
+}
+
+/// Serve reads of the game to a splitter on another machine (see `vitellary_core::game::remote`)
+/// on `listen`, attaching to the game again whenever it exits.
+fn agent(args: &Args, listen: SocketAddr) -> Result<()> {
+    let listener = TcpListener::bind(listen).map_err(|source| exit::Failure::Bind {
+        what: "agent",
+        address: listen,
+        source,
+    })?;
+    log::info!("agent listening on {}", listen);
+    loop {
+        let game = wait_for_game(|| attach(args));
+        for stream in listener.incoming() {
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(err) => {
+                    log::warn!("failed to accept a splitter: {}", err);
+                    continue;
+                }
+            };
+            let peer = stream.peer_addr()?;
+            log::info!("splitter connected from {}", peer);
+            match game::remote::serve(&game, stream) {
+                Ok(()) => log::info!("splitter at {} disconnected", peer),
+                Err(err) if exit::game_exited(&err) => {
+                    log::info!("the game exited");
+                    break;
+                }
+                Err(err) => log::warn!("splitter at {}: {:#}", peer, err),
+            }
+        }
+    }
+}
+
+/// Serve reads of the game to the splitter that ran us (see `vitellary_core::game::remote`) on
+/// stdin and stdout, once the game is running, until either goes away. The splitter runs us again
+/// to wait for the game's next launch.
+fn agent_stdio(args: &Args) -> Result<()> {
+    let game = wait_for_game(|| attach(args));
+    log::info!("answering the splitter on stdin and stdout");
+    match game::remote::serve_stdio(&game) {
+        Ok(()) => log::info!("the splitter disconnected"),
+        Err(err) if exit::game_exited(&err) => log::info!("the game exited"),
+        Err(err) => return Err(err),
+    }
+    Ok(())
+}
+
+/// Attach to the game again with `reattach` while the run `crashed` left can still be resumed,
+/// carrying the run over.
+fn restart(crashed: &Game, reattach: impl Fn() -> Result<Game>) -> Option<Game> {
+    while crashed.awaiting_restart() {
+        std::thread::sleep(Duration::from_secs(1));
+        if let Ok(mut game) = reattach() {
+            log::info!("the game restarted; load a save to resume the run");
+            game.resume_run(crashed);
+            return Some(game);
+        }
+    }
+    log::warn!("the game didn't restart in time");
+    None
+}
+
+/// Poll the game until it can no longer be read, sending everything that happens to `outputs` and
+/// carrying out requests from the control API. `server` is the URL clients should connect to, for
+/// state queries; `reattach` attaches to the game again, splitting with the profile for the given
+/// level.
+fn poll(
+    mut game: Game,
+    mut level: Option<String>,
+    commands: &Receiver<Command>,
+    controls: &Receiver<rpc::Request>,
+    outputs: &watchdog::Outputs,
+    server: &str,
+    reattach: impl Fn(Option<&str>) -> Result<Game>,
+) -> Result<()> {
+    let broadcast = |message: Message, paced: bool| outputs.broadcast(&message, paced);
+    let attached = |game: &Game| Message::Attached {
+        pid: game.pid(),
+        address: game.address(),
+        target: game.target().clone(),
+    };
+    let detached = |game: &Game, err: &anyhow::Error| Message::Detached {
+        pid: game.pid(),
+        reason: format!("{:#}", err),
+    };
+    broadcast(attached(&game), game.paced());
+    let mut paused = false;
+    let mut last = None;
+    let err = loop {
+        outputs.beat();
+        for command in commands.try_iter() {
+            game.queue(command);
+        }
+        for request in controls.try_iter() {
+            let result = match request.control {
+                Control::Pause(pause) => {
+                    paused = pause;
+                    log::info!("output {}", if paused { "paused" } else { "resumed" });
+                    Ok(Value::Null)
+                }
+                Control::Command(command) => {
+                    game.queue(command);
+                    Ok(Value::Null)
+                }
+                Control::Reattach => reattach(level.as_deref()).map(|new| {
+                    game = new;
+                    broadcast(attached(&game), game.paced());
+                    json!({ "pid": game.pid() })
+                }),
+                Control::SwitchProfile(new) => new
+                    .as_deref()
+                    .map(|level| game::Profile::load(&config::dir()?, level))
+                    .transpose()
+                    .and_then(|profile| game.set_profile(profile))
+                    .map(|()| {
+                        log::info!("splitting with the profile for {:?}", new);
+                        level = new;
+                        Value::Null
+                    }),
+                Control::Query => Ok(json!({
+                    "pid": game.pid(),
+                    "address": game.address(),
+                    "paused": paused,
+                    "level": level.as_deref().or(game.level()),
+                    "server": server,
+                    "update": last,
+                })),
+            };
+            request
+                .reply
+                .send(result.map_err(|err| format!("{:#}", err)))
+                .ok();
+        }
+        match game.update() {
+            Ok(Some(update)) => {
+                let record = update.event.and_then(|event| completion(&game, event));
+                last = Some(update.clone());
+                if !paused {
+                    broadcast(Message::Update(update), game.paced());
+                    if let Some(record) = record {
+                        broadcast(record, game.paced());
+                    }
+                }
+            }
+            Ok(None) => {}
+            Err(err) => {
+                let Some(update) = exit::game_exited(&err).then(|| game.crashed()).flatten() else {
+                    break err;
+                };
+                log::warn!("{:#}; waiting for the game to restart", err);
+                broadcast(Message::Update(update), game.paced());
+                broadcast(detached(&game, &err), game.paced());
+                outputs.idle();
+                game = restart(&game, || reattach(level.as_deref())).ok_or(err)?;
+                broadcast(attached(&game), game.paced());
+            }
+        }
+        if game.paced() {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+    };
+    outputs.idle();
+    log::info!("detached from pid {}: {:#}", game.pid(), err);
+    broadcast(detached(&game, &err), game.paced());
+    Err(err)
+}
@@ -0,0 +1,145 @@
+use crate::exit::Failure;
+use anyhow::{bail, Context, Result};
+use read_process_memory::Pid;
+use std::io::BufRead;
+use std::process::{Command, Output};
+
+/// Find the most recently started VVVVVV process.
+pub(crate) fn find_newest() -> Result<Pid> {
+    let output = Command::new("pgrep")
+        .args(["-n", "VVVVVV"])
+        .output()
+        .context("failed to run pgrep")?;
+    if output.status.success() {
+        first_pid(&output)
+    } else if output.status.code() == Some(1) {
+        Err(Failure::NoProcess.into())
+    } else {
+        bail!("pgrep failed with {}", output.status);
+    }
+}
+
+/// Find the process owning a window: the currently focused window if `title` is "active",
+/// otherwise the first window with exactly that title.
+///
+/// On Linux this uses `xdotool`, so it only sees X11 windows (which includes VVVVVV running under
+/// XWayland). On macOS this asks System Events, which requires accessibility permissions for the
+/// terminal.
+pub(crate) fn find_by_window(title: &str) -> Result<Pid> {
+    let output = if cfg!(target_os = "macos") {
+        let script = if title == "active" {
+            "tell application \"System Events\" to get unix id of first process whose frontmost \
+             is true"
+                .to_owned()
+        } else {
+            format!(
+                "tell application \"System Events\" to get unix id of first process whose \
+                 (name of windows) contains {:?}",
+                title
+            )
+        };
+        Command::new("osascript")
+            .args(["-e", &script])
+            .output()
+            .context("failed to run osascript")?
+    } else {
+        let mut command = Command::new("xdotool");
+        if title == "active" {
+            command.arg("getactivewindow");
+        } else {
+            command.args(["search", "--limit", "1", "--name", &format!("^{}$", title)]);
+        }
+        command
+            .arg("getwindowpid")
+            .output()
+            .context("failed to run xdotool")?
+    };
+    if !output.status.success() || output.stdout.is_empty() {
+        return Err(Failure::NoWindow {
+            title: title.to_owned(),
+            detail: String::from_utf8_lossy(&output.stderr).trim().to_owned(),
+        }
+        .into());
+    }
+    first_pid(&output)
+}
+
+/// Translate a PID as seen inside a container (e.g. Flatpak or Docker) into the PID in our own
+/// namespace, which is what `process_vm_readv` needs.
+///
+/// If `pid` is already a VVVVVV process in our namespace it is returned as-is. Otherwise we look
+/// for a VVVVVV process whose `NSpid` line in `/proc/*/status` ends in `pid`.
+#[cfg(target_os = "linux")]
+pub(crate) fn translate_namespace_pid(pid: Pid) -> Result<Pid> {
+    fn is_vvvvvv(status: &str) -> bool {
+        status.lines().any(|line| {
+            line.strip_prefix("Name:")
+                .is_some_and(|name| name.trim().to_lowercase().contains("vvvvvv"))
+        })
+    }
+
+    if let Ok(status) = std::fs::read_to_string(format!("/proc/{}/status", pid)) {
+        if is_vvvvvv(&status) {
+            return Ok(pid);
+        }
+    }
+
+    let mut found = Vec::new();
+    for entry in std::fs::read_dir("/proc")? {
+        let Ok(host_pid) = entry?.file_name().to_string_lossy().parse::<Pid>() else {
+            continue;
+        };
+        let Ok(status) = std::fs::read_to_string(format!("/proc/{}/status", host_pid)) else {
+            continue;
+        };
+        let nspid = status
+            .lines()
+            .find_map(|line| line.strip_prefix("NSpid:"))
+            .map(|pids| pids.split_whitespace().collect::<Vec<_>>());
+        if let Some(nspid) = nspid {
+            if nspid.len() > 1
+                && nspid.last() == Some(&pid.to_string().as_str())
+                && is_vvvvvv(&status)
+            {
+                found.push(host_pid);
+            }
+        }
+    }
+    match found[..] {
+        [host_pid] => {
+            log::info!("translated namespaced pid {} to {}", pid, host_pid);
+            Ok(host_pid)
+        }
+        [] => Ok(pid),
+        _ => bail!(
+            "pid {} matches several namespaced VVVVVV processes ({:?}); pass one of those instead",
+            pid,
+            found
+        ),
+    }
+}
+
+/// Whether `pid` is a Windows executable running under Proton or Wine, going by the process name
+/// Wine gives it (`VVVVVV.exe`).
+#[cfg(target_os = "linux")]
+pub(crate) fn is_wine(pid: Pid) -> bool {
+    std::fs::read_to_string(format!("/proc/{}/comm", pid))
+        .is_ok_and(|comm| comm.trim_end().to_lowercase().ends_with(".exe"))
+}
+
+#[cfg(not(target_os = "linux"))]
+#[allow(clippy::unnecessary_wraps)]
+pub(crate) fn translate_namespace_pid(pid: Pid) -> Result<Pid> {
+    Ok(pid)
+}
+
+fn first_pid(output: &Output) -> Result<Pid> {
+    Ok(output
+        .stdout
+        .lines()
+        .next()
+        .expect("command returned 0 with no output")
+        .expect("command output invalid UTF-8")
+        .trim()
+        .parse()?)
+}
@@ -0,0 +1,95 @@
+use anyhow::Result;
+use std::path::Path;
+
+/// Where `profiles update` fetches `index.toml` and the profiles it lists from by default.
+pub(crate) const DEFAULT_SOURCE: &str =
+    "https://raw.githubusercontent.com/iliana/vitellary/main/profiles";
+
+/// Download the split profiles listed in `source`'s index into `dir`, checking each one's hash
+/// before writing it.
+#[cfg(feature = "profiles")]
+pub(crate) fn update(source: &str, dir: &Path) -> Result<()> {
+    use anyhow::{bail, Context};
+    use serde::Deserialize;
+    use sha2::{Digest, Sha256};
+    use std::io::Read;
+    use std::path::Component;
+
+    #[derive(Deserialize)]
+    struct Index {
+        profiles: Vec<Entry>,
+    }
+
+    #[derive(Deserialize)]
+    struct Entry {
+        path: String,
+        sha256: String,
+    }
+
+    fn get(url: &str) -> Result<Vec<u8>> {
+        let mut data = Vec::new();
+        ureq::get(url)
+            .call()
+            .with_context(|| format!("failed to fetch {}", url))?
+            .into_reader()
+            .read_to_end(&mut data)
+            .with_context(|| format!("failed to fetch {}", url))?;
+        Ok(data)
+    }
+
+    let source = source.trim_end_matches('/');
+    let index: Index = toml::from_str(
+        std::str::from_utf8(&get(&format!("{}/index.toml", source))?)
+            .context("index.toml is not UTF-8")?,
+    )
+    .context("failed to parse index.toml")?;
+
+    for entry in &index.profiles {
+        let path = Path::new(&entry.path);
+        if !path
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+        {
+            bail!(
+                "refusing to write profile outside the config directory: {}",
+                entry.path
+            );
+        }
+        let data = get(&format!("{}/{}", source, entry.path))?;
+        let hash = format!("{:x}", Sha256::digest(&data));
+        if !hash.eq_ignore_ascii_case(&entry.sha256) {
+            bail!(
+                "{} has SHA-256 {}, expected {}",
+                entry.path,
+                hash,
+                entry.sha256
+            );
+        }
+
+        let dest = dir.join(path);
+        if std::fs::read(&dest).is_ok_and(|existing| existing == data) {
+            log::debug!("{} is up to date", entry.path);
+            continue;
+        }
+        if let Some(parent) = dest.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("failed to create {}", parent.display()))?;
+        }
+        std::fs::write(&dest, &data)
+            .with_context(|| format!("failed to write {}", dest.display()))?;
+        log::info!("updated {}", entry.path);
+    }
+    log::info!(
+        "{} profiles up to date in {}",
+        index.profiles.len(),
+        dir.display()
+    );
+    Ok(())
+}
+
+#[cfg(not(feature = "profiles"))]
+pub(crate) fn update(_source: &str, _dir: &Path) -> Result<()> {
+    anyhow::bail!(
+        "this build of vitellary does not support fetching profiles (enable the `profiles` feature)"
+    )
+}
@@ -0,0 +1,16 @@
+use qrcode::render::unicode::Dense1x2;
+use qrcode::QrCode;
+
+/// Render `text` as a QR code out of Unicode half blocks, two modules per character.
+///
+/// The colors are inverted (dark modules are drawn as spaces) since most terminals have a dark
+/// background; phone cameras read it either way, but this keeps the quiet zone visible.
+pub(crate) fn render(text: &str) -> String {
+    QrCode::new(text)
+        .expect("URLs fit in a QR code")
+        .render::<Dense1x2>()
+        .dark_color(Dense1x2::Light)
+        .light_color(Dense1x2::Dark)
+        .quiet_zone(true)
+        .build()
+}
@@ -0,0 +1,140 @@
+use crate::verbosity;
+use anyhow::Result;
+use crossbeam_channel::Sender;
+use log::LevelFilter;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use vitellary_core::game::Command;
+
+/// Something an RPC client asked the poll loop to do.
+#[derive(Debug)]
+pub(crate) enum Control {
+    /// Stop (or resume) sending updates to outputs, while still following the game.
+    Pause(bool),
+    Command(Command),
+    /// Attach to the game again, e.g. after restarting it.
+    Reattach,
+    /// Split using this custom level's profile, or the campaign's splits if `None`.
+    SwitchProfile(Option<String>),
+    Query,
+}
+
+/// A control and where to send its result.
+pub(crate) struct Request {
+    pub(crate) control: Control,
+    pub(crate) reply: Sender<Result<Value, String>>,
+}
+
+#[derive(Deserialize)]
+struct Call {
+    id: Value,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+/// Accept JSON-RPC 2.0 clients on `listener`, one request per line, sending the requests to
+/// `sender` for the poll loop to carry out.
+///
+/// Methods: `pause`, `resume`, `split`, `skipsplit`, `undo`, `reset`, `reattach`,
+/// `switch_profile` (with a `level` parameter, or null for the campaign), `state`, and `verbosity`
+/// (with a `level` parameter like `debug` or `trace`, or null to cycle like SIGUSR1), which is
+/// answered here so it works even while the poll loop is busy.
+pub(crate) fn spawn(listener: TcpListener, sender: Sender<Request>) {
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let sender = sender.clone();
+            std::thread::spawn(move || -> Result<()> { serve(stream?, &sender) });
+        }
+    });
+}
+
+fn serve(stream: TcpStream, sender: &Sender<Request>) -> Result<()> {
+    let mut writer = stream.try_clone()?;
+    for line in BufReader::new(stream).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        writeln!(writer, "{}", respond(&line, sender))?;
+    }
+    Ok(())
+}
+
+/// Carry out one JSON-RPC call, sending it to the poll loop with `sender` unless it can be
+/// answered here, and return the response.
+pub(crate) fn respond(line: &str, sender: &Sender<Request>) -> Value {
+    let call = match serde_json::from_str::<Call>(line) {
+        Ok(call) => call,
+        Err(err) => {
+            return json!({
+                "jsonrpc": "2.0",
+                "id": null,
+                "error": { "code": -32700, "message": err.to_string() },
+            })
+        }
+    };
+    let result = if call.method == "verbosity" {
+        verbosity(&call)
+    } else {
+        control(&call).and_then(|control| {
+            let (reply, result) = crossbeam_channel::bounded(1);
+            sender
+                .send(Request { control, reply })
+                .ok()
+                .and_then(|()| result.recv().ok())
+                .ok_or((-32603, "the poll loop has stopped".to_owned()))?
+                .map_err(|message| (-32000, message))
+        })
+    };
+    match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": call.id, "result": result }),
+        Err((code, message)) => json!({
+            "jsonrpc": "2.0",
+            "id": call.id,
+            "error": { "code": code, "message": message },
+        }),
+    }
+}
+
+/// Change how much is logged, returning the level now shown on top of the log filter.
+fn verbosity(call: &Call) -> Result<Value, (i32, String)> {
+    let level = match call.params.get("level") {
+        None | Some(Value::Null) => None,
+        Some(Value::String(level)) => Some(
+            level
+                .parse::<LevelFilter>()
+                .map_err(|_| (-32602, format!("unknown log level {:?}", level)))?,
+        ),
+        Some(_) => return Err((-32602, "level must be a string or null".into())),
+    };
+    let level = verbosity::set(level);
+    log::info!(
+        "logging {}",
+        level.map_or_else(
+            || "as configured".to_owned(),
+            |level| format!("up to {}", level)
+        )
+    );
+    Ok(json!(level.map(|level| level.to_string().to_lowercase())))
+}
+
+fn control(call: &Call) -> Result<Control, (i32, String)> {
+    Ok(match call.method.as_str() {
+        "pause" => Control::Pause(true),
+        "resume" => Control::Pause(false),
+        "reattach" => Control::Reattach,
+        "state" => Control::Query,
+        "switch_profile" => match call.params.get("level") {
+            None | Some(Value::Null) => Control::SwitchProfile(None),
+            Some(Value::String(level)) => Control::SwitchProfile(Some(level.clone())),
+            Some(_) => return Err((-32602, "level must be a string or null".into())),
+        },
+        method => match method.parse() {
+            Ok(command) => Control::Command(command),
+            Err(_) => return Err((-32601, format!("unknown method {:?}", method))),
+        },
+    })
+}
@@ -0,0 +1,216 @@
+use anyhow::Result;
+use crossbeam_channel::{Receiver, RecvTimeoutError};
+use crossterm::event::{self, Event as TermEvent, KeyCode, KeyModifiers};
+use crossterm::terminal::{self, EnterAlternateScreen, LeaveAlternateScreen};
+use ratatui::backend::CrosstermBackend;
+use ratatui::layout::{Constraint, Direction, Layout};
+use ratatui::widgets::{Block, Borders, List, ListItem, Paragraph};
+use ratatui::Terminal;
+use read_process_memory::Pid;
+use std::collections::VecDeque;
+use std::io::{Stdout, Write};
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use vitellary_core::format_time;
+use vitellary_core::game::{Event, Message, RoomNames, SaveSummary, Update, FULL_SPEED};
+
+const LOG_LINES: usize = 200;
+
+/// A `Write` sink for `env_logger` that keeps the most recent log lines around so they can be
+/// drawn in the status display instead of scribbling over it.
+#[derive(Clone, Default)]
+pub(crate) struct LogBuffer(Arc<Mutex<VecDeque<String>>>);
+
+impl Write for LogBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let mut lines = self.0.lock().unwrap();
+        for line in String::from_utf8_lossy(buf).lines() {
+            if lines.len() == LOG_LINES {
+                lines.pop_front();
+            }
+            lines.push_back(line.to_owned());
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Draw the status display until the user quits or the update channel closes.
+pub(crate) fn run(
+    pid: Pid,
+    bind: SocketAddr,
+    receiver: &Receiver<Message>,
+    log: &LogBuffer,
+    rooms: &RoomNames,
+) -> Result<()> {
+    terminal::enable_raw_mode()?;
+    let mut stdout = std::io::stdout();
+    crossterm::execute!(stdout, EnterAlternateScreen)?;
+    let mut terminal = Terminal::new(CrosstermBackend::new(stdout))?;
+
+    let result = event_loop(&mut terminal, pid, bind, receiver, log, rooms);
+
+    terminal::disable_raw_mode()?;
+    crossterm::execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    terminal.show_cursor()?;
+    result
+}
+
+fn event_loop(
+    terminal: &mut Terminal<CrosstermBackend<Stdout>>,
+    pid: Pid,
+    bind: SocketAddr,
+    receiver: &Receiver<Message>,
+    log: &LogBuffer,
+    rooms: &RoomNames,
+) -> Result<()> {
+    let mut connection = format!("attaching to pid {}", pid);
+    let mut latest: Option<Update> = None;
+    let mut events: VecDeque<(Duration, Event)> = VecDeque::new();
+
+    loop {
+        match receiver.recv_timeout(Duration::from_millis(30)) {
+            Ok(Message::Attached { pid, address, .. }) => {
+                connection = format!("attached to pid {} (game object at {:#x})", pid, address);
+            }
+            Ok(Message::Detached { pid, reason }) => {
+                connection = format!("detached from pid {}: {}", pid, reason);
+            }
+            Ok(Message::Update(update)) => {
+                if let Some(event) = update.event {
+                    if events.len() == LOG_LINES {
+                        events.pop_front();
+                    }
+                    events.push_back((update.time, event));
+                }
+                latest = Some(update);
+            }
+            Ok(
+                Message::Summary(_)
+                | Message::TimeTrial(_)
+                | Message::Gravitron(_)
+                | Message::Health(_),
+            )
+            | Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => return Ok(()),
+        }
+
+        while event::poll(Duration::ZERO)? {
+            if let TermEvent::Key(key) = event::read()? {
+                if key.code == KeyCode::Char('q')
+                    || (key.code == KeyCode::Char('c')
+                        && key.modifiers.contains(KeyModifiers::CONTROL))
+                {
+                    return Ok(());
+                }
+            }
+        }
+
+        terminal.draw(|f| {
+            let chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([
+                    Constraint::Length(8),
+                    Constraint::Percentage(40),
+                    Constraint::Min(3),
+                ])
+                .split(f.size());
+
+            let status = match &latest {
+                Some(update) => format!(
+                    "{}, serving ws://{}\n\
+                     IGT: {}\n\
+                     room: {}\n\
+                     gamestate: {}  state: {}  run: {:?}  flips: {}{}{}",
+                    connection,
+                    bind,
+                    format_time(update.time),
+                    rooms.describe(update.state.room),
+                    update.state.gamestate,
+                    update.state.state,
+                    update.run,
+                    update.flips,
+                    saves(update),
+                    warnings(update),
+                ),
+                None => format!("{}, serving ws://{}\nwaiting for game", connection, bind),
+            };
+            f.render_widget(
+                Paragraph::new(status).block(
+                    Block::default()
+                        .title("vitellary (q to quit)")
+                        .borders(Borders::ALL),
+                ),
+                chunks[0],
+            );
+
+            let height = usize::from(chunks[1].height.saturating_sub(2));
+            let items: Vec<ListItem> = events
+                .iter()
+                .rev()
+                .take(height)
+                .map(|(time, event)| ListItem::new(format!("{}  {:?}", format_time(*time), event)))
+                .collect();
+            f.render_widget(
+                List::new(items).block(Block::default().title("events").borders(Borders::ALL)),
+                chunks[1],
+            );
+
+            let height = usize::from(chunks[2].height.saturating_sub(2));
+            let lines = log.0.lock().unwrap();
+            let items: Vec<ListItem> = lines
+                .iter()
+                .skip(lines.len().saturating_sub(height))
+                .map(|line| ListItem::new(line.as_str()))
+                .collect();
+            f.render_widget(
+                List::new(items).block(Block::default().title("log").borders(Borders::ALL)),
+                chunks[2],
+            );
+        })?;
+    }
+}
+
+/// A line describing the save slots, if the build's fields say where they are.
+fn saves(update: &Update) -> String {
+    let describe = |save: &Option<SaveSummary>| match save {
+        Some(save) => format!(
+            "{} in {} ({} trinkets)",
+            save.time, save.area, save.trinkets
+        ),
+        None => "-".to_owned(),
+    };
+    if update.saves.is_empty() {
+        String::new()
+    } else {
+        format!(
+            "\ntelesave: {}  quicksave: {}",
+            describe(&update.saves.telesave),
+            describe(&update.saves.quicksave)
+        )
+    }
+}
+
+/// A line warning about settings that keep the run off the leaderboards, if any are on.
+fn warnings(update: &Update) -> String {
+    let mut settings = Vec::new();
+    if update.settings.invincibility == Some(true) {
+        settings.push("invincibility".to_owned());
+    }
+    if let Some(slowdown) = update
+        .settings
+        .slowdown
+        .filter(|&slowdown| slowdown < FULL_SPEED)
+    {
+        settings.push(format!("{}/{} speed", slowdown, FULL_SPEED));
+    }
+    if settings.is_empty() {
+        String::new()
+    } else {
+        format!("\nWARNING: not leaderboard-legal: {}", settings.join(", "))
+    }
+}
@@ -0,0 +1,97 @@
+use env_logger::Builder;
+use log::{LevelFilter, Log, Metadata, Record};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+const LEVELS: [LevelFilter; 6] = [
+    LevelFilter::Off,
+    LevelFilter::Error,
+    LevelFilter::Warn,
+    LevelFilter::Info,
+    LevelFilter::Debug,
+    LevelFilter::Trace,
+];
+
+/// How much of vitellary's own logging to show beyond what the log filter allows, as an index
+/// into `LEVELS`, or 0 to follow the filter.
+static RAISED: AtomicUsize = AtomicUsize::new(0);
+
+/// The most verbose level the log filter allows, as an index into `LEVELS`.
+static CONFIGURED: AtomicUsize = AtomicUsize::new(0);
+
+/// A logger whose verbosity can be raised while running, for capturing detailed logs of a bug
+/// without detaching from the game.
+struct Logger {
+    /// Logs with the filter from the command line or environment.
+    configured: env_logger::Logger,
+    /// Logs everything from vitellary.
+    verbose: env_logger::Logger,
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.configured.enabled(metadata)
+            || (metadata.target().starts_with("vitellary") && metadata.level() <= raised())
+    }
+
+    fn log(&self, record: &Record) {
+        if self.configured.matches(record) {
+            self.configured.log(record);
+        } else if self.enabled(record.metadata()) {
+            self.verbose.log(record);
+        }
+    }
+
+    fn flush(&self) {
+        self.configured.flush();
+    }
+}
+
+/// Install a logger built by `builder`, whose verbosity [`set`] (or SIGUSR1) can raise later.
+pub(crate) fn init(builder: impl Fn() -> Builder) {
+    let configured = builder().build();
+    let verbose = builder()
+        .filter_module("vitellary", LevelFilter::Trace)
+        .build();
+    CONFIGURED.store(configured.filter() as usize, Ordering::Relaxed);
+    let max = configured.filter();
+    if log::set_boxed_logger(Box::new(Logger {
+        configured,
+        verbose,
+    }))
+    .is_ok()
+    {
+        log::set_max_level(max);
+    }
+    #[cfg(unix)]
+    // SAFETY: the handler only touches atomics, which is async-signal-safe.
+    unsafe {
+        libc::signal(libc::SIGUSR1, on_signal as *const () as libc::sighandler_t);
+    }
+}
+
+/// How much of vitellary's own logging is shown beyond what the filter allows.
+fn raised() -> LevelFilter {
+    LEVELS[RAISED.load(Ordering::Relaxed)]
+}
+
+/// Show vitellary's own logs up to `level` on top of what the log filter allows, or cycle from
+/// the filter to debug to trace and back if `level` is `None`. Returns the new level, or `None`
+/// if it's back to the filter.
+pub(crate) fn set(level: Option<LevelFilter>) -> Option<LevelFilter> {
+    let index = level.map_or_else(
+        || match raised() {
+            LevelFilter::Off => LevelFilter::Debug as usize,
+            LevelFilter::Debug => LevelFilter::Trace as usize,
+            _ => 0,
+        },
+        |level| level as usize,
+    );
+    RAISED.store(index, Ordering::Relaxed);
+    log::set_max_level(LEVELS[index.max(CONFIGURED.load(Ordering::Relaxed))]);
+    (index != 0).then_some(LEVELS[index])
+}
+
+#[cfg(unix)]
+extern "C" fn on_signal(_: libc::c_int) {
+    set(None);
+}
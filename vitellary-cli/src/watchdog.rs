@@ -0,0 +1,330 @@
+use crate::rpc::{Control, Request};
+use anyhow::Result;
+use crossbeam_channel::Sender;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use vitellary_core::game::{Health, Message};
+use vitellary_protocols::{queue, server};
+
+/// How often the watchdog checks on everything.
+const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How long the poll loop can go without polling, or an output without taking a message, before
+/// it counts as stuck.
+const STUCK_AFTER: Duration = Duration::from_secs(10);
+
+/// How long closing the outputs waits for their threads to finish writing.
+const CLOSE_TIMEOUT: Duration = Duration::from_secs(5);
+
+type Start = Box<dyn Fn() -> Result<Started> + Send + Sync>;
+
+/// A started output's channels, and the threads to wait for when closing it, if any.
+struct Started {
+    senders: Vec<Sender<Message>>,
+    threads: Vec<JoinHandle<()>>,
+}
+
+/// Where the poll loop sends what happens: each output's channels, and how to start the output
+/// again if it gets stuck.
+pub(crate) struct Outputs {
+    outputs: RwLock<Vec<Output>>,
+    epoch: Instant,
+    /// When the poll loop last polled, in milliseconds since `epoch`, or 0 while it isn't.
+    heartbeat: AtomicU64,
+}
+
+struct Output {
+    name: &'static str,
+    senders: Vec<queue::Sender<Message>>,
+    threads: Vec<JoinHandle<()>>,
+    start: Option<Start>,
+}
+
+impl Outputs {
+    pub(crate) fn new() -> Outputs {
+        Outputs {
+            outputs: RwLock::new(Vec::new()),
+            epoch: Instant::now(),
+            heartbeat: AtomicU64::new(0),
+        }
+    }
+
+    /// Add an output that can't be restarted.
+    pub(crate) fn add(&self, name: &'static str, sender: Sender<Message>) {
+        self.outputs.write().unwrap().push(Output {
+            name,
+            senders: vec![queue::Sender::new(sender)],
+            threads: Vec::new(),
+            start: None,
+        });
+    }
+
+    /// Start an output with `start`, which the watchdog calls again to restart it.
+    pub(crate) fn start(
+        &self,
+        name: &'static str,
+        start: impl Fn() -> Result<Vec<Sender<Message>>> + Send + Sync + 'static,
+    ) -> Result<()> {
+        self.push(
+            name,
+            Box::new(move || {
+                Ok(Started {
+                    senders: start()?,
+                    threads: Vec::new(),
+                })
+            }),
+        )
+    }
+
+    /// Start an output that runs on a thread of its own, which closing the outputs waits for.
+    pub(crate) fn start_sink(
+        &self,
+        name: &'static str,
+        start: impl Fn() -> Result<(Sender<Message>, JoinHandle<()>)> + Send + Sync + 'static,
+    ) -> Result<()> {
+        self.push(
+            name,
+            Box::new(move || {
+                let (sender, thread) = start()?;
+                Ok(Started {
+                    senders: vec![sender],
+                    threads: vec![thread],
+                })
+            }),
+        )
+    }
+
+    fn push(&self, name: &'static str, start: Start) -> Result<()> {
+        let started = start()?;
+        self.outputs.write().unwrap().push(Output {
+            name,
+            senders: started
+                .senders
+                .into_iter()
+                .map(queue::Sender::new)
+                .collect(),
+            threads: started.threads,
+            start: Some(start),
+        });
+        Ok(())
+    }
+
+    /// Send `message` to every output. Outputs that fall behind miss updates without events
+    /// (which the watchdog counts), except when replaying at full speed (`paced` is false), where
+    /// they'd miss almost all of them; everything else waits for them to catch up.
+    pub(crate) fn broadcast(&self, message: &Message, paced: bool) {
+        for output in self.outputs.write().unwrap().iter_mut() {
+            for sender in &mut output.senders {
+                if paced {
+                    sender.send(message.clone(), message.droppable()).ok();
+                } else {
+                    sender.send_blocking(message.clone()).ok();
+                }
+            }
+        }
+    }
+
+    /// Send the outputs what's waiting for them, in case the poll loop has gone quiet.
+    fn flush(&self) {
+        for output in self.outputs.write().unwrap().iter_mut() {
+            for sender in &mut output.senders {
+                sender.flush().ok();
+            }
+        }
+    }
+
+    /// Remove every output, so they see the poll loop is done, once they've been sent as much of
+    /// what's waiting for them as they have room for, then wait (for up to [`CLOSE_TIMEOUT`]) for
+    /// the ones on threads of their own to finish writing it.
+    pub(crate) fn close(&self) {
+        self.flush();
+        let outputs = std::mem::take(&mut *self.outputs.write().unwrap());
+        let deadline = Instant::now() + CLOSE_TIMEOUT;
+        for output in outputs {
+            drop(output.senders);
+            for thread in output.threads {
+                while !thread.is_finished() && Instant::now() < deadline {
+                    std::thread::sleep(Duration::from_millis(10));
+                }
+                if thread.is_finished() {
+                    thread.join().ok();
+                } else {
+                    log::warn!("the {} output didn't finish in time", output.name);
+                }
+            }
+        }
+    }
+
+    /// Note that the poll loop is polling.
+    pub(crate) fn beat(&self) {
+        let now = u64::try_from(self.epoch.elapsed().as_millis()).unwrap_or(u64::MAX);
+        self.heartbeat.store(now.max(1), Ordering::Relaxed);
+    }
+
+    /// Note that the poll loop has stopped polling on purpose, e.g. to wait for the game.
+    pub(crate) fn idle(&self) {
+        self.heartbeat.store(0, Ordering::Relaxed);
+    }
+
+    /// How long since the poll loop last polled, if it's polling.
+    fn since_beat(&self) -> Option<Duration> {
+        match self.heartbeat.load(Ordering::Relaxed) {
+            0 => None,
+            beat => Some(
+                self.epoch
+                    .elapsed()
+                    .saturating_sub(Duration::from_millis(beat)),
+            ),
+        }
+    }
+
+    /// Restart the output at `index`, returning whether it could be. Failures are only logged
+    /// as warnings if `warn`, since they're retried.
+    fn restart(&self, index: usize, warn: bool) -> bool {
+        let mut outputs = self.outputs.write().unwrap();
+        // The outputs may have been closed since the watchdog looked at them.
+        let Some(output) = outputs.get_mut(index) else {
+            return false;
+        };
+        let Some(start) = &output.start else {
+            return false;
+        };
+        match start() {
+            Ok(started) => {
+                log::info!("restarted the {} output", output.name);
+                output.senders = started
+                    .senders
+                    .into_iter()
+                    .map(queue::Sender::new)
+                    .collect();
+                // The stuck threads are left to finish (or not) on their own.
+                output.threads = started.threads;
+                true
+            }
+            Err(err) => {
+                let level = if warn {
+                    log::Level::Warn
+                } else {
+                    log::Level::Debug
+                };
+                log::log!(
+                    level,
+                    "failed to restart the {} output: {:#}",
+                    output.name,
+                    err
+                );
+                false
+            }
+        }
+    }
+}
+
+/// Watch the poll loop, the outputs and the WebSocket server, restarting whatever gets stuck, and
+/// send a [`Health`] status to the outputs whenever it changes.
+///
+/// A stuck output (one whose channel stays full) is started again; the WebSocket server is bound
+/// again if it stops accepting clients; and a stuck poll loop is asked to attach to the game again
+/// through `controls`, which it does as soon as it's able to.
+///
+/// With `--inetd`, the server's only client going away is the end of the session, so this exits.
+pub(crate) fn spawn(
+    outputs: Arc<Outputs>,
+    server: Arc<Mutex<server::Status>>,
+    controls: Sender<Request>,
+) {
+    std::thread::spawn(move || {
+        let mut full_since: Vec<Option<Instant>> = Vec::new();
+        let mut dropped: Vec<u64> = Vec::new();
+        let mut reattaching = false;
+        let mut last: Option<Health> = None;
+        loop {
+            std::thread::sleep(CHECK_INTERVAL);
+            if server.lock().unwrap().finished() {
+                log::info!("the client on stdin and stdout disconnected");
+                std::process::exit(0);
+            }
+            outputs.flush();
+            let mut stuck = Vec::new();
+            let mut restarted = Vec::new();
+
+            if outputs
+                .since_beat()
+                .is_some_and(|since| since >= STUCK_AFTER)
+            {
+                stuck.push("poll".to_owned());
+                if !reattaching {
+                    log::warn!("the poll loop is stuck; it will attach to the game again");
+                    let (reply, _) = crossbeam_channel::bounded(1);
+                    controls
+                        .try_send(Request {
+                            control: Control::Reattach,
+                            reply,
+                        })
+                        .ok();
+                    reattaching = true;
+                }
+            } else {
+                reattaching = false;
+            }
+
+            let names: Vec<_> = {
+                let outputs = outputs.outputs.read().unwrap();
+                full_since.resize(outputs.len(), None);
+                dropped.resize(outputs.len(), 0);
+                outputs
+                    .iter()
+                    .zip(full_since.iter_mut().zip(&mut dropped))
+                    .map(|(output, (since, dropped))| {
+                        if output.senders.iter().any(queue::Sender::is_full) {
+                            since.get_or_insert_with(Instant::now);
+                        } else {
+                            *since = None;
+                        }
+                        // Restarting an output starts its count over.
+                        let total = output.senders.iter().map(queue::Sender::dropped).sum();
+                        if total > *dropped {
+                            log::info!(
+                                "the {} output is behind; it missed {} updates without events",
+                                output.name,
+                                total - *dropped
+                            );
+                        }
+                        *dropped = total;
+                        output.name
+                    })
+                    .collect()
+            };
+            for (index, name) in names.into_iter().enumerate() {
+                let failed = name == "server" && server.lock().unwrap().failed();
+                if !failed && full_since[index].is_none_or(|since| since.elapsed() < STUCK_AFTER) {
+                    continue;
+                }
+                let newly = !last
+                    .as_ref()
+                    .is_some_and(|health| health.stuck.iter().any(|stuck| stuck == name));
+                if newly {
+                    log::warn!("the {} output is stuck", name);
+                }
+                if outputs.restart(index, newly) {
+                    full_since[index] = None;
+                    restarted.push(name.to_owned());
+                } else {
+                    stuck.push(name.to_owned());
+                }
+            }
+
+            let health = Health {
+                ok: stuck.is_empty(),
+                stuck,
+                restarted,
+                clients: server.lock().unwrap().clients(),
+            };
+            if last.as_ref() != Some(&health) {
+                outputs.broadcast(&Message::Health(health.clone()), true);
+                last = Some(health);
+            }
+        }
+    });
+}
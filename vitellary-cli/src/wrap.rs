@@ -0,0 +1,176 @@
+use anyhow::{bail, Context, Result};
+use read_process_memory::Pid;
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, ExitCode, ExitStatus};
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::time::Duration;
+
+/// How long to wait between looks for the game among the wrapped command's processes.
+const RETRY: Duration = Duration::from_millis(500);
+
+/// The pid of the wrapped command, or 0 if there isn't one.
+static CHILD: AtomicI32 = AtomicI32::new(0);
+
+/// Run `command` (as Steam's `%command%` expands) as our child.
+pub(crate) fn spawn(command: &[String]) -> Result<Child> {
+    let Some((program, args)) = command.split_first() else {
+        bail!("no command to wrap (use `vitellary wrap -- COMMAND...`)");
+    };
+    let child = Command::new(program)
+        .args(args)
+        .spawn()
+        .with_context(|| format!("failed to run {}", program))?;
+    log::info!("started {} (pid {})", program, child.id());
+    CHILD.store(child.id().try_into().unwrap_or_default(), Ordering::Relaxed);
+    forward_signals();
+    Ok(child)
+}
+
+/// Run the game at `path` as our child, from its own directory, returning it and its pid. `path`
+/// may also be the directory the game is in, or on macOS its app bundle.
+pub(crate) fn launch(path: &Path, args: &[String]) -> Result<(Child, Pid)> {
+    let executable = executable(path);
+    let child = Command::new(&executable)
+        .args(args)
+        .current_dir(executable.parent().unwrap_or(Path::new(".")))
+        .spawn()
+        .with_context(|| format!("failed to run {}", executable.display()))?;
+    let pid = child.id().try_into().context("pid out of range")?;
+    log::info!("launched {} (pid {})", executable.display(), pid);
+    CHILD.store(pid, Ordering::Relaxed);
+    forward_signals();
+    Ok((child, pid))
+}
+
+/// The game's executable, given it or the directory or app bundle it's in.
+fn executable(path: &Path) -> PathBuf {
+    if !path.is_dir() {
+        return path.to_owned();
+    }
+    [
+        path.join("Contents/MacOS/VVVVVV"),
+        path.join("VVVVVV"),
+        path.join("vvvvvv"),
+        path.join("VVVVVV.x86_64"),
+    ]
+    .into_iter()
+    .find(|candidate| candidate.is_file())
+    .unwrap_or_else(|| path.join("VVVVVV"))
+}
+
+/// Pass SIGINT, SIGTERM and SIGHUP on to the wrapped command, instead of handling them
+/// ourselves; we exit once it does. Elsewhere, the console sends Ctrl-C to it as well as us.
+fn forward_signals() {
+    #[cfg(unix)]
+    for signal in [libc::SIGINT, libc::SIGTERM, libc::SIGHUP] {
+        let handler: extern "C" fn(libc::c_int) = forward;
+        // SAFETY: `forward` only does what's safe in a signal handler.
+        unsafe { libc::signal(signal, handler as libc::sighandler_t) };
+    }
+}
+
+/// Send `signal` to the wrapped command, or, once it has exited, take the signal's default
+/// action.
+#[cfg(unix)]
+extern "C" fn forward(signal: libc::c_int) {
+    let pid = CHILD.load(Ordering::Relaxed);
+    // SAFETY: kill, signal and raise are async-signal-safe.
+    unsafe {
+        if pid == 0 || libc::kill(pid, signal) == -1 {
+            libc::signal(signal, libc::SIG_DFL);
+            libc::raise(signal);
+        }
+    }
+}
+
+/// Wait for VVVVVV to start among `child` and its descendants (launchers like Steam's start it
+/// several processes down) and return its pid, failing if `child` exits first.
+pub(crate) fn find_game(child: &mut Child) -> Result<Pid> {
+    let root = child.id().try_into().context("pid out of range")?;
+    loop {
+        if let Some(pid) = find_in_tree(root)? {
+            log::info!("found VVVVVV (pid {}) started by the wrapped command", pid);
+            return Ok(pid);
+        }
+        if let Some(status) = child.try_wait()? {
+            bail!(
+                "the wrapped command exited ({}) before VVVVVV started",
+                status
+            );
+        }
+        std::thread::sleep(RETRY);
+    }
+}
+
+/// Run `attach` until it succeeds, since the game may not have set itself up yet, failing if
+/// `child` exits first.
+pub(crate) fn attach<T>(child: &mut Child, attach: impl Fn() -> Result<T>) -> Result<T> {
+    loop {
+        match attach() {
+            Ok(game) => return Ok(game),
+            Err(err) => {
+                if let Some(status) = child.try_wait()? {
+                    return Err(err.context(format!("the wrapped command exited ({})", status)));
+                }
+                log::debug!("{:#}; retrying", err);
+                std::thread::sleep(RETRY);
+            }
+        }
+    }
+}
+
+/// Wait for `child` to exit and return its exit status as ours.
+pub(crate) fn wait(mut child: Child) -> Result<ExitCode> {
+    let status = child
+        .wait()
+        .context("failed to wait for the wrapped command")?;
+    CHILD.store(0, Ordering::Relaxed);
+    log::info!("the wrapped command exited ({})", status);
+    Ok(exit_code(status))
+}
+
+/// A child's exit status as an exit code, using the shell's 128 + N for death by signal N.
+fn exit_code(status: ExitStatus) -> ExitCode {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        if let Some(signal) = status.signal() {
+            return ExitCode::from(u8::try_from(128 + signal).unwrap_or(u8::MAX));
+        }
+    }
+    ExitCode::from(
+        status
+            .code()
+            .and_then(|code| u8::try_from(code).ok())
+            .unwrap_or(1),
+    )
+}
+
+/// The first VVVVVV process found searching down from `root`, including `root` itself.
+fn find_in_tree(root: Pid) -> Result<Option<Pid>> {
+    let games = pgrep(&["VVVVVV"])?;
+    let mut queue = VecDeque::from([root]);
+    while let Some(pid) = queue.pop_front() {
+        if games.contains(&pid) {
+            return Ok(Some(pid));
+        }
+        queue.extend(pgrep(&["-P", &pid.to_string()])?);
+    }
+    Ok(None)
+}
+
+/// The pids `pgrep` lists for `args`, or none if it finds nothing.
+fn pgrep(args: &[&str]) -> Result<Vec<Pid>> {
+    let output = Command::new("pgrep")
+        .args(args)
+        .output()
+        .context("failed to run pgrep")?;
+    if !output.status.success() && output.status.code() != Some(1) {
+        bail!("pgrep failed with {}", output.status);
+    }
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| line.trim().parse().ok())
+        .collect())
+}
@@ -0,0 +1,346 @@
+//! Attach vitellary to `vitellary-fake-game` and follow scripted runs through its `--stdout`
+//! messages, end to end. They need the fake game, so they only run with `cargo test --features
+//! fake-game`.
+//!
+//! On Linux, vitellary finds the game object by the record the fake game prints for itself. On
+//! macOS it scans for it instead, which needs root (for `task_for_pid`), so run these with sudo
+//! there.
+
+#![cfg(unix)]
+
+use serde_json::Value;
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::process::{Child, ChildStdin, Command, ExitStatus, Stdio};
+use std::sync::mpsc::{self, Receiver};
+use std::time::{Duration, Instant};
+
+/// How long to wait for vitellary to send what we expect.
+const TIMEOUT: Duration = Duration::from_secs(20);
+
+/// vitellary's exit status when the game exits.
+const GAME_EXITED: i32 = 15;
+
+struct Harness {
+    game: Child,
+    script: Option<ChildStdin>,
+    address: usize,
+    vitellary: Child,
+    messages: Receiver<Value>,
+    config: PathBuf,
+}
+
+impl Harness {
+    /// Start the fake game, and vitellary attached to it.
+    fn start() -> Harness {
+        Harness::start_with(&[], &[])
+    }
+
+    /// Start the fake game, and vitellary attached to it with `args` and `files` (paths and
+    /// contents) in its config directory.
+    fn start_with(args: &[&str], files: &[(&str, &str)]) -> Harness {
+        Harness::start_with_record(args, files, "")
+    }
+
+    /// Start the fake game, and vitellary attached to it with `args`, `files`, and `extra` lines
+    /// (like `fields`) in the fake game's fingerprint record.
+    fn start_with_record(args: &[&str], files: &[(&str, &str)], extra: &str) -> Harness {
+        let mut game = Command::new(env!("CARGO_BIN_EXE_vitellary-fake-game"))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .expect("failed to start the fake game");
+        let mut record = String::new();
+        for line in BufReader::new(game.stdout.take().unwrap()).lines() {
+            let line = line.expect("failed to read from the fake game");
+            if line.is_empty() {
+                break;
+            }
+            record += &line;
+            record.push('\n');
+        }
+        let address = record
+            .lines()
+            .find_map(|line| line.strip_prefix("# game object at 0x"))
+            .and_then(|address| usize::from_str_radix(address, 16).ok())
+            .expect("the fake game didn't say where its game object is");
+
+        let config = std::env::temp_dir().join(format!("vitellary-end-to-end-{}", game.id()));
+        std::fs::create_dir_all(&config).unwrap();
+        if cfg!(target_os = "linux") {
+            std::fs::write(config.join("fingerprints.toml"), record + extra).unwrap();
+        }
+        for (path, contents) in files {
+            let path = config.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, contents).unwrap();
+        }
+
+        let mut vitellary = Command::new(env!("CARGO_BIN_EXE_vitellary"))
+            .args(["--bind", "127.0.0.1:0", "--stdout"])
+            .args(args)
+            .arg(game.id().to_string())
+            .env("VITELLARY_CONFIG", &config)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .expect("failed to start vitellary");
+        let stdout = BufReader::new(vitellary.stdout.take().unwrap());
+        let (sender, messages) = mpsc::channel();
+        std::thread::spawn(move || {
+            for line in stdout.lines().map_while(Result::ok) {
+                let message = serde_json::from_str(&line).expect("vitellary printed bad JSON");
+                if sender.send(message).is_err() {
+                    return;
+                }
+            }
+        });
+
+        Harness {
+            script: game.stdin.take(),
+            game,
+            address,
+            vitellary,
+            messages,
+            config,
+        }
+    }
+
+    /// Have the fake game run a script.
+    fn run(&mut self, script: &str) {
+        let stdin = self.script.as_mut().expect("the script has ended");
+        stdin
+            .write_all(script.as_bytes())
+            .and_then(|()| stdin.flush())
+            .expect("failed to write to the fake game");
+    }
+
+    /// End the script, which makes the fake game exit, and reap it as the game's parent would.
+    fn end(&mut self) {
+        self.script = None;
+        let status = self.game.wait().unwrap();
+        assert!(status.success(), "the fake game failed: {}", status);
+    }
+
+    /// Wait for the next message that `matches`, returning it.
+    fn expect(&self, what: &str, matches: impl Fn(&Value) -> bool) -> Value {
+        let deadline = Instant::now() + TIMEOUT;
+        loop {
+            let timeout = deadline.saturating_duration_since(Instant::now());
+            match self.messages.recv_timeout(timeout) {
+                Ok(message) if matches(&message) => return message,
+                Ok(_) => {}
+                Err(_) => panic!("vitellary didn't send {}", what),
+            }
+        }
+    }
+
+    /// Wait for an update with `event`.
+    fn expect_event(&self, event: &str) -> Value {
+        self.expect(event, |message| {
+            message["type"] == "update" && message["event"] == event
+        })
+    }
+
+    /// Wait for vitellary to exit.
+    fn wait(&mut self) -> ExitStatus {
+        let deadline = Instant::now() + TIMEOUT;
+        loop {
+            if let Some(status) = self.vitellary.try_wait().unwrap() {
+                return status;
+            }
+            assert!(Instant::now() < deadline, "vitellary didn't exit");
+            std::thread::sleep(Duration::from_millis(50));
+        }
+    }
+}
+
+impl Drop for Harness {
+    fn drop(&mut self) {
+        for child in [&mut self.vitellary, &mut self.game] {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+        let _ = std::fs::remove_dir_all(&self.config);
+    }
+}
+
+#[test]
+fn attaches_to_the_game_object() {
+    let harness = Harness::start();
+    let attached = harness.expect("attached", |message| message["type"] == "attached");
+    assert_eq!(attached["address"].as_u64(), Some(harness.address as u64));
+}
+
+#[test]
+fn splits_a_run() {
+    let mut harness = Harness::start();
+    harness.expect("attached", |message| message["type"] == "attached");
+    // Verdigris only counts in "Murdering Twinmaker" (see quirks.toml).
+    harness.run("wait 200\ngamestate 0\nroom 115 100\ntick 10\n");
+    harness.expect_event("new-game");
+
+    harness.run("state 3006\ntick 10\nstate 0\ntick 10\n");
+    let update = harness.expect_event("verdigris");
+    assert_eq!(update["crew"], 2);
+
+    harness.run("deathcounts 4\nstate 3503\ntick 10\n");
+    harness.expect_event("game-complete");
+    let summary = harness.expect("summary", |message| message["type"] == "summary");
+    assert_eq!(summary["deaths"], 4);
+    let time = summary["time"].as_f64().unwrap();
+    assert!((0.9..1.1).contains(&time), "run took {}s", time);
+}
+
+#[test]
+fn resets_on_quitting_to_the_menu() {
+    let mut harness = Harness::start();
+    harness.expect("attached", |message| message["type"] == "attached");
+    harness.run("wait 200\ngamestate 0\ntick 10\n");
+    harness.expect_event("new-game");
+    harness.run("gamestate 1\nwait 200\n");
+    let update = harness.expect_event("reset");
+    assert_eq!(update["reset_cause"], "quit-to-menu");
+}
+
+#[test]
+fn exits_with_the_game() {
+    let mut harness = Harness::start();
+    harness.expect("attached", |message| message["type"] == "attached");
+    harness.end();
+    harness.expect("detached", |message| message["type"] == "detached");
+    assert_eq!(harness.wait().code(), Some(GAME_EXITED));
+}
+
+#[test]
+fn splits_once_per_run() {
+    let mut harness = Harness::start();
+    harness.expect("attached", |message| message["type"] == "attached");
+    harness.run("wait 200\ngamestate 0\nroom 115 100\ntick 10\n");
+    harness.expect_event("new-game");
+    harness.run("state 3006\ntick 10\nstate 0\ntick 10\n");
+    harness.expect_event("verdigris");
+
+    // Watching the cutscene again doesn't split again; the next split is Vermilion's.
+    harness.run("state 3006\ntick 10\nstate 0\ntick 10\nstate 3060\ntick 10\n");
+    let update = harness.expect("the next split", |message| {
+        message["type"] == "update" && !message["event"].is_null()
+    });
+    assert_eq!(update["event"], "vermilion");
+}
+
+#[test]
+fn loading_a_save_splits_without_a_new_game() {
+    let mut harness = Harness::start();
+    harness.expect("attached", |message| message["type"] == "attached");
+    // The game clock hasn't started yet, but the save has changed.
+    harness.run("wait 200\nsave 12:34 Space Station\ngamestate 0\nroom 115 100\ntick 10\n");
+    harness.run("state 3006\ntick 10\nstate 0\ntick 10\n");
+    let update = harness.expect("a split", |message| {
+        message["type"] == "update" && !message["event"].is_null()
+    });
+    assert_eq!(update["event"], "verdigris");
+}
+
+#[test]
+fn counts_down_the_gravitron() {
+    let mut harness = Harness::start_with(
+        &[
+            "--fields",
+            "gravitron-mode=0x200,gravitron-timer=0x204,gravitron-game=0x208",
+        ],
+        &[],
+    );
+    harness.expect("attached", |message| message["type"] == "attached");
+    harness.run("wait 200\ngamestate 0\ntick 10\n");
+    harness.expect_event("new-game");
+    // The campaign's gravitron, with 20 seconds left.
+    harness.run("poke 208 0\npoke 204 600\npoke 200 1\ntick 10\n");
+    let update = harness.expect("the countdown", |message| {
+        message["type"] == "update" && !message["gravitron_remaining"].is_null()
+    });
+    assert_eq!(update["gravitron_remaining"], 20.0);
+}
+
+#[test]
+fn tells_teleports_from_walking_away() {
+    let mut harness = Harness::start();
+    harness.expect("attached", |message| message["type"] == "attached");
+    harness.run("wait 200\ngamestate 0\nroom 113 108\ntick 10\n");
+    harness.expect_event("new-game");
+    // Backing out of the teleporter screen and walking into the next room isn't a teleport.
+    harness.run("gamestate 5\ntick 10\ngamestate 0\ntick 10\nroom 114 108\ntick 10\n");
+    harness.run("gamestate 5\ntick 10\ngamestate 0\ntick 10\nroom 102 101\ntick 10\n");
+    let update = harness.expect("a teleport", |message| {
+        message["type"] == "update" && !message["event"].is_null()
+    });
+    assert_eq!(update["event"], "teleport");
+    assert_eq!(update["room"], serde_json::json!([102, 101]));
+}
+
+#[test]
+fn switches_between_the_campaign_and_a_custom_level() {
+    let mut harness = Harness::start_with(
+        &["--level", "test", "--fields", "custom-mode=0x200"],
+        &[(
+            "levels/test.toml",
+            "[[splits]]\nname = \"First\"\nstate = [100, 102]\n\n\
+             [[splits]]\nname = \"Last\"\nstate = [200, 202]\n",
+        )],
+    );
+    harness.expect("attached", |message| message["type"] == "attached");
+    harness.run("wait 200\ngamestate 0\nroom 115 100\ntick 10\n");
+    harness.expect_event("new-game");
+    harness.run("state 3006\ntick 10\nstate 0\ntick 10\n");
+    harness.expect_event("verdigris");
+
+    harness.run("gamestate 1\nwait 200\n");
+    harness.expect_event("reset");
+    harness.run("poke 200 1\nwait 200\ngamestate 0\ntick 10\n");
+    harness.expect_event("new-game");
+    harness.run("state 3006\ntick 10\nstate 100\ntick 10\n");
+    let update = harness.expect("a split", |message| {
+        message["type"] == "update" && !message["event"].is_null()
+    });
+    assert_eq!(update["event"]["custom"], 0);
+}
+
+#[test]
+fn finds_the_profile_for_the_level() {
+    let mut harness = Harness::start_with(
+        &["--fields", "custom-mode=0x200,custom-level=0x210"],
+        &[(
+            "levels/333.toml",
+            "[[splits]]\nname = \"First\"\nstate = [100, 102]\n\n\
+             [[splits]]\nname = \"Last\"\nstate = [200, 202]\n",
+        )],
+    );
+    harness.expect("attached", |message| message["type"] == "attached");
+    harness.run("poke 200 1\ntext 210 levels/333.vvvvvv\nwait 200\ngamestate 0\ntick 10\n");
+    harness.expect_event("new-game");
+    harness.run("state 100\ntick 10\n");
+    let update = harness.expect("a split", |message| {
+        message["type"] == "update" && !message["event"].is_null()
+    });
+    assert_eq!(update["event"]["custom"], 0);
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn flags_a_slowed_down_run() {
+    let mut harness = Harness::start_with_record(&[], &[], "fields = { slowdown = 0x200 }\n");
+    harness.expect("attached", |message| message["type"] == "attached");
+    harness.run("poke 200 30\nwait 200\ngamestate 0\ntick 10\n");
+    harness.expect_event("new-game");
+    harness.run("poke 200 18\ntick 10\n");
+    let update = harness.expect("a warning", |message| {
+        message["type"] == "update" && !message["event"].is_null()
+    });
+    assert_eq!(update["event"]["warning"], "slowdown");
+    assert_eq!(update["slowdown"], 18);
+    harness.run("poke 200 30\ntick 10\nstate 3503\ntick 10\n");
+    harness.expect_event("game-complete");
+    let summary = harness.expect("summary", |message| message["type"] == "summary");
+    assert_eq!(summary["slowdown"], 18);
+}
@@ -0,0 +1,61 @@
+//! Benchmarks for the attach scan and the per-update decode and split path.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use vitellary_core::game::common::{GameObject, GAME_OBJECT_SIZE};
+use vitellary_core::game::{self, State};
+
+/// A synthetic memory image with the game object at `len - 0x1000`, with its `savetime` and
+/// `savearea` strings laid out like x86_64 libc++ short strings.
+fn memory_image(len: usize) -> Vec<u8> {
+    let mut image = vec![0; len];
+    let savetime = len - 0x1000 + 0xb8;
+    image[savetime] = 10;
+    image[savetime + 1..savetime + 6].copy_from_slice(b"00:00");
+    image[savetime + 24] = 14;
+    image[savetime + 25..savetime + 32].copy_from_slice(b"nowhere");
+    image
+}
+
+fn scan(c: &mut Criterion) {
+    let image = memory_image(64 << 20);
+    let read = |address: usize, buf: &mut [u8]| {
+        let end = (address + buf.len()).min(image.len());
+        buf[..end - address].copy_from_slice(&image[address..end]);
+        true
+    };
+    assert_eq!(
+        game::scan::scan(0..image.len(), read),
+        game::scan::Scan::Found(image.len() - 0x1000)
+    );
+    c.bench_function("scan 64 MiB", |b| {
+        b.iter(|| game::scan::scan(0..image.len(), read));
+    });
+}
+
+fn decode(c: &mut Criterion) {
+    let mut buf = [0; GAME_OBJECT_SIZE];
+    buf[0x5c..0x60].copy_from_slice(&3006_u32.to_ne_bytes());
+    buf[0x18..0x1c].copy_from_slice(&115_u32.to_ne_bytes());
+    buf[0x1c..0x20].copy_from_slice(&100_u32.to_ne_bytes());
+    c.bench_function("decode game object", |b| {
+        b.iter(|| GameObject::from(black_box(buf)).into_state());
+    });
+}
+
+fn split(c: &mut Criterion) {
+    let old = State {
+        room: (115, 100),
+        gamestate: 0,
+        state: 0,
+        gravitycontrol: 0,
+        deathcounts: 0,
+    };
+    let cur = State { state: 3006, ..old };
+    let quirks = game::Quirks::builtin();
+    c.bench_function("campaign split", |b| {
+        b.iter(|| game::campaign_split(&quirks, black_box(&old), black_box(&cur)));
+    });
+}
+
+criterion_group!(benches, scan, decode, split);
+criterion_main!(benches);
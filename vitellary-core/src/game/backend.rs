@@ -0,0 +1,188 @@
+use crate::game::common::Timer;
+use crate::game::{imp, process_exists, State, Timestamp};
+use anyhow::{bail, Context, Result};
+use read_process_memory::Pid;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Somewhere to read the game object from.
+pub trait Backend: Send {
+    /// The address of the game object, for display.
+    fn address(&self) -> usize;
+
+    fn read_game_object(&self) -> Result<(State, Timer<u32>)>;
+
+    /// Read a field `offset` bytes from the start of the game object, for fields that aren't
+    /// part of the layout every build shares (see [`Fields`](crate::game::Fields)).
+    fn read_u32(&self, offset: isize) -> Result<u32> {
+        let _ = offset;
+        bail!("this backend can only read the game object")
+    }
+
+    /// Whether reads fail because the game is gone, rather than for some transient reason.
+    fn exited(&self) -> bool;
+
+    /// When the state last read was recorded, for backends that play back recorded states.
+    fn recorded_at(&self) -> Option<Timestamp> {
+        None
+    }
+
+    /// Whether the poll loop should wait between reads. Backends that produce states as fast as
+    /// they're read, like a replay at full speed, return false, and every update they cause is
+    /// delivered to every output.
+    fn paced(&self) -> bool {
+        true
+    }
+}
+
+/// How to find the game, given on the command line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Kind {
+    /// The game running natively on this platform.
+    Native,
+    /// The Windows build running under Proton or Wine.
+    #[cfg(target_os = "linux")]
+    Proton,
+    /// A file containing a copy of the game object, re-read on every update.
+    Dump(PathBuf),
+    /// A game sitting on the title screen forever, for testing outputs without the game.
+    Mock,
+    /// The samples in a verification log, played back.
+    Replay(PathBuf),
+    /// A `vitellary agent` on another machine, at this address.
+    Remote(String),
+    /// A `vitellary agent --stdio` run on this host over SSH.
+    Ssh(String),
+}
+
+impl FromStr for Kind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Kind, String> {
+        match s {
+            "native" => Ok(Kind::Native),
+            #[cfg(target_os = "linux")]
+            "proton" => Ok(Kind::Proton),
+            "mock" => Ok(Kind::Mock),
+            _ => {
+                if let Some(path) = s.strip_prefix("dump:") {
+                    Ok(Kind::Dump(path.into()))
+                } else if let Some(path) = s.strip_prefix("replay:") {
+                    Ok(Kind::Replay(path.into()))
+                } else if let Some(addr) = s.strip_prefix("remote:") {
+                    Ok(Kind::Remote(addr.to_owned()))
+                } else if let Some(host) = s.strip_prefix("ssh:") {
+                    Ok(Kind::Ssh(host.to_owned()))
+                } else {
+                    Err(format!(
+                        "unknown backend {:?} (expected native, proton, dump:PATH, replay:PATH, \
+                         remote:ADDR, ssh:HOST, or mock)",
+                        s
+                    ))
+                }
+            }
+        }
+    }
+}
+
+/// A VVVVVV process on this platform.
+pub(super) struct Native {
+    pub(super) pid: Pid,
+    pub(super) handle: imp::Handle,
+}
+
+impl Backend for Native {
+    fn address(&self) -> usize {
+        imp::address(&self.handle)
+    }
+
+    fn read_game_object(&self) -> Result<(State, Timer<u32>)> {
+        imp::read_game_object(&self.handle)
+    }
+
+    fn read_u32(&self, offset: isize) -> Result<u32> {
+        imp::read_u32(&self.handle, offset)
+    }
+
+    fn exited(&self) -> bool {
+        !process_exists(self.pid)
+    }
+}
+
+/// A copy of the game object in a file, e.g. written by a test harness or another tool.
+pub(super) struct Dump(pub(super) PathBuf);
+
+impl Backend for Dump {
+    fn address(&self) -> usize {
+        0
+    }
+
+    fn read_game_object(&self) -> Result<(State, Timer<u32>)> {
+        let data = std::fs::read(&self.0)
+            .with_context(|| format!("failed to read {}", self.0.display()))?;
+        Ok(super::common::decode(&data)
+            .with_context(|| format!("{} is too short", self.0.display()))?
+            .into_state())
+    }
+
+    fn read_u32(&self, offset: isize) -> Result<u32> {
+        let data = std::fs::read(&self.0)
+            .with_context(|| format!("failed to read {}", self.0.display()))?;
+        let bytes = usize::try_from(offset)
+            .ok()
+            .and_then(|offset| data.get(offset..offset + 4))
+            .with_context(|| format!("{} is too short", self.0.display()))?;
+        Ok(u32::from_ne_bytes(bytes.try_into()?))
+    }
+
+    fn exited(&self) -> bool {
+        !self.0.exists()
+    }
+}
+
+/// A fake game whose state is set directly. Clones share the same state, so a clone can drive a
+/// `Game` that owns the original.
+#[derive(Clone)]
+pub struct Mock(Arc<Mutex<(State, Duration)>>);
+
+impl Default for Mock {
+    fn default() -> Mock {
+        Mock::new()
+    }
+}
+
+impl Mock {
+    pub fn new() -> Mock {
+        Mock(Arc::new(Mutex::new((
+            State {
+                room: (0, 0),
+                gamestate: 1,
+                state: 0,
+                gravitycontrol: 0,
+                deathcounts: 0,
+            },
+            Duration::ZERO,
+        ))))
+    }
+
+    pub fn set(&self, state: State, time: Duration) {
+        *self.0.lock().unwrap() = (state, time);
+    }
+}
+
+impl Backend for Mock {
+    fn address(&self) -> usize {
+        0
+    }
+
+    fn read_game_object(&self) -> Result<(State, Timer<u32>)> {
+        let (state, time) = self.0.lock().unwrap().clone();
+        Ok((state, Timer::from_duration(time)))
+    }
+
+    fn exited(&self) -> bool {
+        false
+    }
+}
@@ -0,0 +1,44 @@
+use crate::game::{Event, Profile, State};
+use std::collections::HashSet;
+
+/// Another category's split table, followed alongside the run's own (see
+/// [`Game::track_category`](crate::game::Game::track_category)).
+#[derive(Debug)]
+pub(super) struct Tracked {
+    pub(super) name: String,
+    profile: Profile,
+    /// The splits made this run, since each only splits once.
+    fired: HashSet<u16>,
+}
+
+impl Tracked {
+    pub(super) fn new(name: String, profile: Profile) -> Tracked {
+        Tracked {
+            name,
+            profile,
+            fired: HashSet::new(),
+        }
+    }
+
+    /// Find the category's split (if any) for a state change, using the glitchrunner mode states
+    /// if `glitchrunner`.
+    pub(super) fn split(&mut self, old: &State, cur: &State, glitchrunner: bool) -> Option<Event> {
+        let event = self.profile.split(old, cur, glitchrunner)?;
+        let Event::Custom(index) = event else {
+            return None;
+        };
+        if !self.fired.insert(index) {
+            log::debug!("ignoring {} split {}, already made", self.name, index + 1);
+            return None;
+        }
+        if let Some(split) = self.profile.get(index) {
+            log::info!("{} split: {}", self.name, split.name);
+        }
+        Some(event)
+    }
+
+    /// Forget the splits made, for a new run.
+    pub(super) fn reset(&mut self) {
+        self.fired.clear();
+    }
+}
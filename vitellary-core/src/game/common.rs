@@ -0,0 +1,113 @@
+use crate::game::State;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::time::Duration;
+use zerocopy::FromBytes;
+
+// Fields past the timer aren't part of this layout. Many of them (like the Super Gravitron's
+// `swntimer`) sit behind `std::string`s and `std::vector`s, whose sizes differ between libstdc++
+// and libc++, so they're read at per-build offsets (found with vitellary-dump) instead; see
+// `Fields`.
+#[derive(Debug, FromBytes)]
+#[repr(C)]
+pub struct GameObject {
+    _unused1: [u8; 0x18], // 0x00
+    room_x: u32,          // 0x18
+    room_y: u32,          // 0x1c
+    _unused2: [u8; 0x3c], // 0x20
+    state: u32,           // 0x5c
+    _unused3: [u8; 0x08], // 0x60
+    gamestate: u32,       // 0x68
+    _unused4: [u8; 0x08], // 0x6c
+    gravitycontrol: u32,  // 0x74
+    _unused5: [u8; 0x28], // 0x78
+    deathcounts: u32,     // 0xa0
+    timer: Timer<u32>,    // 0xa4
+}
+pub const GAME_OBJECT_SIZE: usize = std::mem::size_of::<GameObject>();
+const _: () = assert!(GAME_OBJECT_SIZE == 0xa4 + 16);
+
+impl GameObject {
+    pub fn into_state(self) -> (State, Timer<u32>) {
+        let state = State {
+            room: (self.room_x, self.room_y),
+            gamestate: self.gamestate,
+            state: self.state,
+            gravitycontrol: self.gravitycontrol,
+            deathcounts: self.deathcounts,
+        };
+        log::trace!("read {}, timer {}", state, self.timer);
+        (state, self.timer)
+    }
+}
+
+/// The address of a field `offset` bytes from the game object at `addr`.
+pub fn field_address(addr: usize, offset: isize) -> anyhow::Result<usize> {
+    addr.checked_add_signed(offset)
+        .ok_or_else(|| anyhow::anyhow!("field offset {:#x} is out of range", offset))
+}
+
+/// Decode the game object from the start of `data`, if it's long enough.
+pub fn decode(data: &[u8]) -> Option<GameObject> {
+    let buf: [u8; GAME_OBJECT_SIZE] = data.get(..GAME_OBJECT_SIZE)?.try_into().ok()?;
+    Some(GameObject::from(buf))
+}
+
+impl From<[u8; GAME_OBJECT_SIZE]> for GameObject {
+    fn from(buf: [u8; GAME_OBJECT_SIZE]) -> Self {
+        zerocopy::transmute!(buf)
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, FromBytes, Serialize, Deserialize)]
+pub struct Timer<T> {
+    pub frames: T,
+    seconds: T,
+    minutes: T,
+    hours: T,
+}
+
+impl<T: Copy> Timer<T>
+where
+    u64: From<T>,
+    u32: From<T>,
+{
+    /// The in-game time, where `frames` counts up to `frame_rate` before rolling over into
+    /// `seconds`. This is 30 for the game's fixed logic rate, but builds with uncapped logic may
+    /// count faster.
+    pub fn duration(&self, frame_rate: u32) -> Duration {
+        let frame_rate: u64 = frame_rate.max(1).into();
+        Duration::from_secs(
+            u64::from(self.hours) * 3600 + u64::from(self.minutes) * 60 + u64::from(self.seconds),
+        ) + Duration::from_nanos(1_000_000_000 / frame_rate * u64::from(self.frames))
+    }
+}
+
+/// The timer as the game shows it, with the frame counter after a `+`, e.g. `1:02:03+15`.
+impl fmt::Display for Timer<u32> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{:02}:{:02}+{:02}",
+            self.hours, self.minutes, self.seconds, self.frames
+        )
+    }
+}
+
+impl Timer<u32> {
+    /// Whether the game's counter could have reached these values.
+    pub fn is_plausible(&self) -> bool {
+        self.seconds < 60 && self.minutes < 60 && self.frames < 1000
+    }
+
+    /// The timer as the game would count it at 30 frames per second.
+    pub fn from_duration(time: Duration) -> Timer<u32> {
+        let secs = time.as_secs();
+        Timer {
+            frames: time.subsec_nanos() / (1_000_000_000 / 30),
+            seconds: (secs % 60) as u32,
+            minutes: (secs / 60 % 60) as u32,
+            hours: u32::try_from(secs / 3600).unwrap_or(u32::MAX),
+        }
+    }
+}
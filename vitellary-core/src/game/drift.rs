@@ -0,0 +1,83 @@
+use std::time::Duration;
+
+/// How much real time each comparison of the game timer against the wall clock covers.
+const WINDOW: Duration = Duration::from_secs(10);
+
+/// How far the game timer's rate may be from real time before it counts as drifting. It only
+/// ticks once a frame, so this has to allow for that, plus polling jitter.
+const TOLERANCE: f64 = 0.05;
+
+/// How long the game timer can stand still before we take it as paused, rather than between
+/// frames, and stop measuring until it runs again.
+const STALL: Duration = Duration::from_millis(250);
+
+/// A jump in the game timer bigger than this (or backwards) is a savestate or a new game, not
+/// drift.
+const JUMP: Duration = Duration::from_secs(1);
+
+/// Compares how fast the game timer runs with the wall clock, to catch lag, speedhacks, and mods
+/// that change the frame counter.
+#[derive(Debug, Default)]
+pub(super) struct Drift {
+    /// Where the current window started, by the wall clock and the game timer.
+    anchor: Option<(Duration, Duration)>,
+    /// When the game timer last changed, and to what.
+    last: Option<(Duration, Duration)>,
+    drifting: bool,
+}
+
+impl Drift {
+    /// Follow the game timer reading `time` at `now` (by a monotonic clock) while `playing`,
+    /// returning the timer's rate as a percentage of real time when it starts drifting.
+    pub(super) fn update(&mut self, now: Duration, time: Duration, playing: bool) -> Option<u16> {
+        if !playing {
+            self.anchor = None;
+            self.last = None;
+            return None;
+        }
+        match self.last {
+            Some((_, last)) if time.checked_sub(last).is_none_or(|gained| gained > JUMP) => {
+                self.anchor = None;
+            }
+            Some((at, last)) if time == last => {
+                if now.saturating_sub(at) > STALL {
+                    self.anchor = None;
+                }
+                return None;
+            }
+            _ => {}
+        }
+        self.last = Some((now, time));
+        let Some((started, start_time)) = self.anchor else {
+            self.anchor = Some((now, time));
+            return None;
+        };
+        let elapsed = now.saturating_sub(started);
+        if elapsed < WINDOW {
+            return None;
+        }
+        self.anchor = Some((now, time));
+        let rate = time.saturating_sub(start_time).as_secs_f64() / elapsed.as_secs_f64();
+        let drifting = (rate - 1.0).abs() > TOLERANCE;
+        let percent = (rate * 100.0).round().clamp(0.0, f64::from(u16::MAX));
+        match (self.drifting, drifting) {
+            (false, true) => {
+                log::warn!(
+                    "the game timer ran at {:.0}% of real time over the last {:.0} seconds ({})",
+                    percent,
+                    elapsed.as_secs_f64(),
+                    if rate < 1.0 {
+                        "is the game lagging?"
+                    } else {
+                        "is the game sped up, or its frame counter modded?"
+                    }
+                );
+            }
+            (true, false) => log::info!("the game timer is back to real time"),
+            _ => {}
+        }
+        self.drifting = drifting;
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)] // clamped above
+        drifting.then_some(percent as u16)
+    }
+}
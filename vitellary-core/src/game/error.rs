@@ -0,0 +1,101 @@
+use read_process_memory::Pid;
+use thiserror::Error;
+
+/// Why we can't read the game's memory, for the failures a user can do something about.
+#[derive(Debug, Error)]
+pub enum AccessError {
+    /// Yama's `ptrace_scope` only lets us read the memory of our own children (1) or only lets
+    /// privileged processes read other processes' memory (2), and we're not privileged.
+    #[error("kernel.yama.ptrace_scope is {scope}, which stops us reading VVVVVV (pid {pid})")]
+    PtraceScope { pid: Pid, scope: u32 },
+    /// Yama's `ptrace_scope` is 3, so no process may read another's memory until a reboot.
+    #[error("kernel.yama.ptrace_scope is 3, which stops every process reading another's memory")]
+    PtraceDisabled { pid: Pid },
+    /// Reading was denied for some other reason, e.g. VVVVVV runs as another user.
+    #[error("permission denied reading the memory of VVVVVV (pid {pid})")]
+    PermissionDenied { pid: Pid },
+    /// `task_for_pid` failed and we're not root.
+    #[error("failed to get a Mach task port for VVVVVV (pid {pid}) without root")]
+    TaskForPid { pid: Pid },
+    /// `task_for_pid` failed even though we're root, which System Integrity Protection or the
+    /// hardened runtime causes.
+    #[error("failed to get a Mach task port for VVVVVV (pid {pid}), even as root")]
+    Protected { pid: Pid },
+    /// The game exited.
+    #[error("VVVVVV (pid {pid}) exited")]
+    ProcessGone { pid: Pid },
+}
+
+impl AccessError {
+    /// Work out why reading the memory of `pid` was denied.
+    pub fn denied(pid: Pid) -> AccessError {
+        // SAFETY: geteuid can't fail.
+        let root = unsafe { libc::geteuid() } == 0;
+        if cfg!(target_os = "macos") {
+            return if root {
+                AccessError::Protected { pid }
+            } else {
+                AccessError::TaskForPid { pid }
+            };
+        }
+        match std::fs::read_to_string("/proc/sys/kernel/yama/ptrace_scope")
+            .ok()
+            .and_then(|scope| scope.trim().parse().ok())
+        {
+            Some(3) => AccessError::PtraceDisabled { pid },
+            Some(scope @ (1 | 2)) if !root && !has_ptrace_capability() => {
+                AccessError::PtraceScope { pid, scope }
+            }
+            _ => AccessError::PermissionDenied { pid },
+        }
+    }
+
+    /// What the user can do about it.
+    pub fn hint(&self) -> &'static str {
+        match self {
+            AccessError::PtraceScope { scope: 1, .. } => {
+                "install the memory reader helper once with `sudo vitellary install-helper` (it \
+                 then asks for your password through polkit), give vitellary CAP_SYS_PTRACE \
+                 (sudo setcap cap_sys_ptrace=ep PATH), or allow reading any of your processes \
+                 with `sudo sysctl kernel.yama.ptrace_scope=0`"
+            }
+            AccessError::PtraceScope { .. } => {
+                "install the memory reader helper once with `sudo vitellary install-helper`, or \
+                 give vitellary CAP_SYS_PTRACE (sudo setcap cap_sys_ptrace=ep PATH)"
+            }
+            AccessError::PtraceDisabled { .. } => {
+                "this can't be changed until the next reboot; set kernel.yama.ptrace_scope to 2 \
+                 or lower in sysctl.conf"
+            }
+            AccessError::PermissionDenied { .. } => {
+                "VVVVVV may be running as another user or in a sandbox (like Flatpak); try \
+                 running vitellary as root"
+            }
+            AccessError::TaskForPid { .. } => {
+                "task_for_pid needs root on macOS; install the memory reader helper once with \
+                 `sudo vitellary install-helper`"
+            }
+            AccessError::Protected { .. } => {
+                "macOS denies task_for_pid even to root for apps under System Integrity \
+                 Protection or signed with the hardened runtime; re-sign VVVVVV without it (e.g. \
+                 `codesign --force --sign - VVVVVV.app`)"
+            }
+            AccessError::ProcessGone { .. } => "start VVVVVV again, then vitellary",
+        }
+    }
+}
+
+/// Whether we have `CAP_SYS_PTRACE`, going by `CapEff` in `/proc/self/status`.
+fn has_ptrace_capability() -> bool {
+    const CAP_SYS_PTRACE: u64 = 1 << 19;
+
+    std::fs::read_to_string("/proc/self/status")
+        .ok()
+        .and_then(|status| {
+            let caps = status
+                .lines()
+                .find_map(|line| line.strip_prefix("CapEff:"))?;
+            u64::from_str_radix(caps.trim(), 16).ok()
+        })
+        .is_some_and(|caps| caps & CAP_SYS_PTRACE != 0)
+}
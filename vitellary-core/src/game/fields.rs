@@ -0,0 +1,183 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// The game's speed at full speed, in the units of its `slowdown` setting.
+pub const FULL_SPEED: u32 = 30;
+
+/// Offsets of fields past the ones every build shares, from the start of the game object. Their
+/// offsets differ between compilers and standard libraries (see `common::GameObject`), so they're
+/// only read for builds whose fingerprint record maps them. Fields of other globals (like `map`)
+/// can be given too, since globals sit at fixed distances from each other in the image; those
+/// before the game object have negative offsets.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Fields {
+    /// `game.slowdown`, the game speed from the accessibility options: 30 at full speed, down
+    /// to 12.
+    pub slowdown: Option<isize>,
+    /// `map.invincibility`, from the accessibility options.
+    pub invincibility: Option<isize>,
+    /// `game.glitchrunnermode`, which brings back glitches from 2.2 that some categories allow.
+    pub glitchrunner: Option<isize>,
+    /// `game.showingametimer`, whether the in-game timer is drawn.
+    pub ingame_timer: Option<isize>,
+    /// `graphics.flipmode`, which draws the game upside down.
+    pub flip_mode: Option<isize>,
+    /// `game.timetriallevel`, which time trial is being played.
+    pub time_trial_level: Option<isize>,
+    /// `game.timetrialresulttime`, the first of the time trial results the game keeps.
+    pub time_trial_results: Option<isize>,
+    /// `game.swnmode`, whether the player is in the Super Gravitron.
+    pub gravitron_mode: Option<isize>,
+    /// `game.swntimer`, the frames survived in the current Super Gravitron attempt.
+    pub gravitron_timer: Option<isize>,
+    /// `game.swnrecord`, the best Super Gravitron time in frames.
+    pub gravitron_record: Option<isize>,
+    /// `game.swngame`, which gravitron is running: the campaign's, whose `swntimer` counts down
+    /// the time left to survive, or the Super Gravitron.
+    pub gravitron_game: Option<isize>,
+    /// `game.tele_gametime`, the first of the telesave's summary fields (its game time, trinkets,
+    /// and area).
+    pub telesave: Option<isize>,
+    /// `game.quick_gametime`, the first of the quicksave's summary fields.
+    pub quicksave: Option<isize>,
+    /// The frames per second the game timer counts, in builds that run their logic faster than
+    /// 30 FPS and keep the rate in a field.
+    pub frame_rate: Option<isize>,
+    /// `map.custommode`, whether a custom level is being played rather than the campaign.
+    pub custom_mode: Option<isize>,
+    /// `game.customlevelfilename`, the file the custom level being played was loaded from (e.g.
+    /// `levels/333.vvvvvv`), whose name picks its split profile.
+    pub custom_level: Option<isize>,
+}
+
+impl FromStr for Fields {
+    type Err = String;
+
+    /// Parse comma-separated `FIELD=OFFSET` pairs, with offsets in hexadecimal.
+    fn from_str(s: &str) -> Result<Fields, String> {
+        let mut fields = Fields::default();
+        for pair in s.split(',') {
+            let (field, offset) = pair
+                .split_once('=')
+                .ok_or_else(|| format!("expected FIELD=OFFSET, got {:?}", pair))?;
+            let offset = offset.trim();
+            let (negative, magnitude) = match offset.strip_prefix('-') {
+                Some(magnitude) => (true, magnitude),
+                None => (false, offset),
+            };
+            let magnitude = isize::from_str_radix(
+                magnitude.trim_start_matches("0x").replace('_', "").as_str(),
+                16,
+            )
+            .map_err(|_| format!("invalid hexadecimal offset {:?}", offset))?;
+            let offset = Some(if negative { -magnitude } else { magnitude });
+            match field.trim() {
+                "slowdown" => fields.slowdown = offset,
+                "invincibility" => fields.invincibility = offset,
+                "glitchrunner" => fields.glitchrunner = offset,
+                "ingame-timer" => fields.ingame_timer = offset,
+                "flip-mode" => fields.flip_mode = offset,
+                "time-trial-level" => fields.time_trial_level = offset,
+                "time-trial-results" => fields.time_trial_results = offset,
+                "gravitron-mode" => fields.gravitron_mode = offset,
+                "gravitron-timer" => fields.gravitron_timer = offset,
+                "gravitron-record" => fields.gravitron_record = offset,
+                "gravitron-game" => fields.gravitron_game = offset,
+                "telesave" => fields.telesave = offset,
+                "quicksave" => fields.quicksave = offset,
+                "frame-rate" => fields.frame_rate = offset,
+                "custom-mode" => fields.custom_mode = offset,
+                "custom-level" => fields.custom_level = offset,
+                _ => {
+                    return Err(format!(
+                        "unknown field {:?} (expected slowdown, invincibility, glitchrunner, \
+                         ingame-timer, flip-mode, time-trial-level, time-trial-results, \
+                         gravitron-mode, gravitron-timer, gravitron-record, gravitron-game, \
+                         telesave, quicksave, frame-rate, custom-mode, or custom-level)",
+                        field
+                    ))
+                }
+            }
+        }
+        Ok(fields)
+    }
+}
+
+/// The game's settings that a category's rules care about, as far as the build's fields let us
+/// read them. Settings we can't read are `None`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Settings {
+    /// The game speed (see [`FULL_SPEED`]).
+    pub slowdown: Option<u32>,
+    pub invincibility: Option<bool>,
+    pub glitchrunner: Option<bool>,
+    pub ingame_timer: Option<bool>,
+    pub flip_mode: Option<bool>,
+}
+
+/// A setting that keeps a run off the leaderboards.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Setting {
+    Invincibility,
+    /// The game running below full speed.
+    Slowdown,
+}
+
+/// The leaderboard a full-game run belongs to, as far as the game's settings decide it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Category {
+    #[serde(rename = "any%")]
+    AnyPercent,
+    /// Any% played with flip mode on, which has its own leaderboard.
+    FlipMode,
+}
+
+impl Category {
+    /// Every category there's a preset for.
+    pub const ALL: [Category; 2] = [Category::AnyPercent, Category::FlipMode];
+
+    /// The name of this category's preset, which `--category` takes.
+    pub fn preset(self) -> &'static str {
+        match self {
+            Category::AnyPercent => "any%",
+            Category::FlipMode => "flip-mode",
+        }
+    }
+}
+
+impl FromStr for Category {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Category, String> {
+        Category::ALL
+            .into_iter()
+            .find(|category| category.preset() == s)
+            .ok_or_else(|| format!("unknown category {:?} (expected any% or flip-mode)", s))
+    }
+}
+
+impl fmt::Display for Category {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Category::AnyPercent => "any%",
+            Category::FlipMode => "flip mode",
+        })
+    }
+}
+
+impl Settings {
+    /// The category these settings make a run, if flip mode can be read.
+    pub fn category(&self) -> Option<Category> {
+        self.flip_mode.map(|flip_mode| {
+            if flip_mode {
+                Category::FlipMode
+            } else {
+                Category::AnyPercent
+            }
+        })
+    }
+}
@@ -0,0 +1,294 @@
+use crate::game::{Fields, Gamestates};
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use std::fmt::{self, Write};
+use std::path::Path;
+
+const BUILTIN: &str = include_str!("fingerprints.toml");
+
+/// Known builds of the game, by executable fingerprint.
+#[derive(Debug, Default, Deserialize)]
+pub struct Fingerprints {
+    builds: Vec<Build>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Build {
+    fingerprint: String,
+    pub name: String,
+    /// Offset of the game object from the start of the executable image.
+    pub offset: usize,
+    layout: String,
+    /// The gamestates a modded build uses, if not the game's own.
+    pub gamestates: Option<Gamestates>,
+    /// Where the fields past the shared layout are, if anyone has worked them out.
+    #[serde(default)]
+    pub fields: Fields,
+    /// Architecture the build was compiled for, as named by `std::env::consts::ARCH`.
+    #[serde(default = "default_arch")]
+    arch: String,
+}
+
+fn default_arch() -> String {
+    "x86_64".into()
+}
+
+/// An executable's fingerprint and the architecture it was compiled for.
+#[derive(Debug)]
+pub struct Fingerprint {
+    id: String,
+    pub arch: &'static str,
+}
+
+impl Fingerprint {
+    /// The ID `fingerprints.toml` records use.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+}
+
+impl fmt::Display for Fingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} on {}", self.id, self.arch)
+    }
+}
+
+impl Fingerprints {
+    /// Load the built-in fingerprints, preceded by any in `fingerprints.toml` in `config_dir`.
+    pub fn load(config_dir: Option<&Path>) -> Result<Fingerprints> {
+        let mut fingerprints = Fingerprints::default();
+        if let Some(path) = config_dir.map(|dir| dir.join("fingerprints.toml")) {
+            if path.exists() {
+                let data = std::fs::read_to_string(&path)
+                    .with_context(|| format!("failed to read {}", path.display()))?;
+                fingerprints.parse(&data, &path)?;
+            }
+        }
+        fingerprints.parse(BUILTIN, Path::new("built-in fingerprints"))?;
+        Ok(fingerprints)
+    }
+
+    fn parse(&mut self, data: &str, path: &Path) -> Result<()> {
+        let parsed: Fingerprints =
+            toml::from_str(data).with_context(|| format!("failed to parse {}", path.display()))?;
+        for build in &parsed.builds {
+            if build.layout != "2.3" {
+                bail!(
+                    "{}: unknown layout {:?} for {}",
+                    path.display(),
+                    build.layout,
+                    build.name
+                );
+            }
+        }
+        self.builds.extend(parsed.builds);
+        Ok(())
+    }
+
+    /// Find the record for `fingerprint`, or else the fallback for its kind. Records for another
+    /// architecture are skipped, since their offsets would be wrong even if the ID somehow
+    /// matched.
+    pub fn lookup(&self, fingerprint: &Fingerprint) -> Option<&Build> {
+        let kind = fingerprint.id.split_once(':').map_or("", |(kind, _)| kind);
+        let find = |id: &str| {
+            self.builds.iter().find(|build| {
+                build.fingerprint.eq_ignore_ascii_case(id) && build.arch == fingerprint.arch
+            })
+        };
+        find(&fingerprint.id).or_else(|| find(&format!("{}:", kind)))
+    }
+}
+
+impl Build {
+    /// Whether this is the fallback for a kind of fingerprint, rather than a known build.
+    pub fn is_fallback(&self) -> bool {
+        self.fingerprint.ends_with(':')
+    }
+}
+
+/// Fingerprint an executable by its ELF build ID, Mach-O UUID, or (for Windows builds) PE
+/// timestamp and image size.
+pub fn fingerprint(executable: &Path) -> Result<Option<Fingerprint>> {
+    let data = std::fs::read(executable)
+        .with_context(|| format!("failed to read {}", executable.display()))?;
+    Ok(if data.starts_with(b"\x7fELF") {
+        elf_build_id(&data).and_then(|id| {
+            Some(Fingerprint {
+                id: format!("elf:{}", hex(id)),
+                arch: elf_arch(&data)?,
+            })
+        })
+    } else if data.starts_with(b"MZ") {
+        pe_fingerprint(&data)
+    } else {
+        macho_uuid(&data).map(|uuid| Fingerprint {
+            id: format!("macho:{}", hex(uuid)),
+            arch: std::env::consts::ARCH,
+        })
+    })
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().fold(String::new(), |mut s, byte| {
+        write!(s, "{:02x}", byte).unwrap();
+        s
+    })
+}
+
+fn u16_le(data: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(
+        data.get(at..at.checked_add(2)?)?.try_into().ok()?,
+    ))
+}
+
+fn u32_le(data: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(
+        data.get(at..at.checked_add(4)?)?.try_into().ok()?,
+    ))
+}
+
+fn u32_be(data: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(
+        data.get(at..at.checked_add(4)?)?.try_into().ok()?,
+    ))
+}
+
+fn usize_le(data: &[u8], at: usize) -> Option<usize> {
+    usize::try_from(u64::from_le_bytes(
+        data.get(at..at.checked_add(8)?)?.try_into().ok()?,
+    ))
+    .ok()
+}
+
+/// The architecture of a little-endian ELF file, from its `e_machine`.
+fn elf_arch(data: &[u8]) -> Option<&'static str> {
+    Some(match u16_le(data, 0x12)? {
+        0x03 => "x86",
+        0x28 => "arm",
+        0x3e => "x86_64",
+        0xb7 => "aarch64",
+        _ => return None,
+    })
+}
+
+/// Find the `NT_GNU_BUILD_ID` note of a little-endian ELF file. Both classes are handled, since
+/// 32-bit ARM builds are still common on Raspberry Pi OS.
+fn elf_build_id(data: &[u8]) -> Option<&[u8]> {
+    const PT_NOTE: u32 = 4;
+    const NT_GNU_BUILD_ID: u32 = 3;
+
+    // Offsets of e_phoff, e_phentsize, p_offset and p_filesz, and the reader for address-sized
+    // fields, for each ELF class.
+    let (phoff, phentsize, p_offset, p_filesz, word): (_, _, _, _, fn(&[u8], usize) -> _) =
+        match data.get(4..6)? {
+            [1, 1] => (0x1c, 0x2a, 4, 0x10, |data, at| {
+                u32_le(data, at).map(|x| x as usize)
+            }),
+            [2, 1] => (0x20, 0x36, 8, 0x20, usize_le),
+            _ => return None,
+        };
+    let phnum = usize::from(u16_le(data, phentsize + 2)?);
+    let phentsize = usize::from(u16_le(data, phentsize)?);
+    let phoff = word(data, phoff)?;
+    (0..phnum).find_map(|i| {
+        // The sizes and offsets come from the file, so none of the arithmetic on them may
+        // overflow.
+        let phdr = phoff.checked_add(i.checked_mul(phentsize)?)?;
+        if u32_le(data, phdr)? != PT_NOTE {
+            return None;
+        }
+        let offset = word(data, phdr.checked_add(p_offset)?)?;
+        let size = word(data, phdr.checked_add(p_filesz)?)?;
+        let notes = data.get(offset..offset.checked_add(size)?)?;
+        let mut at = 0;
+        while at + 12 <= notes.len() {
+            let namesz = u32_le(notes, at)? as usize;
+            let descsz = u32_le(notes, at + 4)? as usize;
+            let kind = u32_le(notes, at + 8)?;
+            let name = at + 12;
+            let desc = name.checked_add(namesz.checked_next_multiple_of(4)?)?;
+            if kind == NT_GNU_BUILD_ID && notes.get(name..name.checked_add(namesz)?)? == b"GNU\0" {
+                return notes.get(desc..desc.checked_add(descsz)?);
+            }
+            at = desc.checked_add(descsz.checked_next_multiple_of(4)?)?;
+        }
+        None
+    })
+}
+
+/// The key symbol servers use for a PE file: its link timestamp and image size.
+fn pe_fingerprint(data: &[u8]) -> Option<Fingerprint> {
+    let pe = u32_le(data, 0x3c)? as usize;
+    if data.get(pe..pe.checked_add(4)?)? != b"PE\0\0" {
+        return None;
+    }
+    let arch = match u16_le(data, pe.checked_add(4)?)? {
+        0x014c => "x86",
+        0x8664 => "x86_64",
+        _ => return None,
+    };
+    let timestamp = u32_le(data, pe.checked_add(8)?)?;
+    // SizeOfImage is at the same offset in the PE32 and PE32+ optional headers.
+    let size_of_image = u32_le(data, pe.checked_add(24 + 56)?)?;
+    Some(Fingerprint {
+        id: format!("pe:{:08X}{:x}", timestamp, size_of_image),
+        arch,
+    })
+}
+
+/// Find the `LC_UUID` load command of a 64-bit Mach-O file, picking the slice for this machine's
+/// architecture out of universal binaries.
+fn macho_uuid(data: &[u8]) -> Option<&[u8]> {
+    const FAT_MAGIC: u32 = 0xcafe_babe;
+    const MH_MAGIC_64: u32 = 0xfeed_facf;
+    const LC_UUID: u32 = 0x1b;
+    const CPU_TYPE: u32 = if cfg!(target_arch = "aarch64") {
+        0x0100_000c
+    } else {
+        0x0100_0007
+    };
+
+    let data = if u32_be(data, 0)? == FAT_MAGIC {
+        (0..u32_be(data, 4)? as usize).find_map(|i| {
+            let arch = 8 + i * 20;
+            if u32_be(data, arch)? != CPU_TYPE {
+                return None;
+            }
+            let offset = u32_be(data, arch + 8)? as usize;
+            data.get(offset..offset.checked_add(u32_be(data, arch + 12)? as usize)?)
+        })?
+    } else {
+        data
+    };
+    if u32_le(data, 0)? != MH_MAGIC_64 {
+        return None;
+    }
+    let ncmds = u32_le(data, 16)?;
+    let mut at = 32;
+    for _ in 0..ncmds {
+        let cmd = u32_le(data, at)?;
+        if cmd == LC_UUID {
+            return data.get(at + 8..at + 24);
+        }
+        at += u32_le(data, at + 4)? as usize;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn falls_back_on_the_release_layout() {
+        let fingerprints = Fingerprints::load(None).unwrap();
+        let unknown = |arch| Fingerprint {
+            id: "elf:0123456789abcdef".into(),
+            arch,
+        };
+        let build = fingerprints.lookup(&unknown("x86_64")).unwrap();
+        assert!(build.is_fallback());
+        assert_eq!(build.offset, 0x0045_4dc0);
+        assert!(fingerprints.lookup(&unknown("aarch64")).is_none());
+    }
+}
@@ -0,0 +1,56 @@
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+/// The `gamestate` values a build uses. Forks and mods add gamemodes of their own, which would
+/// otherwise look like leaving the game (resetting the run) or like a bad read.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Gamestates {
+    /// The gamestates a run is live in. Leaving them for any other resets the run (or pauses it,
+    /// with a reset grace period), and coming back with the timer at zero starts a new one.
+    pub playing: Vec<u32>,
+    /// The highest gamestate the build uses; reads above it are taken as a sign we're looking in
+    /// the wrong place.
+    pub max: u32,
+}
+
+impl Default for Gamestates {
+    /// The game's own: `GAMEMODE`, `MAPMODE` and `TELEPORTERMODE` are playing, up to `PRELOADER`.
+    fn default() -> Gamestates {
+        Gamestates {
+            playing: vec![0, 4, 5],
+            max: 9,
+        }
+    }
+}
+
+impl Gamestates {
+    pub fn is_playing(&self, gamestate: u32) -> bool {
+        self.playing.contains(&gamestate)
+    }
+}
+
+impl FromStr for Gamestates {
+    type Err = String;
+
+    /// Parse a comma-separated list of playing gamestates. The highest gamestate is the game's
+    /// own, or the highest of these if that's higher.
+    fn from_str(s: &str) -> Result<Gamestates, String> {
+        let playing = s
+            .split(',')
+            .map(|gamestate| {
+                gamestate
+                    .trim()
+                    .parse()
+                    .map_err(|_| format!("invalid gamestate {:?}", gamestate))
+            })
+            .collect::<Result<Vec<u32>, _>>()?;
+        let max = playing
+            .iter()
+            .copied()
+            .chain([Gamestates::default().max])
+            .max()
+            .unwrap_or_default();
+        Ok(Gamestates { playing, max })
+    }
+}
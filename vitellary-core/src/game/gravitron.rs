@@ -0,0 +1,91 @@
+use serde::Serialize;
+use std::time::Duration;
+
+/// `swngame` while the campaign's gravitron is running, where `swntimer` counts down the time left
+/// to survive instead of up.
+const COUNTDOWN: u32 = 0;
+
+/// A Super Gravitron attempt that just ended.
+#[derive(Debug, Clone, Serialize)]
+pub struct Gravitron {
+    /// How long the player survived.
+    #[serde(serialize_with = "super::serialize_secs")]
+    pub survived: Duration,
+    /// The game's best time after the attempt, if the build's fields map it.
+    #[serde(serialize_with = "serialize_optional_secs")]
+    pub record: Option<Duration>,
+    /// Whether the attempt beat the game's best time, if it can be read.
+    pub personal_best: Option<bool>,
+}
+
+#[allow(clippy::ref_option)] // required by serde
+pub(super) fn serialize_optional_secs<S: serde::Serializer>(
+    time: &Option<Duration>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match time {
+        Some(time) => serializer.serialize_some(&time.as_secs_f64()),
+        None => serializer.serialize_none(),
+    }
+}
+
+/// What we've seen of the Super Gravitron since entering it.
+#[derive(Debug, Clone, Default)]
+pub(super) struct Tracker {
+    /// Whether the player is in the Super Gravitron.
+    active: bool,
+    /// `swntimer` at the last update.
+    timer: u32,
+    /// `swnrecord` when the current attempt started.
+    record: Option<u32>,
+    /// `swntimer` while the campaign's gravitron is counting down.
+    countdown: Option<u32>,
+    /// The frames per second the timers count, as for the game timer.
+    frame_rate: u32,
+}
+
+impl Tracker {
+    /// Follow `swnmode`, `swngame`, `swntimer` and `swnrecord` (the last two if known), counting
+    /// `frame_rate` frames a second, returning the Super Gravitron attempt that ended, if one did:
+    /// the player died (and the timer went back to zero) or left. The campaign's gravitron isn't
+    /// an attempt; only its countdown is kept.
+    pub(super) fn update(
+        &mut self,
+        mode: bool,
+        game: Option<u32>,
+        timer: u32,
+        record: Option<u32>,
+        frame_rate: u32,
+    ) -> Option<Gravitron> {
+        self.frame_rate = frame_rate;
+        self.countdown = (mode && game == Some(COUNTDOWN)).then_some(timer);
+        let mode = mode && self.countdown.is_none();
+        let ended = self.active && (!mode || timer < self.timer);
+        let attempt = ended.then(|| {
+            let survived = self.to_duration(self.timer);
+            Gravitron {
+                survived,
+                record: record.map(|record| self.to_duration(record)),
+                personal_best: record
+                    .zip(self.record)
+                    .map(|(record, before)| record > before),
+            }
+        });
+        if mode && (!self.active || ended) {
+            log::debug!("super gravitron attempt started");
+            self.record = record;
+        }
+        self.active = mode;
+        self.timer = timer;
+        attempt
+    }
+
+    /// The time left to survive in the campaign's gravitron, while it's running.
+    pub(super) fn remaining(&self) -> Option<Duration> {
+        self.countdown.map(|frames| self.to_duration(frames))
+    }
+
+    fn to_duration(&self, frames: u32) -> Duration {
+        Duration::from_secs(u64::from(frames)) / self.frame_rate.max(1)
+    }
+}
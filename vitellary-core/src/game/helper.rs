@@ -0,0 +1,206 @@
+//! A privileged helper that reads the game's memory for us, so vitellary itself doesn't need to
+//! run as root. The helper (`vitellary-helper PID [OFFSET]`) attaches to the game and answers
+//! requests for reads near the game object, one per line on stdin:
+//!
+//! ```text
+//! OFFSET LEN
+//! ```
+//!
+//! with the bytes in hex, or `error: MESSAGE`, one per line on stdout. Its first line is the game
+//! object's address in hex, or an error if it couldn't attach.
+
+use crate::game::backend::Backend;
+use crate::game::common::{self, Timer, GAME_OBJECT_SIZE};
+use crate::game::{imp, process_exists, State};
+use anyhow::{bail, Context, Result};
+use read_process_memory::Pid;
+use std::fmt::Write as _;
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+use std::sync::Mutex;
+
+/// How far from the game object the helper reads, so it can't be used to read the rest of the
+/// game's memory.
+pub(super) const MAX_DISTANCE: isize = 0x1_0000;
+
+/// Serve reads of the game object of `pid` over stdin and stdout until stdin closes, if the user
+/// running us may read it. `offset` is where the game object is from the start of the
+/// executable, for builds whose fingerprint is known.
+pub fn serve(pid: Pid, offset: Option<usize>) -> Result<()> {
+    let mut stdout = std::io::stdout().lock();
+    let handle = match check(pid).and_then(|()| imp::find_game_object(pid, None, offset)) {
+        Ok(handle) => handle,
+        Err(err) => {
+            writeln!(stdout, "error: {:#}", err)?;
+            return Err(err);
+        }
+    };
+    writeln!(stdout, "{:x}", imp::address(&handle))?;
+    stdout.flush()?;
+    for line in std::io::stdin().lock().lines() {
+        let line = line?;
+        match read(&handle, &line) {
+            Ok(data) => writeln!(stdout, "{}", data)?,
+            Err(err) => writeln!(stdout, "error: {:#}", err)?,
+        }
+        stdout.flush()?;
+    }
+    Ok(())
+}
+
+/// Refuse to read processes that aren't VVVVVV, or that the user running us doesn't own. Under
+/// `pkexec` that's the user who ran it, not root.
+fn check(pid: Pid) -> Result<()> {
+    let path = imp::executable_path(pid).context("could not find the executable")?;
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    if !name.contains("vvvvvv") {
+        bail!("pid {} is {}, not VVVVVV", pid, path.display());
+    }
+    // SAFETY: getuid can't fail.
+    let mut caller = unsafe { libc::getuid() };
+    if caller == 0 {
+        if let Some(uid) = std::env::var("PKEXEC_UID")
+            .ok()
+            .and_then(|uid| uid.parse().ok())
+        {
+            caller = uid;
+        }
+    }
+    let owner = owner(pid)?;
+    if caller != 0 && caller != owner {
+        bail!("pid {} belongs to another user", pid);
+    }
+    Ok(())
+}
+
+/// The user owning `pid`.
+#[cfg(target_os = "linux")]
+fn owner(pid: Pid) -> Result<u32> {
+    use std::os::unix::fs::MetadataExt;
+    Ok(std::fs::metadata(format!("/proc/{}", pid))
+        .with_context(|| format!("could not find the owner of pid {}", pid))?
+        .uid())
+}
+
+/// The user owning `pid`. This runs `ps` by its full path, since we may be setuid root and the
+/// `PATH` is the caller's.
+#[cfg(target_os = "macos")]
+fn owner(pid: Pid) -> Result<u32> {
+    let output = Command::new("/bin/ps")
+        .args(["-o", "uid=", "-p", &pid.to_string()])
+        .output()
+        .context("failed to run ps")?;
+    String::from_utf8_lossy(&output.stdout)
+        .trim()
+        .parse()
+        .with_context(|| format!("could not find the owner of pid {}", pid))
+}
+
+/// Carry out a request.
+fn read(handle: &imp::Handle, request: &str) -> Result<String> {
+    let (offset, len) = request.split_once(' ').context("expected OFFSET LEN")?;
+    let offset: isize = offset.parse().context("invalid offset")?;
+    let len: usize = len.parse().context("invalid length")?;
+    let end = isize::try_from(len)
+        .ok()
+        .and_then(|len| offset.checked_add(len));
+    if offset < -MAX_DISTANCE || end.is_none_or(|end| end > MAX_DISTANCE) {
+        bail!("refusing to read that far from the game object");
+    }
+    let mut buf = vec![0; len];
+    imp::read(handle, offset, &mut buf)?;
+    let mut hex = String::with_capacity(len * 2);
+    for byte in buf {
+        let _ = write!(hex, "{:02x}", byte);
+    }
+    Ok(hex)
+}
+
+/// The game, read through a helper.
+pub(super) struct Helper {
+    pid: Pid,
+    addr: usize,
+    pipes: Mutex<(ChildStdin, BufReader<ChildStdout>)>,
+    _child: Child,
+}
+
+impl Helper {
+    /// Run `helper` for `pid`, passing the game object's offset from the start of the executable
+    /// if it's known.
+    pub(super) fn spawn(mut helper: Command, pid: Pid, offset: Option<usize>) -> Result<Helper> {
+        helper.arg(pid.to_string());
+        if let Some(offset) = offset {
+            helper.arg(format!("{:#x}", offset));
+        }
+        let mut child = helper
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .with_context(|| format!("failed to run {}", helper.get_program().to_string_lossy()))?;
+        let stdin = child.stdin.take().context("helper has no stdin")?;
+        let mut stdout = BufReader::new(child.stdout.take().context("helper has no stdout")?);
+        let addr = usize::from_str_radix(&response(&mut stdout)?, 16)
+            .context("helper sent an invalid address")?;
+        Ok(Helper {
+            pid,
+            addr,
+            pipes: Mutex::new((stdin, stdout)),
+            _child: child,
+        })
+    }
+
+    fn read(&self, offset: isize, len: usize) -> Result<Vec<u8>> {
+        let mut pipes = self.pipes.lock().unwrap();
+        let (stdin, stdout) = &mut *pipes;
+        writeln!(stdin, "{} {}", offset, len).context("helper went away")?;
+        let hex = response(stdout)?;
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| {
+                hex.get(i..i + 2)
+                    .and_then(|byte| u8::from_str_radix(byte, 16).ok())
+                    .context("helper sent invalid data")
+            })
+            .collect()
+    }
+}
+
+/// Read one response from the helper.
+fn response(stdout: &mut impl BufRead) -> Result<String> {
+    let mut line = String::new();
+    if stdout.read_line(&mut line).context("helper went away")? == 0 {
+        bail!("helper exited");
+    }
+    let line = line.trim_end();
+    if let Some(err) = line.strip_prefix("error: ") {
+        bail!("helper: {}", err);
+    }
+    Ok(line.to_owned())
+}
+
+impl Backend for Helper {
+    fn address(&self) -> usize {
+        self.addr
+    }
+
+    fn read_game_object(&self) -> Result<(State, Timer<u32>)> {
+        let data = self.read(0, GAME_OBJECT_SIZE)?;
+        Ok(common::decode(&data)
+            .context("helper sent a short game object")?
+            .into_state())
+    }
+
+    fn read_u32(&self, offset: isize) -> Result<u32> {
+        let data = self.read(offset, 4)?;
+        Ok(u32::from_ne_bytes(data.try_into().map_err(|_| {
+            anyhow::anyhow!("helper sent a short field")
+        })?))
+    }
+
+    fn exited(&self) -> bool {
+        !process_exists(self.pid)
+    }
+}
@@ -0,0 +1,98 @@
+use crate::game::serialize_secs;
+use serde::Serialize;
+use std::time::Duration;
+
+/// How far into a logic frame the time can be taken, so it never reaches the next frame's.
+const MAX_FRACTION: f64 = 0.95;
+
+/// When the game's current logic frame started, so clients can interpolate the time between
+/// frames themselves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Frame {
+    /// Frames since the game timer was at zero.
+    pub index: u64,
+    /// How many frames make a second of game time.
+    pub rate: u32,
+    /// When the frame was first read, by the clock of [`Timestamp::monotonic`].
+    ///
+    /// [`Timestamp::monotonic`]: crate::game::Timestamp::monotonic
+    #[serde(serialize_with = "serialize_secs")]
+    pub started: Duration,
+    /// The measured wall-clock time between frames, once known.
+    #[serde(serialize_with = "serialize_secs_opt")]
+    pub timestep: Option<Duration>,
+}
+
+#[allow(clippy::ref_option)] // required by serde
+fn serialize_secs_opt<S: serde::Serializer>(
+    time: &Option<Duration>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match time {
+        Some(time) => serialize_secs(time, serializer),
+        None => serializer.serialize_none(),
+    }
+}
+
+/// Estimates how far the game is into its current logic frame. VVVVVV 2.3 can draw above 30 FPS,
+/// but its logic (and the timer) only steps every 34 ms at full speed, and less often when slowed
+/// down; mods may step faster. Rather than read the timestep, it's measured from how often the
+/// frame counter changes.
+#[derive(Debug, Default)]
+pub(super) struct Interpolation {
+    /// The game time last read and when it was first seen.
+    last: Option<(Duration, Duration)>,
+    /// The measured wall-clock time between logic frames.
+    timestep: Option<Duration>,
+}
+
+impl Interpolation {
+    /// Follow the game timer reading `time` at `now` (by a monotonic clock) while `playing`, with
+    /// each frame counting for `frame` of game time, returning how much game time has passed
+    /// since the last frame.
+    pub(super) fn update(
+        &mut self,
+        now: Duration,
+        time: Duration,
+        frame: Duration,
+        playing: bool,
+    ) -> Duration {
+        if !playing {
+            self.last = None;
+            return Duration::ZERO;
+        }
+        match self.last {
+            Some((last, at)) if time == last => {
+                let Some(timestep) = self.timestep else {
+                    return Duration::ZERO;
+                };
+                // A frame that's taking longer than usual (like when the game lags) holds just
+                // short of the next, so the time never goes backwards.
+                let fraction = now.saturating_sub(at).as_secs_f64() / timestep.as_secs_f64();
+                return frame.mul_f64(fraction.min(MAX_FRACTION));
+            }
+            Some((last, at)) if time.checked_sub(last) == Some(frame) => {
+                let sample = now.saturating_sub(at);
+                self.timestep = Some(
+                    self.timestep
+                        .map_or(sample, |timestep| (timestep * 7 + sample) / 8),
+                );
+            }
+            _ => {}
+        }
+        self.last = Some((time, now));
+        Duration::ZERO
+    }
+
+    /// The frame the game is on, with each counting for `frame` of game time.
+    pub(super) fn frame(&self, frame: Duration) -> Option<Frame> {
+        let (time, started) = self.last?;
+        let rate = u32::try_from(Duration::from_secs(1).as_nanos() / frame.as_nanos()).ok()?;
+        Some(Frame {
+            index: u64::try_from(time.as_nanos() / frame.as_nanos()).ok()?,
+            rate,
+            started,
+            timestep: self.timestep,
+        })
+    }
+}
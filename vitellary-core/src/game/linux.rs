@@ -0,0 +1,104 @@
+#![cfg(target_os = "linux")]
+
+use crate::game::common::{field_address, GameObject, Timer, GAME_OBJECT_SIZE};
+use crate::game::State;
+use anyhow::{bail, Context, Result};
+use read_process_memory::{CopyAddress, Pid, ProcessHandle};
+use std::ops::Range;
+use std::path::PathBuf;
+
+pub(super) struct Handle {
+    process: ProcessHandle,
+    addr: usize,
+}
+
+/// Where the v2.3.6 release executable is loaded. It isn't position-independent, so it's always
+/// loaded here; other builds may not be.
+const DEFAULT_BASE: usize = 0x0040_0000;
+
+#[allow(clippy::needless_pass_by_value)] // matches the macOS signature
+pub(super) fn find_game_object(
+    pid: Pid,
+    scan_range: Option<Range<usize>>,
+    offset: Option<usize>,
+) -> Result<Handle> {
+    if scan_range.is_some() {
+        log::warn!("ignoring --scan-range; the game object is at a fixed offset on Linux");
+    }
+    let Some(offset) = offset else {
+        bail!(
+            "no known game object offset for this build on {}; add its fingerprint to \
+             fingerprints.toml",
+            std::env::consts::ARCH
+        );
+    };
+    let base = load_base(pid).unwrap_or_else(|err| {
+        log::warn!(
+            "could not find executable load address, assuming {:#x}: {:#}",
+            DEFAULT_BASE,
+            err
+        );
+        DEFAULT_BASE
+    });
+    if base != DEFAULT_BASE {
+        log::info!("executable loaded at {:#x}", base);
+    }
+    let addr = base.checked_add(offset).with_context(|| {
+        format!(
+            "game object offset {:#x} is out of range from {:#x}",
+            offset, base
+        )
+    })?;
+    Ok(Handle {
+        process: ProcessHandle::try_from(pid)?,
+        addr,
+    })
+}
+
+/// Find where the executable is mapped from `/proc/PID/maps`.
+fn load_base(pid: Pid) -> Result<usize> {
+    let exe = executable_path(pid)?;
+    let maps = std::fs::read_to_string(format!("/proc/{}/maps", pid))?;
+    maps.lines()
+        .find_map(|line| {
+            // start-end perms offset dev inode path, with single spaces between the first five
+            // fields, then padding before the path, which can have spaces of its own
+            let mut fields = line.splitn(6, ' ');
+            let (start, _) = fields.next()?.split_once('-')?;
+            let offset = fields.nth(1)?;
+            let path = fields.nth(2)?.trim_start();
+            (u64::from_str_radix(offset, 16).ok()? == 0 && exe.as_os_str() == path)
+                .then(|| usize::from_str_radix(start, 16).ok())?
+        })
+        .with_context(|| format!("{} is not mapped", exe.display()))
+}
+
+pub(super) fn address(handle: &Handle) -> usize {
+    handle.addr
+}
+
+pub(super) fn read_game_object(handle: &Handle) -> Result<(State, Timer<u32>)> {
+    let mut buf = [0; GAME_OBJECT_SIZE];
+    handle.process.copy_address(handle.addr, &mut buf)?;
+    Ok(GameObject::from(buf).into_state())
+}
+
+pub(super) fn read_u32(handle: &Handle, offset: isize) -> Result<u32> {
+    let mut buf = [0; 4];
+    handle
+        .process
+        .copy_address(field_address(handle.addr, offset)?, &mut buf)?;
+    Ok(u32::from_ne_bytes(buf))
+}
+
+/// Read `buf.len()` bytes from `offset` bytes past the start of the game object.
+pub(super) fn read(handle: &Handle, offset: isize, buf: &mut [u8]) -> Result<()> {
+    handle
+        .process
+        .copy_address(field_address(handle.addr, offset)?, buf)?;
+    Ok(())
+}
+
+pub(super) fn executable_path(pid: Pid) -> Result<PathBuf> {
+    Ok(std::fs::read_link(format!("/proc/{}/exe", pid))?)
+}
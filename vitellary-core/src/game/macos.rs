@@ -0,0 +1,236 @@
+#![cfg(target_os = "macos")]
+
+use crate::game::common::{field_address, GameObject, Timer, GAME_OBJECT_SIZE};
+use crate::game::scan::{self, Scan};
+use crate::game::{AccessError, State};
+use anyhow::{bail, Context, Result};
+use read_process_memory::{CopyAddress, Pid, ProcessHandle};
+use std::ffi::OsStr;
+use std::ops::Range;
+use std::os::unix::ffi::OsStrExt;
+use std::path::PathBuf;
+
+/// Where to look for the game object if `--scan-range` isn't given. The image is almost always in
+/// the first range; the others are only scanned if it isn't.
+const SCAN_RANGES: [Range<usize>; 3] = [
+    0x1_0000_0000..0x1_4000_0000,
+    0x1_4000_0000..0x2_0000_0000,
+    0x2_0000_0000..0x10_0000_0000,
+];
+
+/// How much of the executable image to scan, starting from its header.
+const IMAGE_SCAN_LEN: usize = 0x1000_0000;
+
+pub(super) struct Handle {
+    process: ProcessHandle,
+    addr: usize,
+}
+
+/// Set up a Mach port to a VVVVVV process and try to find the game object.
+///
+/// This is the reason this program must run as root on macOS; in order to get a Mach port to a
+/// process -- even if it is a child process! -- we must be running as root due to limitations on
+/// the `task_for_pid` call.
+///
+/// Once we have a port, we need to scan the memory space for the game object. VVVVVV's game object
+/// is a global starting with v2.3.x, so theoretically it's in the same place every time, but macOS
+/// runs PIE executables with ASLR, so we scan for it (see `scan::scan`).
+pub(super) fn find_game_object(
+    pid: Pid,
+    scan_range: Option<Range<usize>>,
+    offset: Option<usize>,
+) -> Result<Handle> {
+    // The `std::io::Error` returned here is useless, because the read-process-memory crate assumes
+    // errno is being set. That's not how this platform works!
+    let handle = ProcessHandle::try_from(pid).map_err(|_| AccessError::denied(pid))?;
+
+    let base = image_base(pid, &handle)
+        .map_err(|err| log::warn!("could not find the executable image: {:#}", err))
+        .ok();
+    if let (Some(base), Some(offset)) = (base, offset) {
+        let addr = base.checked_add(offset).with_context(|| {
+            format!(
+                "game object offset {:#x} is out of range from {:#x}",
+                offset, base
+            )
+        })?;
+        return Ok(Handle {
+            process: handle,
+            addr,
+        });
+    }
+
+    let ranges = match scan_range {
+        Some(range) => vec![range],
+        None => {
+            let mut ranges = SCAN_RANGES.to_vec();
+            if let Some(base) = base {
+                log::info!("executable loaded at {:#x}", base);
+                ranges.insert(0, base..base.saturating_add(IMAGE_SCAN_LEN));
+            }
+            ranges
+        }
+    };
+    let resume = scan::resume_point(pid);
+    for (i, range) in ranges.into_iter().enumerate() {
+        if resume.is_some_and(|address| address >= range.end) {
+            continue;
+        }
+        if i > 0 {
+            log::info!(
+                "game object not found; widening scan to {:#x}..{:#x}",
+                range.start,
+                range.end
+            );
+        }
+        let start = resume
+            .filter(|address| range.contains(address))
+            .unwrap_or(range.start);
+        let result = scan::scan(start..range.end, |address, buf| {
+            handle.copy_address(address, buf).is_ok()
+        });
+        scan::save_resume_point(
+            pid,
+            match result {
+                Scan::Cancelled(address) => Some(address),
+                _ => None,
+            },
+        );
+        match result {
+            Scan::Found(addr) => {
+                return Ok(Handle {
+                    process: handle,
+                    addr,
+                })
+            }
+            Scan::NotFound => {}
+            Scan::Cancelled(address) => {
+                bail!("scan cancelled at {:#x}; run again to resume", address)
+            }
+        }
+    }
+    bail!("failed to find game object (see --scan-range)")
+}
+
+/// `TASK_DYLD_INFO` from `<mach/task_info.h>`, which libc doesn't have.
+const TASK_DYLD_INFO: libc::task_flavor_t = 17;
+
+/// `struct task_dyld_info`, which is packed to 4 bytes like the rest of `<mach/task_info.h>`.
+#[repr(C, packed(4))]
+#[derive(Default)]
+#[allow(dead_code)] // `task_info` fills in all of it, but only the address is used
+struct TaskDyldInfo {
+    all_image_info_addr: u64,
+    all_image_info_size: u64,
+    all_image_info_format: i32,
+}
+
+extern "C" {
+    fn mach_port_deallocate(
+        task: libc::mach_port_t,
+        name: libc::mach_port_t,
+    ) -> libc::kern_return_t;
+}
+
+/// Find the main executable's Mach-O header, which is where the ASLR slide put the image.
+///
+/// dyld keeps a list of the images it loaded (`struct dyld_all_image_infos`) in the process, which
+/// `TASK_DYLD_INFO` points us to; the executable is the one whose header says it is.
+///
+/// For builds in the fingerprint database, the game object is at a known offset from here;
+/// otherwise this is where we start scanning.
+fn image_base(pid: Pid, handle: &ProcessHandle) -> Result<usize> {
+    const MH_MAGIC_64: u32 = 0xfeed_facf;
+    const MH_EXECUTE: u32 = 2;
+    /// The size of a `struct dyld_image_info`: the header address, the path and the mtime.
+    const IMAGE_INFO_SIZE: usize = 24;
+
+    let mut info = TaskDyldInfo::default();
+    #[allow(clippy::cast_possible_truncation)]
+    let mut count = (std::mem::size_of::<TaskDyldInfo>() / std::mem::size_of::<libc::natural_t>())
+        as libc::mach_msg_type_number_t;
+    // `ProcessHandle` doesn't give us its task port, so get another one.
+    let mut task = 0;
+    let result = unsafe { libc::task_for_pid(libc::mach_task_self(), pid, &mut task) };
+    if result != libc::KERN_SUCCESS {
+        bail!("task_for_pid failed ({})", result);
+    }
+    let result = unsafe {
+        libc::task_info(
+            task,
+            TASK_DYLD_INFO,
+            std::ptr::addr_of_mut!(info).cast(),
+            &mut count,
+        )
+    };
+    unsafe { mach_port_deallocate(libc::mach_task_self(), task) };
+    if result != libc::KERN_SUCCESS {
+        bail!("task_info(TASK_DYLD_INFO) failed ({})", result);
+    }
+
+    // The start of `struct dyld_all_image_infos`: the version, the number of images, and where
+    // the array of `struct dyld_image_info` is.
+    let mut all = [0; 16];
+    handle.copy_address(usize::try_from(info.all_image_info_addr)?, &mut all)?;
+    let images = u32::from_ne_bytes(all[4..8].try_into().unwrap());
+    let array = usize::try_from(u64::from_ne_bytes(all[8..16].try_into().unwrap()))?;
+    if array == 0 {
+        bail!("dyld is updating its list of images");
+    }
+
+    let mut image = [0; 8];
+    let mut header = [0; 16];
+    for i in 0..images as usize {
+        let entry = i
+            .checked_mul(IMAGE_INFO_SIZE)
+            .and_then(|offset| array.checked_add(offset))
+            .context("dyld's list of images is out of range")?;
+        handle.copy_address(entry, &mut image)?;
+        let address = usize::try_from(u64::from_ne_bytes(image))?;
+        if handle.copy_address(address, &mut header).is_ok()
+            && u32::from_ne_bytes(header[0..4].try_into().unwrap()) == MH_MAGIC_64
+            && u32::from_ne_bytes(header[12..16].try_into().unwrap()) == MH_EXECUTE
+        {
+            return Ok(address);
+        }
+    }
+    bail!("none of the {} images dyld loaded is an executable", images)
+}
+
+pub(super) fn address(handle: &Handle) -> usize {
+    handle.addr
+}
+
+pub(super) fn read_game_object(handle: &Handle) -> Result<(State, Timer<u32>)> {
+    let mut buf = [0; GAME_OBJECT_SIZE];
+    handle.process.copy_address(handle.addr, &mut buf)?;
+    Ok(GameObject::from(buf).into_state())
+}
+
+pub(super) fn read_u32(handle: &Handle, offset: isize) -> Result<u32> {
+    let mut buf = [0; 4];
+    handle
+        .process
+        .copy_address(field_address(handle.addr, offset)?, &mut buf)?;
+    Ok(u32::from_ne_bytes(buf))
+}
+
+/// Read `buf.len()` bytes from `offset` bytes past the start of the game object.
+pub(super) fn read(handle: &Handle, offset: isize, buf: &mut [u8]) -> Result<()> {
+    handle
+        .process
+        .copy_address(field_address(handle.addr, offset)?, buf)?;
+    Ok(())
+}
+
+pub(super) fn executable_path(pid: Pid) -> Result<PathBuf> {
+    // PROC_PIDPATHINFO_MAXSIZE
+    let mut buf = [0_u8; 4096];
+    #[allow(clippy::cast_possible_truncation)]
+    let len = unsafe { libc::proc_pidpath(pid, buf.as_mut_ptr().cast(), buf.len() as u32) };
+    if len <= 0 {
+        return Err(std::io::Error::last_os_error().into());
+    }
+    #[allow(clippy::cast_sign_loss)]
+    Ok(OsStr::from_bytes(&buf[..len as usize]).into())
+}
@@ -0,0 +1,1982 @@
+mod backend;
+mod category;
+pub mod common;
+mod drift;
+mod error;
+mod fields;
+mod fingerprint;
+mod gamestates;
+mod gravitron;
+pub mod helper;
+mod interpolation;
+mod linux;
+mod macos;
+mod profile;
+mod proton;
+mod quirks;
+pub mod remote;
+mod rooms;
+mod saves;
+mod savetime;
+pub mod scan;
+mod string;
+mod target;
+mod timetrial;
+mod version;
+
+#[cfg(target_os = "linux")]
+use linux as imp;
+#[cfg(target_os = "macos")]
+use macos as imp;
+
+use anyhow::{anyhow, bail, Result};
+pub use common::Timer;
+use debug_ignore::DebugIgnore;
+use read_process_memory::Pid;
+use serde::{Deserialize, Serialize, Serializer};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::fmt;
+use std::ops::{Range, RangeInclusive};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant, SystemTime};
+use timetrial::RESULT_STATES;
+use uuid::Uuid;
+
+pub use backend::{Backend, Kind as BackendKind, Mock};
+pub use error::AccessError;
+pub use fields::{Category, Fields, Setting, Settings, FULL_SPEED};
+pub use fingerprint::{fingerprint, Fingerprint, Fingerprints};
+pub use gamestates::Gamestates;
+pub use gravitron::Gravitron;
+pub use interpolation::Frame;
+pub use profile::Profile;
+pub use quirks::Quirks;
+pub use rooms::RoomNames;
+pub use saves::{SaveSummary, Saves};
+pub use target::Target;
+pub use timetrial::{Rank, TimeTrial};
+pub use version::Version;
+
+const GAMEMODE: u32 = 0;
+const TELEPORTERMODE: u32 = 5;
+// These are the campaign's splits. Custom levels are split with their profile instead (see
+// `Game::set_profile`).
+const SPLITS: [(Event, RangeInclusive<u32>); 8] = [
+    (Event::Verdigris, 3006..=3011),
+    (Event::Vermilion, 3060..=3065),
+    (Event::Victoria, 3040..=3045),
+    (Event::Violet, 4091..=4099),
+    (Event::Vitellary, 3020..=3025),
+    (Event::IntermissionOne, 3085..=3087),
+    (Event::IntermissionTwo, 3080..=3082),
+    (Event::GameComplete, 3503..=3509),
+];
+
+/// How many splits a full game makes with the built-in splits, not counting teleports.
+pub const BUILTIN_SPLIT_COUNT: usize = SPLITS.len();
+
+/// The crewmates rescued by campaign splits. Viridian doesn't need rescuing.
+const CREW_SPLITS: [Event; 5] = [
+    Event::Verdigris,
+    Event::Vermilion,
+    Event::Victoria,
+    Event::Violet,
+    Event::Vitellary,
+];
+/// How often to re-read the save slots.
+const SAVES_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The "you have found a shiny trinket" cutscene.
+const TRINKET_STATES: RangeInclusive<u32> = 1000..=1003;
+/// The "you have found a crewmate" cutscene, which custom levels use.
+const CREW_STATES: RangeInclusive<u32> = 1010..=1013;
+
+/// The room the final level starts in, which the ship's teleporter takes the player to once the
+/// final level opens.
+const FINAL_LEVEL_ROOM: (u32, u32) = (46, 54);
+
+/// If `state` leaves a split's range this soon after the split fired from a state the quirks guard,
+/// the split is assumed to have come from a spurious intermediate state that slipped past them
+/// (like the 3006 pre-increment in `quirks.toml`, on a modded build) and is undone.
+const CORRECTION_WINDOW: Duration = Duration::from_millis(100);
+
+/// Leaving the teleporter screen only means the player teleported if the room changes within this
+/// long (the teleport animation plays first); otherwise they backed out.
+const TELEPORT_WINDOW: Duration = Duration::from_secs(5);
+
+/// The map is this many rooms across and down, and walking off one edge can wrap around to the
+/// other.
+const MAP_ROOMS: u32 = 20;
+
+/// Leaving the game this soon after dying counts as quitting because of the death.
+const DEATH_QUIT_WINDOW: Duration = Duration::from_secs(5);
+
+/// How many times to retry a failed read of the game object within a single update.
+const READ_RETRIES: u32 = 3;
+/// How many updates in a row may fail to read the game object before we give up.
+const MAX_FAILED_UPDATES: u32 = 100;
+
+/// The game clock is reset just before a new game enters a playing state. If it's already further
+/// along than this, a save was loaded instead (e.g. a telesave from the menu while practicing),
+/// even if it's the save that was loaded last (see `Game::loaded_save`).
+const LOAD_THRESHOLD: Duration = Duration::from_secs(1);
+
+/// `game.savetime` before the first save, which a new game sets it back to.
+const NEW_SAVETIME: &str = "00:00";
+
+/// How many reads in a row the frame counter has to be past the frame rate before it's raised, so
+/// a single bad read can't.
+const FRAME_RATE_READS: u32 = 3;
+
+/// The highest frame rate the game timer is taken to count at.
+const MAX_FRAME_RATE: u32 = 1000;
+
+/// In practice forks, the game clock running backwards or jumping ahead by more than this between
+/// two updates means a savestate was loaded.
+const SAVESTATE_THRESHOLD: Duration = Duration::from_secs(1);
+
+#[derive(Debug)]
+#[allow(clippy::struct_excessive_bools)] // independent settings
+pub struct Game {
+    pid: Pid,
+    backend: DebugIgnore<Box<dyn Backend>>,
+    failed_updates: u32,
+    old: State,
+    cur: State,
+    pending: VecDeque<Event>,
+    /// The last split, the state it fired from, and when, in case it has to be undone.
+    last_split: Option<(Event, u32, Instant)>,
+    debounce: HashMap<Event, Debounce>,
+    fired: HashMap<Event, Instant>,
+    run: Run,
+    run_id: Uuid,
+    seq: u64,
+    frame_rate: u32,
+    /// How many reads in a row the frame counter has been past `frame_rate`.
+    frames_past_rate: u32,
+    drift: drift::Drift,
+    interpolation: interpolation::Interpolation,
+    /// Whether to add `subframe` to the game time in updates.
+    interpolate: bool,
+    /// The game time since the last logic frame.
+    subframe: Duration,
+    save_check: savetime::SaveCheck,
+    practice: Option<Event>,
+    offset: Duration,
+    practice_fork: bool,
+    last_time: Duration,
+    timer: Timer<u32>,
+    flips: u32,
+    trinkets: u32,
+    custom_crew: u32,
+    left_teleporter: Option<Instant>,
+    final_level_deaths: Option<u32>,
+    profile: Option<Profile>,
+    /// Whether a custom level is being played, if the build's `custom-mode` field says.
+    custom: Option<bool>,
+    /// The config directory to find custom level profiles in; see [`Game::find_profiles`].
+    profile_dir: Option<PathBuf>,
+    /// The custom level being played, as named by the build's `custom-level` field.
+    level: Option<String>,
+    strict_profiles: bool,
+    reset_grace: Option<Duration>,
+    crash_resume: Option<Duration>,
+    left_playing: Option<Instant>,
+    /// `game.savetime` and `game.savearea` when the game last left a playing state (or when we
+    /// attached outside of one), to tell loading a save from a new game.
+    menu_save: Option<(String, String)>,
+    /// Whether `left_playing` is from the game going away, not from leaving a playing state.
+    crashed: bool,
+    last_death: Option<Instant>,
+    reset_cause: Option<ResetCause>,
+    room_names: RoomNames,
+    /// The quirks that apply to the attached build.
+    quirks: Quirks,
+    gamestates: Gamestates,
+    fields: Fields,
+    settings: Settings,
+    saves: Saves,
+    /// Other categories' split tables to follow, and their events for the next update.
+    categories: Vec<category::Tracked>,
+    category_events: BTreeMap<String, Event>,
+    /// When `saves` was last read.
+    saves_read: Option<Instant>,
+    start_settings: Settings,
+    slowest: Option<u32>,
+    invincible_run: bool,
+    category: Option<Category>,
+    time_trial: Option<TimeTrial>,
+    gravitron: gravitron::Tracker,
+    gravitron_attempt: Option<Gravitron>,
+    target: Target,
+}
+
+/// Where we are in a run, as far as the splitter is concerned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Run {
+    /// In the menus (or anywhere else outside of a playing state).
+    NotRunning,
+    /// Between `Event::NewGame` and `Event::GameComplete`; splits may fire.
+    Running,
+    /// After `Event::GameComplete`; no more splits fire until the next new game.
+    Finished,
+}
+
+/// Why a run reset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ResetCause {
+    /// The player quit to the menu.
+    QuitToMenu,
+    /// The player quit to the menu right after dying.
+    DeathQuit,
+    /// The reset command.
+    Manual,
+    /// The game went away mid-run: it crashed, or was closed.
+    Crash,
+}
+
+/// Limits on how often a split event may fire, for when its state range can be re-entered (e.g.
+/// rewatching a cutscene, or savestates in practice mods).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Debounce {
+    /// Ignore the event if it fired less than this long ago, when it may `repeat`.
+    pub cooldown: Option<Duration>,
+    /// Let the event fire again after it already fired since the last new game or reset, which
+    /// it otherwise can't.
+    pub repeat: bool,
+}
+
+/// A per-event cooldown, given on the command line as `EVENT=SECONDS`.
+#[derive(Debug, Clone, Copy)]
+pub struct Cooldown {
+    pub event: Event,
+    pub duration: Duration,
+}
+
+impl FromStr for Cooldown {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Cooldown, String> {
+        let (event, seconds) = s
+            .split_once('=')
+            .ok_or_else(|| format!("expected EVENT=SECONDS, got {:?}", s))?;
+        Ok(Cooldown {
+            event: event.parse()?,
+            duration: Duration::try_from_secs_f64(
+                seconds
+                    .parse()
+                    .map_err(|_| format!("invalid number of seconds {:?}", seconds))?,
+            )
+            .map_err(|err| err.to_string())?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct State {
+    pub room: (u32, u32),
+    pub gamestate: u32,
+    pub state: u32,
+    /// 0 if the player is on the floor, 1 if on the ceiling.
+    pub gravitycontrol: u32,
+    /// Deaths since the start of the game.
+    pub deathcounts: u32,
+}
+
+impl State {
+    fn new() -> State {
+        State {
+            room: (u32::MAX, u32::MAX),
+            gamestate: u32::MAX,
+            state: u32::MAX,
+            gravitycontrol: u32::MAX,
+            deathcounts: u32::MAX,
+        }
+    }
+}
+
+impl fmt::Display for State {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "room ({}, {}), gamestate {}, state {}, gravity {}, {} deaths",
+            self.room.0,
+            self.room.1,
+            self.gamestate,
+            self.state,
+            self.gravitycontrol,
+            self.deathcounts
+        )
+    }
+}
+
+/// Everything the poll loop sends to outputs.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum Message {
+    /// We found the game object of a VVVVVV process.
+    Attached {
+        pid: Pid,
+        address: usize,
+        target: Target,
+    },
+    /// We can no longer read from the VVVVVV process.
+    Detached {
+        pid: Pid,
+        reason: String,
+    },
+    Update(Update),
+    /// Sent after `Event::GameComplete`.
+    Summary(Summary),
+    /// Sent after `Event::TimeTrialComplete`.
+    TimeTrial(TimeTrial),
+    /// Sent after `Event::GravitronAttempt`.
+    Gravitron(Gravitron),
+    /// Sent by the watchdog when the splitter's own health changes.
+    Health(Health),
+}
+
+impl Message {
+    /// Whether an output that's fallen behind can miss this message: an update without an event
+    /// (in any category), which the next update supersedes.
+    pub fn droppable(&self) -> bool {
+        matches!(
+            self,
+            Message::Update(update) if update.event.is_none() && update.categories.is_empty()
+        )
+    }
+
+    /// The message as an output following `category` sees it (see [`Update::in_category`]).
+    #[must_use]
+    pub fn in_category(&self, category: &str) -> Message {
+        match self {
+            Message::Update(update) => Message::Update(update.in_category(category)),
+            message => message.clone(),
+        }
+    }
+}
+
+impl Update {
+    /// The event for a timer following `category` (one of [`Update::categories`]): its own split,
+    /// or the run starting, resetting, pausing or resuming.
+    pub fn category_event(&self, category: &str) -> Option<Event> {
+        self.categories.get(category).copied().or_else(|| {
+            self.event.filter(|event| {
+                matches!(
+                    event,
+                    Event::NewGame
+                        | Event::Reset
+                        | Event::Pause
+                        | Event::Resume
+                        | Event::Manual(Command::Reset)
+                )
+            })
+        })
+    }
+
+    /// The update with [`Update::category_event`] as its event, so outputs can follow another
+    /// category's splits instead of the run's own.
+    #[must_use]
+    pub fn in_category(&self, category: &str) -> Update {
+        Update {
+            event: self.category_event(category),
+            ..self.clone()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Update {
+    /// Increases by one with every update, so consumers can detect gaps.
+    pub seq: u64,
+    /// Changes on every new game.
+    pub run_id: Uuid,
+    /// The game time, interpolated between logic frames if [`Game::set_interpolation`] is on.
+    #[serde(serialize_with = "serialize_secs")]
+    pub time: Duration,
+    /// The game's own timer, which `time` is computed from.
+    pub timer: Timer<u32>,
+    pub event: Option<Event>,
+    #[serde(flatten)]
+    pub state: State,
+    pub run: Run,
+    /// How many times gravity flipped this run.
+    pub flips: u32,
+    /// Trinkets collected this run, counted from the cutscene that plays for each, so ones
+    /// collected before we attached or in a loaded save aren't included.
+    pub trinkets: u32,
+    /// Crewmates rescued this run, including Viridian, counted from the campaign's splits (or the
+    /// rescue cutscene in custom levels).
+    pub crew: u32,
+    /// The game's settings, where the build's fingerprint record says where they are.
+    #[serde(flatten)]
+    pub settings: Settings,
+    /// The save slots, where the build's fingerprint record says where they are.
+    #[serde(skip_serializing_if = "Saves::is_empty")]
+    pub saves: Saves,
+    /// Events from the other categories' split tables followed alongside the run (see
+    /// [`Game::track_category`]), by category.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub categories: BTreeMap<String, Event>,
+    /// The time left to survive in the campaign's gravitron, while it's running, where the build's
+    /// fields map it.
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "gravitron::serialize_optional_secs"
+    )]
+    pub gravitron_remaining: Option<Duration>,
+    /// The logic frame the game is on, while playing, for clients that interpolate the time
+    /// between frames: they can add the part of a frame that's passed since it started to `time`
+    /// (unless [`Game::set_interpolation`] already did).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub frame: Option<Frame>,
+    /// Why the run reset, with `Event::Reset`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reset_cause: Option<ResetCause>,
+    /// When the game was read (or, for a replay, when the state was recorded).
+    #[serde(flatten)]
+    pub clock: Timestamp,
+}
+
+/// Stats for a completed run.
+#[derive(Debug, Clone, Serialize)]
+pub struct Summary {
+    pub run_id: Uuid,
+    #[serde(serialize_with = "serialize_secs")]
+    pub time: Duration,
+    pub flips: u32,
+    pub deaths: u32,
+    /// Whether the final level was cleared without dying, if we saw it being entered.
+    pub final_level_deathless: Option<bool>,
+    /// The lowest game speed set at any point in the run, if it was ever below full speed. Times
+    /// from slowed-down runs aren't comparable with full-speed ones.
+    pub slowdown: Option<u32>,
+    /// Whether invincibility was on at any point in the run.
+    pub invincibility: bool,
+    /// The game's settings when the run started.
+    pub settings: Settings,
+    /// The category the run was played as: the one given with [`Game::set_category`], or the
+    /// one its settings made it, if they could be read.
+    pub category: Option<Category>,
+    /// The executable the run was played on.
+    pub target: Target,
+}
+
+/// When something happened, by the wall clock and by a monotonic one, so recordings can be lined
+/// up with stream VODs and system logs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Timestamp {
+    /// Time since the Unix epoch (UTC), by the system clock.
+    #[serde(serialize_with = "serialize_secs")]
+    pub utc: Duration,
+    /// Time since vitellary first read the game, by a clock that changes to the system clock
+    /// don't affect.
+    #[serde(serialize_with = "serialize_secs")]
+    pub monotonic: Duration,
+}
+
+impl Timestamp {
+    pub fn now() -> Timestamp {
+        static STARTED: OnceLock<Instant> = OnceLock::new();
+        Timestamp {
+            utc: SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .unwrap_or_default(),
+            monotonic: STARTED.get_or_init(Instant::now).elapsed(),
+        }
+    }
+}
+
+/// How the splitter's own parts are doing.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Health {
+    /// Whether nothing is stuck.
+    pub ok: bool,
+    /// The parts that are stuck, like `poll` or an output's name.
+    pub stuck: Vec<String>,
+    /// The parts restarted since the last status.
+    pub restarted: Vec<String>,
+    /// How many WebSocket clients are connected.
+    pub clients: usize,
+}
+
+#[allow(clippy::trivially_copy_pass_by_ref)] // required by serde
+fn serialize_secs<S: Serializer>(time: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_f64(time.as_secs_f64())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Event {
+    NewGame,
+    Verdigris,
+    Vermilion,
+    Victoria,
+    Violet,
+    Vitellary,
+    IntermissionOne,
+    IntermissionTwo,
+    GameComplete,
+    /// The player used a teleporter. Not a split unless `--split-on-teleport` is given.
+    Teleport,
+    Reset,
+    /// The game left a playing state during a run; the run resets if it doesn't come back within
+    /// the reset grace period.
+    Pause,
+    /// The game came back to a playing state within the reset grace period.
+    Resume,
+    Manual(Command),
+    /// A split from the custom level profile, by index.
+    Custom(u16),
+    /// Undo the previous split, which fired from a spurious state.
+    Correction,
+    /// A time trial's results screen came up; see [`Game::time_trial`].
+    TimeTrialComplete,
+    /// A Super Gravitron attempt ended; see [`Game::gravitron`].
+    GravitronAttempt,
+    /// A setting that keeps the run off the leaderboards was on when it started, or was turned
+    /// on during it.
+    Warning(Setting),
+    /// The game timer ran at this percentage of real time over the last several seconds of play,
+    /// from lag, a speedhack, or a mod changing the frame counter.
+    TimerDrift(u16),
+}
+
+impl FromStr for Event {
+    type Err = String;
+
+    /// Parse the name of a split event.
+    fn from_str(s: &str) -> Result<Event, String> {
+        match s {
+            "verdigris" => Ok(Event::Verdigris),
+            "vermilion" => Ok(Event::Vermilion),
+            "victoria" => Ok(Event::Victoria),
+            "violet" => Ok(Event::Violet),
+            "vitellary" => Ok(Event::Vitellary),
+            "intermission-one" => Ok(Event::IntermissionOne),
+            "intermission-two" => Ok(Event::IntermissionTwo),
+            "game-complete" => Ok(Event::GameComplete),
+            _ => Err(format!(
+                "unknown event {:?} (expected verdigris, vermilion, victoria, violet, vitellary, \
+                 intermission-one, intermission-two, or game-complete)",
+                s
+            )),
+        }
+    }
+}
+
+/// A manual override from the runner, e.g. to correct a missed autosplit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Command {
+    Split,
+    #[serde(rename = "skipsplit")]
+    SkipSplit,
+    Undo,
+    Reset,
+}
+
+impl FromStr for Command {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Command, String> {
+        match s {
+            "split" => Ok(Command::Split),
+            "skipsplit" => Ok(Command::SkipSplit),
+            "undo" => Ok(Command::Undo),
+            "reset" => Ok(Command::Reset),
+            _ => Err(format!(
+                "unknown command {:?} (expected split, skipsplit, undo, or reset)",
+                s
+            )),
+        }
+    }
+}
+
+impl Game {
+    /// Attach to a VVVVVV process, scanning `scan_range` for the game object where we need to
+    /// (instead of the default ranges) if `fingerprints` doesn't know where it is.
+    pub fn attach(
+        pid: Pid,
+        scan_range: Option<Range<usize>>,
+        fingerprints: &Fingerprints,
+    ) -> Result<Game> {
+        let (target, build) = match imp::executable_path(pid) {
+            Ok(path) => Target::inspect(path, fingerprints),
+            Err(err) => {
+                log::warn!("could not find the executable of pid {}: {:#}", pid, err);
+                (Target::default(), None)
+            }
+        };
+        if let Some(version) = target.version.filter(|version| *version < Version::V2_3) {
+            bail!(
+                "VVVVVV {} is not supported; versions before 2.3 don't keep the game object \
+                 somewhere we can find it",
+                version
+            );
+        }
+        let handle = imp::find_game_object(pid, scan_range, build.map(|build| build.offset))?;
+        Ok(Game::with_build(
+            pid,
+            Box::new(backend::Native { pid, handle }),
+            target,
+            build,
+        ))
+    }
+
+    /// Attach to a VVVVVV process through a privileged helper run with `helper` (see
+    /// [`helper`]), for when we can't read its memory ourselves.
+    pub fn attach_helper(
+        pid: Pid,
+        helper: std::process::Command,
+        fingerprints: &Fingerprints,
+    ) -> Result<Game> {
+        let (target, build) = Target::inspect(imp::executable_path(pid)?, fingerprints);
+        let backend = helper::Helper::spawn(helper, pid, build.map(|build| build.offset))?;
+        Ok(Game::with_build(pid, Box::new(backend), target, build))
+    }
+
+    /// Set up a game read from `backend`, with what its fingerprint record says about it.
+    fn with_build(
+        pid: Pid,
+        backend: Box<dyn Backend>,
+        target: Target,
+        build: Option<&fingerprint::Build>,
+    ) -> Game {
+        let mut game = Game::with_backend(pid, backend);
+        if let Some(build) = build {
+            if let Some(gamestates) = build.gamestates.clone() {
+                game.set_gamestates(gamestates);
+            }
+            game.fields = build.fields.clone();
+        }
+        game.target = target;
+        game
+    }
+
+    /// Attach to the Windows build of VVVVVV running under Proton or Wine.
+    #[cfg(target_os = "linux")]
+    pub fn attach_proton(pid: Pid, fingerprints: &Fingerprints) -> Result<Game> {
+        let backend = proton::Proton::find(pid, fingerprints)?;
+        let gamestates = backend.gamestates.clone();
+        let fields = backend.fields.clone();
+        let target = backend.target.clone();
+        let mut game = Game::with_backend(pid, Box::new(backend));
+        if let Some(gamestates) = gamestates {
+            game.set_gamestates(gamestates);
+        }
+        game.fields = fields;
+        game.target = target;
+        Ok(game)
+    }
+
+    /// Read the game through the agent at `addr` (see [`remote`]), waiting for it to attach to
+    /// the game if it hasn't yet.
+    pub fn attach_remote(addr: &str) -> Result<Game> {
+        let (backend, hello) = remote::Remote::connect(addr)?;
+        log::info!("reading the game through the agent at {}", addr);
+        let mut game = Game::with_backend(hello.pid, Box::new(backend));
+        game.set_gamestates(hello.gamestates);
+        game.fields = hello.fields;
+        game.target = hello.target;
+        Ok(game)
+    }
+
+    /// Read the game through an agent run by `agent`, a command that runs `vitellary agent
+    /// --stdio` (e.g. over SSH, see [`remote`]), waiting for it to attach to the game if it hasn't
+    /// yet.
+    pub fn attach_remote_command(agent: std::process::Command) -> Result<Game> {
+        let what = format!("{:?}", agent);
+        let (backend, hello) = remote::Remote::spawn(agent)?;
+        log::info!("reading the game through the agent run by {}", what);
+        let mut game = Game::with_backend(hello.pid, Box::new(backend));
+        game.set_gamestates(hello.gamestates);
+        game.fields = hello.fields;
+        game.target = hello.target;
+        Ok(game)
+    }
+
+    /// Read the game object from a file instead of a process.
+    pub fn from_dump(path: PathBuf) -> Game {
+        Game::with_backend(0, Box::new(backend::Dump(path)))
+    }
+
+    pub fn with_backend(pid: Pid, backend: Box<dyn Backend>) -> Game {
+        log::info!(
+            "attached to pid {}, game object at {:#x}",
+            pid,
+            backend.address()
+        );
+        Game {
+            pid,
+            backend: DebugIgnore(backend),
+            failed_updates: 0,
+            old: State::new(),
+            cur: State::new(),
+            pending: VecDeque::new(),
+            last_split: None,
+            debounce: HashMap::new(),
+            fired: HashMap::new(),
+            run: Run::NotRunning,
+            run_id: Uuid::new_v4(),
+            seq: 0,
+            frame_rate: 30,
+            frames_past_rate: 0,
+            drift: drift::Drift::default(),
+            interpolation: interpolation::Interpolation::default(),
+            interpolate: false,
+            subframe: Duration::ZERO,
+            save_check: savetime::SaveCheck::default(),
+            practice: None,
+            offset: Duration::ZERO,
+            practice_fork: false,
+            last_time: Duration::ZERO,
+            timer: Timer::default(),
+            flips: 0,
+            trinkets: 0,
+            custom_crew: 0,
+            left_teleporter: None,
+            final_level_deaths: None,
+            profile: None,
+            custom: None,
+            profile_dir: None,
+            level: None,
+            strict_profiles: false,
+            reset_grace: None,
+            crash_resume: None,
+            left_playing: None,
+            menu_save: None,
+            crashed: false,
+            last_death: None,
+            reset_cause: None,
+            room_names: RoomNames::builtin(),
+            quirks: Quirks::builtin(),
+            gamestates: Gamestates::default(),
+            fields: Fields::default(),
+            settings: Settings::default(),
+            saves: Saves::default(),
+            categories: Vec::new(),
+            category_events: BTreeMap::new(),
+            saves_read: None,
+            start_settings: Settings::default(),
+            slowest: None,
+            invincible_run: false,
+            category: None,
+            time_trial: None,
+            gravitron: gravitron::Tracker::default(),
+            gravitron_attempt: None,
+            target: Target::default(),
+        }
+    }
+
+    pub fn pid(&self) -> Pid {
+        self.pid
+    }
+
+    /// The address of the game object in the VVVVVV process.
+    pub fn address(&self) -> usize {
+        self.backend.address()
+    }
+
+    /// The executable we attached to, as far as it could be identified.
+    pub fn target(&self) -> &Target {
+        &self.target
+    }
+
+    /// Whether to wait between updates; see [`Backend::paced`].
+    pub fn paced(&self) -> bool {
+        self.backend.paced()
+    }
+
+    /// Set how many frames the game's timer counts per second (30 unless the build runs its logic
+    /// faster).
+    pub fn set_frame_rate(&mut self, frame_rate: u32) {
+        self.frame_rate = frame_rate.clamp(1, MAX_FRAME_RATE);
+    }
+
+    /// Interpolate the game time in updates between the game's logic frames, for timers that show
+    /// it more smoothly than the game counts it.
+    pub fn set_interpolation(&mut self, interpolate: bool) {
+        self.interpolate = interpolate;
+    }
+
+    /// Only split on `event`, and start a run (with the time counting from zero) whenever a save
+    /// is loaded, so a single segment can be practiced over and over.
+    pub fn set_practice(&mut self, event: Event) {
+        self.practice = Some(event);
+    }
+
+    /// Split using a custom level's profile instead of the campaign's splits, or go back to the
+    /// campaign's splits with `None`. Where the build's `custom-mode` field is mapped, the profile
+    /// is only used while a custom level is being played, and the campaign's splits otherwise.
+    ///
+    /// The profile is checked first for [problems](Profile::problems), and for practice segments
+    /// and debounced splits that name campaign splits it doesn't have. They're logged as warnings,
+    /// or with strict profiles (see [`Game::set_strict_profiles`]), the profile is refused.
+    pub fn set_profile(&mut self, profile: Option<Profile>) -> Result<()> {
+        if let Some(profile) = &profile {
+            let mut problems = profile.problems();
+            let named = self
+                .practice
+                .iter()
+                .map(|event| ("practice segment", event))
+                .chain(self.debounce.keys().map(|event| ("debounced split", event)));
+            for (what, event) in named {
+                if !matches!(event, Event::Custom(_)) {
+                    problems.push(format!(
+                        "the {} {:?} is a campaign split, which the profile doesn't have",
+                        what, event
+                    ));
+                }
+            }
+            if !problems.is_empty() && self.strict_profiles {
+                bail!(
+                    "the split profile has problems:\n  {}",
+                    problems.join("\n  ")
+                );
+            }
+            for problem in problems {
+                log::warn!("split profile: {}", problem);
+            }
+            if self.fields.custom_mode.is_none() {
+                log::info!(
+                    "the build's custom-mode field isn't mapped, so the campaign is split with \
+                     the profile too"
+                );
+            }
+        }
+        self.profile = profile;
+        Ok(())
+    }
+
+    /// Split each custom level the game plays with its profile from `dir` (see [`Profile::load`]),
+    /// found by the level's name where the build's `custom-level` field is mapped, instead of
+    /// setting one profile for every level.
+    pub fn find_profiles(&mut self, dir: PathBuf) {
+        if self.fields.custom_level.is_none() {
+            log::debug!("the build's custom-level field isn't mapped, so no profile will be found");
+        }
+        self.profile_dir = Some(dir);
+    }
+
+    /// The custom level being played, if [`Game::find_profiles`] is on and the build's fields
+    /// name it.
+    pub fn level(&self) -> Option<&str> {
+        self.level.as_deref()
+    }
+
+    /// Also follow `profile`'s split table as the category `name`, alongside the run's own splits,
+    /// sending its splits as namespaced events in [`Update::categories`]. Each of its splits is
+    /// made once per run. The profile is checked for problems as in [`Game::set_profile`].
+    pub fn track_category(&mut self, name: String, profile: Profile) -> Result<()> {
+        let problems = profile.problems();
+        if !problems.is_empty() && self.strict_profiles {
+            bail!(
+                "the split profile for {} has problems:\n  {}",
+                name,
+                problems.join("\n  ")
+            );
+        }
+        for problem in problems {
+            log::warn!("split profile for {}: {}", name, problem);
+        }
+        self.categories.push(category::Tracked::new(name, profile));
+        Ok(())
+    }
+
+    /// Refuse split profiles with problems instead of warning about them (see
+    /// [`Game::set_profile`]).
+    pub fn set_strict_profiles(&mut self, strict: bool) {
+        self.strict_profiles = strict;
+    }
+
+    /// Detect savestates loaded by practice forks of the game and don't split on the state and
+    /// room jumps they cause. When practicing, a savestate starts a new attempt.
+    pub fn set_practice_fork(&mut self, practice_fork: bool) {
+        self.practice_fork = practice_fork;
+    }
+
+    /// Only reset a run once the game has been out of a playing state for `grace`, pausing the
+    /// timer in the meantime, so a quick trip to the menu and back doesn't end the run.
+    pub fn set_reset_grace(&mut self, grace: Duration) {
+        self.reset_grace = Some(grace);
+    }
+
+    /// When the game goes away mid-run, keep the run for `window` in case the game restarts, so it
+    /// can be carried over with [`Game::resume_run`] and resumed from a loaded save.
+    pub fn set_crash_resume(&mut self, window: Duration) {
+        self.crash_resume = Some(window);
+    }
+
+    /// Pause the run in progress because the game went away, if we're to wait for it to restart
+    /// (see [`Game::set_crash_resume`]).
+    pub fn crashed(&mut self) -> Option<Update> {
+        if self.run != Run::Running || self.practice.is_some() {
+            return None;
+        }
+        let window = self.crash_resume?;
+        log::info!("game went away mid-run; keeping the run for {:?}", window);
+        self.left_playing = Some(Instant::now());
+        self.crashed = true;
+        self.reset_cause = Some(ResetCause::Crash);
+        Some(self.emit(self.last_time, Some(Event::Pause)))
+    }
+
+    /// Whether the run paused by [`Game::crashed`] can still be resumed.
+    pub fn awaiting_restart(&self) -> bool {
+        self.crashed && self.grace_left()
+    }
+
+    /// Take over the run paused by [`Game::crashed`] on `crashed`, the game before it restarted.
+    /// Loading a save resumes it; starting a new game or waiting out the rest of the window
+    /// resets it.
+    pub fn resume_run(&mut self, crashed: &Game) {
+        self.run = crashed.run;
+        self.run_id = crashed.run_id;
+        self.seq = crashed.seq;
+        self.last_split = crashed.last_split;
+        self.fired.clone_from(&crashed.fired);
+        self.flips = crashed.flips;
+        self.trinkets = crashed.trinkets;
+        self.custom_crew = crashed.custom_crew;
+        self.final_level_deaths = crashed.final_level_deaths;
+        self.start_settings.clone_from(&crashed.start_settings);
+        self.slowest = crashed.slowest;
+        self.invincible_run = crashed.invincible_run;
+        self.left_playing = crashed.left_playing;
+        self.crashed = crashed.crashed;
+        self.reset_cause = crashed.reset_cause;
+        // Entering a playing state from here is what resumes or resets the run.
+        self.old = State {
+            gamestate: u32::MAX,
+            ..crashed.cur.clone()
+        };
+    }
+
+    /// Whether the reset grace period (or the crash resume window) since leaving the game hasn't
+    /// run out yet.
+    fn grace_left(&self) -> bool {
+        let grace = if self.crashed {
+            self.crash_resume
+        } else {
+            self.reset_grace
+        };
+        self.left_playing
+            .zip(grace)
+            .is_some_and(|(at, grace)| at.elapsed() < grace)
+    }
+
+    /// Use `room_names` to name rooms in the log, instead of the built-in names.
+    pub fn set_room_names(&mut self, room_names: RoomNames) {
+        self.room_names = room_names;
+    }
+
+    /// Guard the campaign splits with `quirks` (those that apply to the attached build), instead
+    /// of the built-in ones.
+    pub fn set_quirks(&mut self, quirks: &Quirks) {
+        self.quirks = quirks.applying_to(&self.target);
+    }
+
+    /// Use the `gamestate` values of a modded build, instead of the ones its fingerprint record
+    /// gives (or the game's own).
+    pub fn set_gamestates(&mut self, gamestates: Gamestates) {
+        log::info!("playing gamestates: {:?}", gamestates.playing);
+        self.gamestates = gamestates;
+    }
+
+    /// Read the fields at `fields` instead of the ones the build's fingerprint record maps.
+    /// Mostly useful with dumps.
+    pub fn set_fields(&mut self, fields: Fields) {
+        self.fields = fields;
+    }
+
+    /// Tag runs as `category`, warning about runs whose settings make them another one, instead
+    /// of tagging each with the category its settings make it.
+    pub fn set_category(&mut self, category: Category) {
+        self.category = Some(category);
+    }
+
+    /// Get the debounce settings for a split event, to change them.
+    pub fn debounce(&mut self, event: Event) -> &mut Debounce {
+        self.debounce.entry(event).or_default()
+    }
+
+    /// Queue a manual command to be sent with the next update that doesn't carry an event of its
+    /// own.
+    pub fn queue(&mut self, command: Command) {
+        log::info!("manual command: {:?}", command);
+        self.pending.push_back(Event::Manual(command));
+    }
+
+    /// Read the game object once and check that it holds values the game could have, as a sign
+    /// that we found the right address.
+    pub fn validate(&self) -> Result<State> {
+        let (state, timer) = self.backend.read_game_object()?;
+        if state.gamestate > self.gamestates.max {
+            bail!(
+                "gamestate is {}, which the game never uses",
+                state.gamestate
+            );
+        }
+        if state.gravitycontrol > 1 {
+            bail!("gravitycontrol is {}, not 0 or 1", state.gravitycontrol);
+        }
+        if !timer.is_plausible() {
+            bail!("the game timer reads {:?}, which it can't count to", timer);
+        }
+        Ok(state)
+    }
+
+    /// Read the game object and work out what happened since the last update.
+    ///
+    /// Returns `Ok(None)` if the game object couldn't be read this time but might be next time.
+    pub fn update(&mut self) -> Result<Option<Update>> {
+        // The game clock only shows a savestate being loaded if the last update read it just
+        // before this one: not on the first update after attaching, or after failed reads.
+        let continuous = self.old.state != u32::MAX && self.failed_updates == 0;
+        let Some((state, timer)) = self.read()? else {
+            return Ok(None);
+        };
+        self.check_frame_rate(timer.frames);
+        let time = timer.duration(self.frame_rate);
+        let last_time = std::mem::replace(&mut self.last_time, time);
+        self.timer = timer;
+        self.check_drift(time, state.gamestate);
+        self.interpolate(time, state.gamestate);
+        if self.old.state == u32::MAX {
+            // We may have attached in the middle of a run, so let splits fire.
+            if self.gamestates.is_playing(state.gamestate) {
+                self.run = Run::Running;
+                self.detect_level();
+            } else {
+                self.menu_save = savetime::read_save(self.backend.as_ref());
+            }
+            self.old = state.clone();
+            self.cur = state;
+        } else {
+            self.old = std::mem::replace(&mut self.cur, state);
+        }
+
+        self.log_changes(time);
+        self.read_settings();
+        self.read_saves();
+        if self.run == Run::Running {
+            self.count_progress();
+            self.check_save();
+        }
+        if self.old.gamestate == TELEPORTERMODE && self.cur.gamestate == GAMEMODE {
+            self.left_teleporter = Some(Instant::now());
+        }
+
+        if self.practice_fork
+            && continuous
+            && self.gamestates.is_playing(self.cur.gamestate)
+            && self.gamestates.is_playing(self.old.gamestate)
+            && time
+                .checked_sub(last_time)
+                .is_none_or(|elapsed| elapsed > SAVESTATE_THRESHOLD)
+        {
+            log::info!(
+                "savestate loaded ({:?} -> {:?}); ignoring jump",
+                last_time,
+                time
+            );
+            self.old = self.cur.clone();
+            self.last_split = None;
+            if self.practice.is_some() {
+                return Ok(Some(self.start_attempt(time)));
+            }
+            return Ok(Some(self.emit(time, None)));
+        }
+
+        if self.gamestates.is_playing(self.cur.gamestate)
+            && !self.gamestates.is_playing(self.old.gamestate)
+        {
+            return Ok(Some(self.enter_game(time)));
+        }
+        if !self.gamestates.is_playing(self.cur.gamestate)
+            && self.gamestates.is_playing(self.old.gamestate)
+        {
+            return Ok(Some(self.leave_game(time)));
+        }
+
+        if self.left_playing.is_some() && !self.grace_left() {
+            log::info!("still out of the game after the grace period; resetting");
+            self.left_playing = None;
+            self.start_run(Run::NotRunning);
+            return Ok(Some(self.emit(time, Some(Event::Reset))));
+        }
+
+        self.split_categories();
+        let mut event = self.split();
+        if event.is_none() {
+            event = self.teleport();
+        }
+        if let Some(challenge) = self.challenge(time) {
+            match event {
+                Some(_) => self.pending.push_back(challenge),
+                None => event = Some(challenge),
+            }
+        }
+        if self.spurious_split() {
+            // Send any split that fired this cycle after the correction.
+            if let Some(event) = event {
+                self.pending.push_front(event);
+            }
+            event = Some(Event::Correction);
+        }
+
+        if event.is_none() {
+            event = self.pending.pop_front();
+        }
+        if let Some(event) = event {
+            self.record(event);
+        }
+
+        Ok(Some(self.emit(time, event)))
+    }
+
+    fn log_changes(&self, time: Duration) {
+        if self.old.room != self.cur.room {
+            log::debug!(
+                "room: {} -> {} @ {:?}",
+                self.room_names.describe(self.old.room),
+                self.room_names.describe(self.cur.room),
+                time
+            );
+        }
+        if self.old.gamestate != self.cur.gamestate {
+            log::debug!(
+                "gamestate: {} -> {} @ {:?}",
+                self.old.gamestate,
+                self.cur.gamestate,
+                time
+            );
+        }
+        if self.old.state != self.cur.state {
+            log::debug!(
+                "state: {} -> {} @ {:?}",
+                self.old.state,
+                self.cur.state,
+                time
+            );
+        }
+    }
+
+    /// Read the settings the build's fields say where to find, logging changes and warning about
+    /// ones that keep a run off the leaderboards.
+    fn read_settings(&mut self) {
+        if let Some(slowdown) = self.read_field("slowdown", self.fields.slowdown) {
+            if self.settings.slowdown != Some(slowdown) {
+                if slowdown < FULL_SPEED {
+                    log::warn!("game slowed down to {}/{} speed", slowdown, FULL_SPEED);
+                    self.warn(Setting::Slowdown);
+                } else if self.settings.slowdown.is_some() {
+                    log::info!("game back to full speed");
+                }
+                self.settings.slowdown = Some(slowdown);
+            }
+            if self.run == Run::Running && slowdown < FULL_SPEED {
+                self.slowest = Some(
+                    self.slowest
+                        .map_or(slowdown, |slowest| slowest.min(slowdown)),
+                );
+            }
+        }
+        let flag = |name, offset| self.read_field(name, offset).map(|value| value != 0);
+        let invincibility = flag("invincibility", self.fields.invincibility);
+        let glitchrunner = flag("glitchrunner", self.fields.glitchrunner);
+        let ingame_timer = flag("ingame-timer", self.fields.ingame_timer);
+        let flip_mode = flag("flip-mode", self.fields.flip_mode);
+        if let Some(invincibility) = invincibility {
+            if invincibility && self.settings.invincibility != Some(true) {
+                log::warn!("invincibility is on");
+                self.warn(Setting::Invincibility);
+            }
+            if self.run == Run::Running && invincibility {
+                self.invincible_run = true;
+            }
+        }
+        set_flag(
+            &mut self.settings.invincibility,
+            invincibility,
+            "invincibility",
+        );
+        set_flag(
+            &mut self.settings.glitchrunner,
+            glitchrunner,
+            "glitchrunner mode",
+        );
+        set_flag(
+            &mut self.settings.ingame_timer,
+            ingame_timer,
+            "in-game timer",
+        );
+        set_flag(&mut self.settings.flip_mode, flip_mode, "flip mode");
+    }
+
+    /// Re-read the save slots every so often, if the build has them; the game only changes them
+    /// when saving.
+    fn read_saves(&mut self) {
+        if self.fields.telesave.is_none() && self.fields.quicksave.is_none()
+            || self
+                .saves_read
+                .is_some_and(|at| at.elapsed() < SAVES_INTERVAL)
+        {
+            return;
+        }
+        self.saves_read = Some(Instant::now());
+        let read = |offset: Option<isize>| saves::read(self.backend.as_ref(), offset?);
+        let saves = Saves {
+            telesave: read(self.fields.telesave),
+            quicksave: read(self.fields.quicksave),
+        };
+        if saves != self.saves {
+            for (name, save) in [
+                ("telesave", &saves.telesave),
+                ("quicksave", &saves.quicksave),
+            ] {
+                if let Some(save) = save {
+                    log::debug!(
+                        "{}: {} in {}, {} trinkets",
+                        name,
+                        save.time,
+                        save.area,
+                        save.trinkets
+                    );
+                }
+            }
+            self.saves = saves;
+        }
+    }
+
+    /// Read the field at `offset`, if the build has it. A field that can't be read is skipped,
+    /// since the game object itself was just read fine.
+    fn read_field(&self, name: &str, offset: Option<isize>) -> Option<u32> {
+        match self.backend.read_u32(offset?) {
+            Ok(value) => Some(value),
+            Err(err) => {
+                log::debug!("failed to read {}: {:#}", name, err);
+                None
+            }
+        }
+    }
+
+    /// Follow the game timer's frame rate: from the build's `frame-rate` field where it's mapped,
+    /// or else by raising it if the frame counter stays past it (e.g. with a 60 FPS mod).
+    fn check_frame_rate(&mut self, frames: u32) {
+        if let Some(rate) = self
+            .read_field("frame-rate", self.fields.frame_rate)
+            .filter(|rate| (1..=MAX_FRAME_RATE).contains(rate))
+        {
+            if rate != self.frame_rate {
+                log::info!("the game timer counts {} frames per second", rate);
+                self.frame_rate = rate;
+            }
+            return;
+        }
+        if frames < self.frame_rate || self.frame_rate >= MAX_FRAME_RATE {
+            self.frames_past_rate = 0;
+            return;
+        }
+        self.frames_past_rate += 1;
+        if self.frames_past_rate < FRAME_RATE_READS {
+            return;
+        }
+        log::warn!(
+            "frame counter reached {} but frame rate is {}; raising it (see --frame-rate)",
+            frames,
+            self.frame_rate
+        );
+        self.frame_rate = frames.saturating_add(1).clamp(60, MAX_FRAME_RATE);
+        self.frames_past_rate = 0;
+    }
+
+    /// Compare the game timer's progress with the wall clock (the recorded one, when replaying),
+    /// queueing a [`Event::TimerDrift`] when it starts running fast or slow.
+    fn check_drift(&mut self, time: Duration, gamestate: u32) {
+        let now = self.backend.recorded_at().unwrap_or_else(Timestamp::now);
+        let playing = self.gamestates.is_playing(gamestate);
+        if let Some(percent) = self.drift.update(now.monotonic, time, playing) {
+            self.pending.push_back(Event::TimerDrift(percent));
+        }
+    }
+
+    /// Cross-check the crewmate splits against the game saving after each rescue, queueing a
+    /// rescue's split if the game saved for it but it never fired.
+    fn check_save(&mut self) {
+        let now = self.backend.recorded_at().unwrap_or_else(Timestamp::now);
+        let rescue = SPLITS
+            .iter()
+            .find(|(event, range)| CREW_SPLITS.contains(event) && range.contains(&self.cur.state))
+            .map(|(event, _)| *event)
+            .filter(|_| self.quirks.allow(&self.old, &self.cur));
+        let Some(event) = self
+            .save_check
+            .update(now.monotonic, rescue, self.backend.as_ref())
+        else {
+            return;
+        };
+        if self.fired.contains_key(&event) || self.pending.contains(&event) {
+            log::debug!("the game's save confirms {:?}", event);
+        } else if !self.in_custom_level() && self.practice.is_none() {
+            log::warn!(
+                "the game saved after rescuing {:?}, but it never split; splitting now",
+                event
+            );
+            self.pending.push_back(event);
+        }
+    }
+
+    /// Work out how far the game is into its current logic frame.
+    fn interpolate(&mut self, time: Duration, gamestate: u32) {
+        let now = self.backend.recorded_at().unwrap_or_else(Timestamp::now);
+        let frame = Duration::from_secs(1) / self.frame_rate;
+        let playing = self.gamestates.is_playing(gamestate);
+        self.subframe = self
+            .interpolation
+            .update(now.monotonic, time, frame, playing);
+    }
+
+    /// Queue a warning about `setting` if a run is live.
+    fn warn(&mut self, setting: Setting) {
+        if self.run == Run::Running {
+            self.pending.push_back(Event::Warning(setting));
+        }
+    }
+
+    /// Count the flips, trinkets, and rescues in the latest state change.
+    fn count_progress(&mut self) {
+        // This also counts gravity lines and respawns, since we only see the result.
+        if self.old.gravitycontrol != self.cur.gravitycontrol {
+            self.flips += 1;
+        }
+        if self.cur.deathcounts > self.old.deathcounts {
+            self.last_death = Some(Instant::now());
+        }
+        let entered = |range: &RangeInclusive<u32>| {
+            range.contains(&self.cur.state) && !range.contains(&self.old.state)
+        };
+        if entered(&TRINKET_STATES) {
+            self.trinkets += 1;
+        }
+        if entered(&CREW_STATES) {
+            self.custom_crew += 1;
+        }
+        // The final level opens once every crewmate is rescued and both intermissions are done.
+        if self.final_level_deaths.is_none()
+            && self.cur.room == FINAL_LEVEL_ROOM
+            && self.old.room != FINAL_LEVEL_ROOM
+            && SPLITS
+                .iter()
+                .all(|(e, _)| *e == Event::GameComplete || self.fired.contains_key(e))
+        {
+            log::info!(
+                "entered the final level with {} deaths",
+                self.cur.deathcounts
+            );
+            self.final_level_deaths = Some(self.cur.deathcounts);
+        }
+    }
+
+    /// Keep track of the splits fired this run.
+    fn record(&mut self, event: Event) {
+        if let Event::Custom(index) = event {
+            if let Some(split) = self.profile().and_then(|p| p.get(index)) {
+                log::info!("split: {}", split.name);
+            }
+        }
+        if self.range(event).is_some() {
+            self.last_split = Some((event, self.cur.state, Instant::now()));
+            self.fired.insert(event, Instant::now());
+        }
+        if self.is_final(event) {
+            log::debug!("run: {:?} -> {:?}", self.run, Run::Finished);
+            self.run = Run::Finished;
+        }
+    }
+
+    /// Read the game object, retrying reads that fail for reasons other than the process exiting
+    /// or us lacking permission.
+    fn read(&mut self) -> Result<Option<(State, Timer<u32>)>> {
+        let mut last_err = None;
+        for _ in 0..READ_RETRIES {
+            match self.backend.read_game_object() {
+                Ok(result) => {
+                    if self.failed_updates > 0 {
+                        log::info!(
+                            "reads recovered after {} failed updates",
+                            self.failed_updates
+                        );
+                        self.failed_updates = 0;
+                    }
+                    return Ok(Some(result));
+                }
+                Err(err) if self.backend.exited() => {
+                    return Err(err.context(AccessError::ProcessGone { pid: self.pid }));
+                }
+                Err(err)
+                    if err
+                        .downcast_ref::<std::io::Error>()
+                        .is_some_and(|err| err.kind() == std::io::ErrorKind::PermissionDenied) =>
+                {
+                    return Err(err.context(AccessError::denied(self.pid)));
+                }
+                Err(err) => last_err = Some(err),
+            }
+        }
+
+        let err = last_err.unwrap_or_else(|| anyhow!("no read attempted"));
+        self.failed_updates += 1;
+        if self.failed_updates >= MAX_FAILED_UPDATES {
+            return Err(err.context(format!(
+                "failed to read game object {} times in a row",
+                self.failed_updates
+            )));
+        }
+        if self.failed_updates == 1 {
+            log::warn!("failed to read game object, retrying: {:#}", err);
+        }
+        Ok(None)
+    }
+
+    /// Check whether the last split fired from a spurious state and should be undone, forgetting
+    /// it if so. Only states the quirks guard are suspect: the scripts pass through those on the
+    /// way to others, while leaving any other split's range quickly (like `IntermissionOne`'s
+    /// short one) is the game moving on.
+    fn spurious_split(&mut self) -> bool {
+        let Some((split, state, at)) = self.last_split else {
+            return false;
+        };
+        let range = self.range(split);
+        if at.elapsed() > CORRECTION_WINDOW || !self.quirks.guards(state) {
+            self.last_split = None;
+            false
+        } else if range.is_some_and(|range| !range.contains(&self.cur.state)) {
+            log::warn!(
+                "state left {:?} range within {:?} (now {}); undoing split",
+                split,
+                at.elapsed(),
+                self.cur.state
+            );
+            self.last_split = None;
+            self.fired.remove(&split);
+            if self.is_final(split) {
+                self.run = Run::Running;
+            }
+            true
+        } else {
+            false
+        }
+    }
+
+    fn emit(&mut self, time: Duration, event: Option<Event>) -> Update {
+        self.seq += 1;
+        Update {
+            seq: self.seq,
+            run_id: self.run_id,
+            time: if self.interpolate {
+                time + self.subframe
+            } else {
+                time
+            }
+            .saturating_sub(self.offset),
+            timer: self.timer,
+            event,
+            state: self.cur.clone(),
+            run: self.run,
+            flips: self.flips,
+            trinkets: self.trinkets,
+            crew: self.crew(),
+            settings: self.settings.clone(),
+            saves: self.saves.clone(),
+            categories: std::mem::take(&mut self.category_events),
+            gravitron_remaining: self.gravitron.remaining(),
+            frame: self
+                .interpolation
+                .frame(Duration::from_secs(1) / self.frame_rate),
+            reset_cause: self.reset_cause.filter(|_| event == Some(Event::Reset)),
+            clock: self.backend.recorded_at().unwrap_or_else(Timestamp::now),
+        }
+    }
+
+    /// Crewmates rescued this run, including Viridian.
+    fn crew(&self) -> u32 {
+        let rescued = if self.in_custom_level() {
+            self.custom_crew
+        } else {
+            let fired = CREW_SPLITS
+                .iter()
+                .filter(|event| self.fired.contains_key(event))
+                .count();
+            u32::try_from(fired).unwrap_or_default()
+        };
+        1 + rescued
+    }
+
+    /// Stats for the current run, as of the last update.
+    pub fn summary(&self) -> Summary {
+        Summary {
+            run_id: self.run_id,
+            time: self.last_time.saturating_sub(self.offset),
+            flips: self.flips,
+            deaths: self.cur.deathcounts,
+            final_level_deathless: self
+                .final_level_deaths
+                .map(|deaths| deaths == self.cur.deathcounts),
+            slowdown: self.slowest,
+            invincibility: self.invincible_run,
+            settings: self.start_settings.clone(),
+            category: self.category.or(self.start_settings.category()),
+            target: self.target.clone(),
+        }
+    }
+
+    /// The results of the last time trial completed, sent with `Event::TimeTrialComplete`.
+    pub fn time_trial(&self) -> Option<&TimeTrial> {
+        self.time_trial.as_ref()
+    }
+
+    /// Check for the end of a time trial or Super Gravitron attempt, which aren't splits but are
+    /// recorded like them.
+    fn challenge(&mut self, time: Duration) -> Option<Event> {
+        let mut event = None;
+        if RESULT_STATES.contains(&self.cur.state) && !RESULT_STATES.contains(&self.old.state) {
+            self.time_trial = Some(self.read_time_trial(time));
+            event = Some(Event::TimeTrialComplete);
+        }
+        if let Some(attempt) = self.track_gravitron() {
+            self.gravitron_attempt = Some(attempt);
+            if event.is_some() {
+                self.pending.push_back(Event::GravitronAttempt);
+            } else {
+                event = Some(Event::GravitronAttempt);
+            }
+        }
+        event
+    }
+
+    /// The last Super Gravitron attempt, sent with `Event::GravitronAttempt`.
+    pub fn gravitron(&self) -> Option<&Gravitron> {
+        self.gravitron_attempt.as_ref()
+    }
+
+    /// Follow the gravitrons, if the build's fields map them, returning the Super Gravitron attempt
+    /// that just ended, if one did.
+    fn track_gravitron(&mut self) -> Option<Gravitron> {
+        let mode = self.read_field("gravitron-mode", self.fields.gravitron_mode)?;
+        let timer = self.read_field("gravitron-timer", self.fields.gravitron_timer)?;
+        let record = self.read_field("gravitron-record", self.fields.gravitron_record);
+        let game = self.read_field("gravitron-game", self.fields.gravitron_game);
+        self.gravitron
+            .update(mode != 0, game, timer, record, self.frame_rate)
+    }
+
+    /// Read the results of a time trial that just finished, from the game's own results if the
+    /// build's fields map them.
+    fn read_time_trial(&self, time: Duration) -> TimeTrial {
+        let level = self.read_field("time-trial-level", self.fields.time_trial_level);
+        let results = self.fields.time_trial_results.and_then(|offset| {
+            let mut results = [0; timetrial::RESULT_FIELDS];
+            for (field, offset) in results.iter_mut().zip((offset..).step_by(4)) {
+                *field = self.read_field("time-trial-results", Some(offset))?;
+            }
+            Some(results)
+        });
+        let trial = match results {
+            Some(results) => TimeTrial::from_results(level, results),
+            None => TimeTrial {
+                level,
+                time,
+                deaths: self.cur.deathcounts,
+                trinkets: None,
+                trinket_target: None,
+                par: None,
+                rank: None,
+            },
+        };
+        log::debug!("time trial results: {:?}", trial);
+        trial
+    }
+
+    /// Handle the game entering a playing state: a new game, a loaded save, or coming back within
+    /// the reset grace period.
+    fn enter_game(&mut self, time: Duration) -> Update {
+        self.detect_level();
+        if self.practice.is_some() {
+            return self.start_attempt(time);
+        }
+        let loaded = self.loaded_save(time);
+        if let Some(at) = self.left_playing.take() {
+            self.crashed = false;
+            if loaded {
+                log::info!("back in game after {:?}; resuming", at.elapsed());
+                return self.emit(time, Some(Event::Resume));
+            }
+            // A new game within the grace period: reset the paused run, then start the new one
+            // with the next update.
+            let update = self.emit(time, Some(Event::Reset));
+            self.start_run(Run::Running);
+            self.pending.push_front(Event::NewGame);
+            return update;
+        }
+        if loaded {
+            // Carry on from the save without starting the timer, but let splits fire.
+            log::info!("loaded a save at {:?}; not starting a new run", time);
+            if self.run == Run::NotRunning {
+                self.run = Run::Running;
+            }
+            return self.emit(time, None);
+        }
+        self.start_run(Run::Running);
+        self.emit(Duration::ZERO, Some(Event::NewGame))
+    }
+
+    /// Whether entering a playing state at `time` is loading a save rather than starting a new
+    /// game: the game clock is already going, or loading the save changed `game.savetime` and
+    /// `game.savearea` since the game left a playing state. (A new game only ever resets them.)
+    fn loaded_save(&mut self, time: Duration) -> bool {
+        let before = self.menu_save.take();
+        if time > LOAD_THRESHOLD {
+            return true;
+        }
+        let (Some(before), Some(now)) = (before, savetime::read_save(self.backend.as_ref())) else {
+            return false;
+        };
+        if before == now || now.0 == NEW_SAVETIME {
+            return false;
+        }
+        log::debug!("save changed to {} at {}", now.1, now.0);
+        true
+    }
+
+    /// Handle the game leaving a playing state.
+    fn leave_game(&mut self, time: Duration) -> Update {
+        self.menu_save = savetime::read_save(self.backend.as_ref());
+        self.reset_cause = Some(
+            if self
+                .last_death
+                .is_some_and(|at| at.elapsed() < DEATH_QUIT_WINDOW)
+            {
+                ResetCause::DeathQuit
+            } else {
+                ResetCause::QuitToMenu
+            },
+        );
+        if self.run != Run::NotRunning && self.practice.is_none() && self.reset_grace.is_some() {
+            log::info!("left the game; pausing until the reset grace period is over");
+            self.left_playing = Some(Instant::now());
+            return self.emit(time, Some(Event::Pause));
+        }
+        // Don't reset a timer we never started, e.g. when leaving a loaded save.
+        let event = (self.run != Run::NotRunning).then_some(Event::Reset);
+        self.start_run(Run::NotRunning);
+        self.emit(time, event)
+    }
+
+    /// Start a practice attempt, counting the time from now.
+    fn start_attempt(&mut self, time: Duration) -> Update {
+        log::info!("starting practice attempt at {:?}", time);
+        self.start_run(Run::Running);
+        self.offset = time;
+        self.emit(time, Some(Event::NewGame))
+    }
+
+    /// Move to `run` on a new game or reset, forgetting which splits fired.
+    fn start_run(&mut self, run: Run) {
+        log::debug!("run: {:?} -> {:?}", self.run, run);
+        self.run = run;
+        if run == Run::Running {
+            self.run_id = Uuid::new_v4();
+            log::info!("new run {}", self.run_id);
+        }
+        self.last_split = None;
+        self.fired.clear();
+        self.save_check.clear();
+        for category in &mut self.categories {
+            category.reset();
+        }
+        self.offset = Duration::ZERO;
+        self.flips = 0;
+        self.trinkets = 0;
+        self.custom_crew = 0;
+        self.final_level_deaths = None;
+        self.left_playing = None;
+        self.crashed = false;
+        self.last_death = None;
+        if run == Run::Running {
+            log::debug!("settings at start: {:?}", self.settings);
+            self.start_settings = self.settings.clone();
+            match (self.category, self.settings.category()) {
+                (Some(category), Some(detected)) if category != detected => log::warn!(
+                    "tagging this run {}, but the game's settings make it a {} run",
+                    category,
+                    detected
+                ),
+                (None, Some(detected)) => log::info!("tagging this run {}", detected),
+                _ => {}
+            }
+        }
+        self.slowest = self
+            .settings
+            .slowdown
+            .filter(|&slowdown| slowdown < FULL_SPEED);
+        self.invincible_run = self.settings.invincibility == Some(true);
+        if self.slowest.is_some() {
+            self.warn(Setting::Slowdown);
+        }
+        if self.invincible_run {
+            self.warn(Setting::Invincibility);
+        }
+    }
+
+    /// Check whether the latest room change came from the teleporter the player just left: a jump
+    /// to a room walking can't reach. Walking into the next room means they backed out.
+    fn teleport(&mut self) -> Option<Event> {
+        let at = self.left_teleporter?;
+        if at.elapsed() > TELEPORT_WINDOW {
+            self.left_teleporter = None;
+            None
+        } else if self.old.room != self.cur.room {
+            self.left_teleporter = None;
+            let teleported = !adjacent(self.old.room, self.cur.room);
+            (teleported && self.run == Run::Running).then_some(Event::Teleport)
+        } else {
+            None
+        }
+    }
+
+    /// The `state` range of a split event.
+    fn range(&self, event: Event) -> Option<RangeInclusive<u32>> {
+        match event {
+            Event::Custom(index) => {
+                let split = self.profile()?.get(index)?;
+                Some(split.range(self.glitchrunner()))
+            }
+            _ => SPLITS
+                .iter()
+                .find(|(e, _)| *e == event)
+                .map(|(_, range)| range.clone()),
+        }
+    }
+
+    /// Whether a custom level is being played: as the game says, where the build's fields let us
+    /// read it, or else whenever there's a profile to split with.
+    fn in_custom_level(&self) -> bool {
+        self.custom
+            .unwrap_or(self.profile.is_some() || self.level.is_some())
+    }
+
+    /// The custom level's profile, while a custom level is being played.
+    fn profile(&self) -> Option<&Profile> {
+        self.profile.as_ref().filter(|_| self.in_custom_level())
+    }
+
+    /// Check whether the game is playing a custom level or the campaign, where the build's fields
+    /// say, so each is split with its own table.
+    fn detect_level(&mut self) {
+        let custom = self
+            .read_field("custom-mode", self.fields.custom_mode)
+            .map(|mode| mode != 0);
+        if let Some(custom) = custom.filter(|&custom| self.custom != Some(custom)) {
+            let what = if custom {
+                "a custom level"
+            } else {
+                "the campaign"
+            };
+            log::info!("playing {}", what);
+            self.custom = Some(custom);
+        }
+        self.find_profile();
+    }
+
+    /// With [`Game::find_profiles`] on, load the profile for the custom level being played when it
+    /// changes, or go back to the campaign's splits outside of custom levels.
+    fn find_profile(&mut self) {
+        let Some(dir) = self.profile_dir.clone() else {
+            return;
+        };
+        let level = self
+            .fields
+            .custom_level
+            .filter(|_| self.custom != Some(false))
+            .and_then(|offset| string::read(self.backend.as_ref(), offset))
+            .and_then(|(file, _)| {
+                let name = Path::new(&file).file_stem()?.to_str()?;
+                (!name.is_empty()).then(|| name.to_owned())
+            });
+        if level == self.level {
+            return;
+        }
+        let profile = level.as_deref().and_then(|level| {
+            Profile::load(&dir, level)
+                .map_err(|err| log::warn!("not splitting {}: {:#}", level, err))
+                .ok()
+        });
+        if let Some(level) = &level {
+            log::info!("playing the custom level {}", level);
+        }
+        self.level = level;
+        if let Err(err) = self.set_profile(profile) {
+            log::warn!(
+                "not splitting {}: {:#}",
+                self.level.as_deref().unwrap_or_default(),
+                err
+            );
+            self.profile = None;
+        }
+    }
+
+    /// Whether glitchrunner mode is on, as far as we can tell.
+    fn glitchrunner(&self) -> bool {
+        self.settings.glitchrunner == Some(true)
+    }
+
+    /// Whether a split event finishes the run.
+    fn is_final(&self, event: Event) -> bool {
+        match event {
+            Event::GameComplete if !self.in_custom_level() => true,
+            Event::Custom(index) => self.profile().is_some_and(|p| p.is_last(index)),
+            _ => Some(event) == self.practice,
+        }
+    }
+
+    /// Find the other categories' splits (if any) for the latest state change.
+    fn split_categories(&mut self) {
+        if self.run != Run::Running {
+            return;
+        }
+        let glitchrunner = self.glitchrunner();
+        for category in &mut self.categories {
+            if let Some(event) = category.split(&self.old, &self.cur, glitchrunner) {
+                self.category_events.insert(category.name.clone(), event);
+            }
+        }
+    }
+
+    /// Find the split event (if any) for the latest state change.
+    fn split(&self) -> Option<Event> {
+        if self.run != Run::Running {
+            return None;
+        }
+
+        let split = if self.in_custom_level() {
+            // A custom level without a profile has nothing to split on.
+            self.profile()?
+                .split(&self.old, &self.cur, self.glitchrunner())?
+        } else {
+            campaign_split(&self.quirks, &self.old, &self.cur)?
+        };
+        if self.practice.is_some_and(|segment| segment != split) {
+            log::debug!("ignoring {:?} while practicing", split);
+            return None;
+        }
+        let debounce = self.debounce.get(&split).copied().unwrap_or_default();
+        if let Some(at) = self.fired.get(&split) {
+            if !debounce.repeat {
+                log::debug!("ignoring {:?}, already fired this run", split);
+                return None;
+            } else if debounce
+                .cooldown
+                .is_some_and(|cooldown| at.elapsed() < cooldown)
+            {
+                log::debug!("ignoring {:?}, fired {:?} ago", split, at.elapsed());
+                return None;
+            }
+        }
+        Some(split)
+    }
+}
+
+/// Whether rooms `a` and `b` are next to each other on the map, counting the ones that wrap around
+/// its edges, so walking can take the player from one to the other.
+fn adjacent(a: (u32, u32), b: (u32, u32)) -> bool {
+    let near = |a: u32, b: u32| a.abs_diff(b) == 1 || a.abs_diff(b) == MAP_ROOMS - 1;
+    (a.0 == b.0 && near(a.1, b.1)) || (a.1 == b.1 && near(a.0, b.0))
+}
+
+/// Find the campaign split (if any) for a state change, unless one of `quirks` rules it out (like
+/// the 3006 pre-increment that would fire `Event::Verdigris` outside "Murdering Twinmaker").
+pub fn campaign_split(quirks: &Quirks, old: &State, cur: &State) -> Option<Event> {
+    if !quirks.allow(old, cur) {
+        return None;
+    }
+
+    SPLITS.into_iter().find_map(|(event, range)| {
+        (range.contains(&cur.state) && !range.contains(&old.state)).then_some(event)
+    })
+}
+
+/// Store a setting that's on or off, if it was read, logging it if it changed.
+fn set_flag(setting: &mut Option<bool>, value: Option<bool>, name: &str) {
+    let Some(value) = value else {
+        return;
+    };
+    if setting.is_some_and(|was| was != value) {
+        log::info!("{} turned {}", name, if value { "on" } else { "off" });
+    }
+    *setting = Some(value);
+}
+
+/// Whether a process exists (even if we're not allowed to signal it).
+fn process_exists(pid: Pid) -> bool {
+    // SAFETY: signal 0 only checks whether the process can be signalled.
+    let result = unsafe { libc::kill(pid, 0) };
+    result == 0 || std::io::Error::last_os_error().raw_os_error() != Some(libc::ESRCH)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Play through every campaign split on `mock`, dying `deaths_before` times on the way back to
+    /// the ship and `deaths_after` times in the final level, and return the run's summary.
+    fn play(deaths_before: u32, deaths_after: u32) -> Summary {
+        let mock = Mock::new();
+        let mut state = State {
+            room: (115, 100),
+            gamestate: GAMEMODE,
+            state: 0,
+            gravitycontrol: 0,
+            deathcounts: 0,
+        };
+        let mut game = Game::with_backend(0, Box::new(mock.clone()));
+        let mut step = |state: &State| {
+            mock.set(state.clone(), Duration::from_secs(100));
+            game.update().unwrap();
+        };
+        step(&state);
+        for (_, range) in &SPLITS[..SPLITS.len() - 1] {
+            state.state = *range.start();
+            step(&state);
+            // Leaving a split's range right away would undo it as spurious.
+            std::thread::sleep(CORRECTION_WINDOW);
+            state.state = 0;
+            step(&state);
+        }
+        state.room = (113, 109);
+        for _ in 0..deaths_before {
+            state.deathcounts += 1;
+            step(&state);
+        }
+        state.room = FINAL_LEVEL_ROOM;
+        step(&state);
+        for _ in 0..deaths_after {
+            state.deathcounts += 1;
+            step(&state);
+        }
+        state.state = *SPLITS[SPLITS.len() - 1].1.start();
+        step(&state);
+        game.summary()
+    }
+
+    #[test]
+    fn final_level_deaths_count_from_entering_it() {
+        assert_eq!(play(3, 0).final_level_deathless, Some(true));
+        assert_eq!(play(0, 2).final_level_deathless, Some(false));
+    }
+}
@@ -0,0 +1,304 @@
+use crate::game::{Event, State};
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::ops::RangeInclusive;
+use std::path::Path;
+
+/// A split table for a custom level, read from `levels/NAME.toml` in the config directory:
+///
+/// ```toml
+/// [[splits]]
+/// name = "Trinket 1"
+/// state = [100, 102]
+/// # optional; only split in this room
+/// room = [101, 100]
+/// # optional; the states to use instead when glitchrunner mode is on
+/// glitchrunner-state = [100, 103]
+/// ```
+///
+/// The run finishes on the last split.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Profile {
+    pub splits: Vec<Split>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Split {
+    pub name: String,
+    /// The first and last `state` of the script that triggers the split.
+    state: [u32; 2],
+    room: Option<(u32, u32)>,
+    /// The states to use when glitchrunner mode is on, for scripts whose state numbers it changes.
+    glitchrunner_state: Option<[u32; 2]>,
+}
+
+impl Profile {
+    /// Load the profile for the custom level named `level` from `dir`.
+    pub fn load(dir: &Path, level: &str) -> Result<Profile> {
+        if level.contains(['/', '\\']) || level.starts_with('.') {
+            bail!("invalid level name {:?}", level);
+        }
+        let path = dir.join("levels").join(format!("{}.toml", level));
+        let profile: Profile = toml::from_str(
+            &std::fs::read_to_string(&path)
+                .with_context(|| format!("failed to read {}", path.display()))?,
+        )
+        .with_context(|| format!("failed to parse {}", path.display()))?;
+        if profile.splits.is_empty() || u16::try_from(profile.splits.len()).is_err() {
+            bail!("{} must have between 1 and 65535 splits", path.display());
+        }
+        log::info!(
+            "loaded {} splits for {:?} from {}",
+            profile.splits.len(),
+            level,
+            path.display()
+        );
+        Ok(profile)
+    }
+
+    /// Find the split (if any) for a state change, using the glitchrunner mode states if
+    /// `glitchrunner`.
+    pub(super) fn split(&self, old: &State, cur: &State, glitchrunner: bool) -> Option<Event> {
+        self.splits.iter().zip(0..).find_map(|(split, index)| {
+            let range = split.range(glitchrunner);
+            (range.contains(&cur.state)
+                && !range.contains(&old.state)
+                && split.room.is_none_or(|room| room == cur.room))
+            .then_some(Event::Custom(index))
+        })
+    }
+
+    /// What's wrong with the profile that would make it split wrongly: empty state ranges, ranges
+    /// that overlap (only the first split whose range is entered fires, so a later split's overlap
+    /// with it never does), and duplicate names.
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        let mut names = HashMap::new();
+        for (i, split) in self.splits.iter().enumerate() {
+            for (key, state) in [
+                ("state", Some(split.state)),
+                ("glitchrunner-state", split.glitchrunner_state),
+            ] {
+                if let Some([first, last]) = state.filter(|[first, last]| first > last) {
+                    problems.push(format!(
+                        "{}: {} [{}, {}] is empty, so it never splits",
+                        split.describe(i),
+                        key,
+                        first,
+                        last
+                    ));
+                }
+            }
+            if let Some(first) = names.insert(split.name.as_str(), i) {
+                problems.push(format!(
+                    "{} and {} have the same name",
+                    self.splits[first].describe(first),
+                    split.describe(i)
+                ));
+            }
+            for (j, later) in self.splits.iter().enumerate().skip(i + 1) {
+                problems.extend(split.overlap(i, later, j));
+            }
+        }
+        problems
+    }
+
+    pub(super) fn get(&self, index: u16) -> Option<&Split> {
+        self.splits.get(usize::from(index))
+    }
+
+    pub(super) fn is_last(&self, index: u16) -> bool {
+        usize::from(index) + 1 == self.splits.len()
+    }
+}
+
+impl Split {
+    /// The split at `index`, for diagnostics.
+    fn describe(&self, index: usize) -> String {
+        format!("split {} ({:?})", index + 1, self.name)
+    }
+
+    /// How this split, at `index`, keeps a `later` one at `later_index` from firing, if it does.
+    fn overlap(&self, index: usize, later: &Split, later_index: usize) -> Vec<String> {
+        if self.room.is_some() && later.room.is_some() && self.room != later.room {
+            return Vec::new();
+        }
+        let mut problems = Vec::new();
+        for glitchrunner in [false, true] {
+            if glitchrunner
+                && self.glitchrunner_state.is_none()
+                && later.glitchrunner_state.is_none()
+            {
+                continue;
+            }
+            let (range, later_range) = (self.range(glitchrunner), later.range(glitchrunner));
+            let (start, end) = (
+                *range.start().max(later_range.start()),
+                *range.end().min(later_range.end()),
+            );
+            if range.is_empty() || later_range.is_empty() || start > end {
+                continue;
+            }
+            let mode = if glitchrunner {
+                " in glitchrunner mode"
+            } else {
+                ""
+            };
+            problems.push(
+                if (start, end) == (*later_range.start(), *later_range.end())
+                    && self.room.is_none_or(|room| Some(room) == later.room)
+                {
+                    format!(
+                        "{} never splits{}: its states are all {}'s, which is checked first",
+                        later.describe(later_index),
+                        mode,
+                        self.describe(index)
+                    )
+                } else {
+                    format!(
+                        "{} and {} overlap on states {} to {}{}; only the first splits there",
+                        self.describe(index),
+                        later.describe(later_index),
+                        start,
+                        end,
+                        mode
+                    )
+                },
+            );
+        }
+        problems
+    }
+
+    /// The states that trigger the split, in glitchrunner mode if `glitchrunner`.
+    pub(super) fn range(&self, glitchrunner: bool) -> RangeInclusive<u32> {
+        let [first, last] = match self.glitchrunner_state {
+            Some(state) if glitchrunner => state,
+            _ => self.state,
+        };
+        first..=last
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn curated_profiles_have_no_problems() {
+        let dir = Path::new(concat!(env!("CARGO_MANIFEST_DIR"), "/../profiles"));
+        let profile = Profile::load(dir, "any-percent").unwrap();
+        assert_eq!(profile.splits.len(), crate::game::BUILTIN_SPLIT_COUNT);
+        assert_eq!(profile.problems(), Vec::<String>::new());
+    }
+
+    fn profile(toml: &str) -> Profile {
+        toml::from_str(toml).unwrap()
+    }
+
+    #[test]
+    fn rejects_bad_level_names() {
+        let dir = Path::new(concat!(env!("CARGO_MANIFEST_DIR"), "/../profiles"));
+        for level in ["../any-percent", "levels/any-percent", ".hidden"] {
+            let err = Profile::load(dir, level).unwrap_err();
+            assert!(err.to_string().starts_with("invalid level name"), "{}", err);
+        }
+    }
+
+    #[test]
+    fn rejects_profiles_without_splits() {
+        let dir = std::env::temp_dir().join(format!("vitellary-profile-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("levels")).unwrap();
+        std::fs::write(dir.join("levels/empty.toml"), "splits = []\n").unwrap();
+        let err = Profile::load(&dir, "empty").unwrap_err();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(
+            err.to_string()
+                .ends_with("must have between 1 and 65535 splits"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn finds_empty_ranges() {
+        let profile = profile(
+            r#"
+            [[splits]]
+            name = "A"
+            state = [10, 5]
+            glitchrunner-state = [20, 21]
+            "#,
+        );
+        assert_eq!(
+            profile.problems(),
+            [r#"split 1 ("A"): state [10, 5] is empty, so it never splits"#]
+        );
+    }
+
+    #[test]
+    fn finds_duplicate_names() {
+        let profile = profile(
+            r#"
+            [[splits]]
+            name = "A"
+            state = [1, 2]
+            [[splits]]
+            name = "A"
+            state = [3, 4]
+            "#,
+        );
+        assert_eq!(
+            profile.problems(),
+            [r#"split 1 ("A") and split 2 ("A") have the same name"#]
+        );
+    }
+
+    #[test]
+    fn finds_overlapping_and_unreachable_splits() {
+        let profile = profile(
+            r#"
+            [[splits]]
+            name = "A"
+            state = [1, 10]
+            [[splits]]
+            name = "B"
+            state = [5, 15]
+            [[splits]]
+            name = "C"
+            state = [2, 3]
+            "#,
+        );
+        assert_eq!(
+            profile.problems(),
+            [
+                concat!(
+                    r#"split 1 ("A") and split 2 ("B") overlap on states 5 to 10; "#,
+                    "only the first splits there"
+                ),
+                concat!(
+                    r#"split 3 ("C") never splits: its states are all split 1 ("A")'s, "#,
+                    "which is checked first"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn splits_in_different_rooms_dont_overlap() {
+        let profile = profile(
+            r#"
+            [[splits]]
+            name = "A"
+            state = [1, 10]
+            room = [100, 100]
+            [[splits]]
+            name = "B"
+            state = [1, 10]
+            room = [101, 100]
+            "#,
+        );
+        assert_eq!(profile.problems(), Vec::<String>::new());
+    }
+}
@@ -0,0 +1,89 @@
+#![cfg(target_os = "linux")]
+
+use crate::game::backend::Backend;
+use crate::game::common::{field_address, GameObject, Timer, GAME_OBJECT_SIZE};
+use crate::game::{process_exists, Fields, Fingerprints, Gamestates, State, Target};
+use anyhow::{bail, Context, Result};
+use read_process_memory::{CopyAddress, Pid, ProcessHandle};
+use std::path::PathBuf;
+
+/// The Windows build of VVVVVV, running under Proton or Wine.
+///
+/// Wine maps `VVVVVV.exe` into the process like any other file, so we find where it's loaded from
+/// `/proc/PID/maps` and add the game object's offset for that build from the fingerprint
+/// database. There's no fallback: Windows builds have to be in the database.
+pub(super) struct Proton {
+    pid: Pid,
+    process: ProcessHandle,
+    addr: usize,
+    /// The build's gamestates, from its fingerprint record.
+    pub(super) gamestates: Option<Gamestates>,
+    /// The fields its fingerprint record maps.
+    pub(super) fields: Fields,
+    pub(super) target: Target,
+}
+
+impl Proton {
+    pub(super) fn find(pid: Pid, fingerprints: &Fingerprints) -> Result<Proton> {
+        let (base, path) = image(pid)?;
+        log::info!("{} loaded at {:#x}", path.display(), base);
+        let (target, build) = Target::inspect(path.clone(), fingerprints);
+        let Some(fingerprint) = &target.fingerprint else {
+            bail!("could not fingerprint {}", path.display());
+        };
+        let Some(build) = build else {
+            bail!(
+                "unknown Windows build {}; add its game object offset to fingerprints.toml",
+                fingerprint
+            );
+        };
+        Ok(Proton {
+            pid,
+            process: ProcessHandle::try_from(pid)?,
+            addr: base + build.offset,
+            gamestates: build.gamestates.clone(),
+            fields: build.fields.clone(),
+            target,
+        })
+    }
+}
+
+/// Find where `VVVVVV.exe` is mapped in a Wine process.
+fn image(pid: Pid) -> Result<(usize, PathBuf)> {
+    let maps = std::fs::read_to_string(format!("/proc/{}/maps", pid))?;
+    maps.lines()
+        .find_map(|line| {
+            // start-end perms offset dev inode path
+            let mut fields = line.splitn(6, ' ');
+            let (start, _) = fields.next()?.split_once('-')?;
+            let offset = fields.nth(1)?;
+            let path = fields.nth(2)?.trim_start();
+            let exe = path.rsplit('/').next()?.eq_ignore_ascii_case("VVVVVV.exe");
+            (exe && u64::from_str_radix(offset, 16).ok()? == 0)
+                .then(|| Some((usize::from_str_radix(start, 16).ok()?, PathBuf::from(path))))?
+        })
+        .with_context(|| format!("VVVVVV.exe is not mapped in pid {}", pid))
+}
+
+impl Backend for Proton {
+    fn address(&self) -> usize {
+        self.addr
+    }
+
+    fn read_game_object(&self) -> Result<(State, Timer<u32>)> {
+        let mut buf = [0; GAME_OBJECT_SIZE];
+        self.process.copy_address(self.addr, &mut buf)?;
+        Ok(GameObject::from(buf).into_state())
+    }
+
+    fn read_u32(&self, offset: isize) -> Result<u32> {
+        let mut buf = [0; 4];
+        self.process
+            .copy_address(field_address(self.addr, offset)?, &mut buf)?;
+        Ok(u32::from_ne_bytes(buf))
+    }
+
+    fn exited(&self) -> bool {
+        !process_exists(self.pid)
+    }
+}
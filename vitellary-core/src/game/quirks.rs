@@ -0,0 +1,102 @@
+use crate::game::{State, Target, Version};
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::path::Path;
+
+const BUILTIN: &str = include_str!("quirks.toml");
+
+/// Conditions on `state` changes that only look like splits in certain circumstances, from the
+/// game's scripts passing through a split's state on the way to another.
+#[derive(Debug, Clone, Default)]
+pub struct Quirks(Vec<Quirk>);
+
+#[derive(Deserialize)]
+struct File {
+    quirks: Vec<Quirk>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Quirk {
+    state: u32,
+    /// The room the state has to be reached in.
+    room: Option<(u32, u32)>,
+    /// The states it has to be reached from.
+    previous: Option<Vec<u32>>,
+    /// The game versions the quirk applies to; it applies to any version we couldn't detect.
+    since: Option<Version>,
+    before: Option<Version>,
+    #[serde(default)]
+    reason: String,
+}
+
+impl Quirks {
+    /// The built-in quirks.
+    pub fn builtin() -> Quirks {
+        let mut quirks = Quirks::default();
+        quirks
+            .parse(BUILTIN, Path::new("built-in quirks"))
+            .expect("built-in quirks are valid");
+        quirks
+    }
+
+    /// Load the built-in quirks, plus any in `quirks.toml` in `config_dir`.
+    pub fn load(config_dir: Option<&Path>) -> Result<Quirks> {
+        let mut quirks = Quirks::builtin();
+        if let Some(path) = config_dir.map(|dir| dir.join("quirks.toml")) {
+            if path.exists() {
+                let data = std::fs::read_to_string(&path)
+                    .with_context(|| format!("failed to read {}", path.display()))?;
+                quirks.parse(&data, &path)?;
+            }
+        }
+        Ok(quirks)
+    }
+
+    fn parse(&mut self, data: &str, path: &Path) -> Result<()> {
+        let parsed: File =
+            toml::from_str(data).with_context(|| format!("failed to parse {}", path.display()))?;
+        self.0.extend(parsed.quirks);
+        Ok(())
+    }
+
+    /// Only the quirks that apply to `target`.
+    pub(super) fn applying_to(&self, target: &Target) -> Quirks {
+        Quirks(
+            self.0
+                .iter()
+                .filter(|quirk| {
+                    target.version.is_none_or(|version| {
+                        quirk.since.is_none_or(|since| version >= since)
+                            && quirk.before.is_none_or(|before| version < before)
+                    })
+                })
+                .cloned()
+                .collect(),
+        )
+    }
+
+    /// Whether a quirk guards `state`: the game's scripts are known to pass through it on the way
+    /// to another state.
+    pub fn guards(&self, state: u32) -> bool {
+        self.0.iter().any(|quirk| quirk.state == state)
+    }
+
+    /// Whether the change from `old` to `cur` can count as reaching `cur.state`.
+    pub fn allow(&self, old: &State, cur: &State) -> bool {
+        self.0
+            .iter()
+            .filter(|quirk| quirk.state == cur.state)
+            .all(|quirk| {
+                let allowed = quirk.room.is_none_or(|room| room == cur.room)
+                    && quirk
+                        .previous
+                        .as_ref()
+                        .is_none_or(|previous| previous.contains(&old.state));
+                if !allowed {
+                    log::debug!("ignoring state {} ({})", cur.state, quirk.reason);
+                }
+                allowed
+            })
+    }
+}
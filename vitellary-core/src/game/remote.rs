@@ -0,0 +1,241 @@
+//! Reading the game on another machine. An agent on the machine running the game (`vitellary
+//! agent`) attaches to it and does nothing but read its memory, for a splitter elsewhere
+//! (`vitellary serve --remote`) that runs the timer logic and the outputs. They talk over TCP, or
+//! over the stdin and stdout of an agent the splitter runs with a command like `ssh HOST vitellary
+//! agent --stdio`, one JSON object per line: the agent first sends a [`Hello`] describing the game,
+//! then answers each [`Request`] with a [`Response`].
+
+use crate::game::backend::Backend;
+use crate::game::common::Timer;
+use crate::game::helper::MAX_DISTANCE;
+use crate::game::{AccessError, Fields, Game, Gamestates, State, Target};
+use anyhow::{anyhow, bail, Context, Result};
+use read_process_memory::Pid;
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// The port agents listen on unless told otherwise.
+pub const DEFAULT_PORT: u16 = 5557;
+
+/// How long the splitter waits for an answer before taking the agent as gone.
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// What the agent knows about the game, sent when the splitter connects.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Hello {
+    pub pid: Pid,
+    pub address: usize,
+    pub target: Target,
+    pub gamestates: Gamestates,
+    pub fields: Fields,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Request {
+    /// Read the game object.
+    GameObject,
+    /// Read a field this many bytes from the start of the game object.
+    U32(isize),
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Response {
+    GameObject(State, Timer<u32>),
+    U32(u32),
+    /// The read failed; `exited` if it's because the game is gone.
+    Error {
+        message: String,
+        exited: bool,
+    },
+}
+
+/// Answer the splitter connected on `stream` with reads of `game` until it disconnects. Fails
+/// with [`AccessError::ProcessGone`] once the game exits.
+pub fn serve(game: &Game, stream: TcpStream) -> Result<()> {
+    stream.set_nodelay(true)?;
+    let writer = stream.try_clone()?;
+    answer(game, BufReader::new(stream), writer)
+}
+
+/// Answer the splitter on stdin and stdout (which ran us, e.g. over SSH) with reads of `game`
+/// until it closes stdin. Fails with [`AccessError::ProcessGone`] once the game exits.
+pub fn serve_stdio(game: &Game) -> Result<()> {
+    answer(game, std::io::stdin().lock(), std::io::stdout().lock())
+}
+
+fn answer(game: &Game, reader: impl BufRead, mut writer: impl Write) -> Result<()> {
+    let hello = Hello {
+        pid: game.pid,
+        address: game.backend.address(),
+        target: game.target.clone(),
+        gamestates: game.gamestates.clone(),
+        fields: game.fields.clone(),
+    };
+    writeln!(writer, "{}", serde_json::to_string(&hello)?)?;
+    writer.flush()?;
+    for line in reader.lines() {
+        let response = match serde_json::from_str(&line?) {
+            Ok(Request::GameObject) => game
+                .backend
+                .read_game_object()
+                .map(|(state, timer)| Response::GameObject(state, timer)),
+            Ok(Request::U32(offset)) if !(-MAX_DISTANCE..MAX_DISTANCE).contains(&offset) => {
+                Err(anyhow!("refusing to read that far from the game object"))
+            }
+            Ok(Request::U32(offset)) => game.backend.read_u32(offset).map(Response::U32),
+            Err(err) => Err(anyhow!("invalid request: {}", err)),
+        };
+        let exited = response.is_err() && game.backend.exited();
+        let response = response.unwrap_or_else(|err| Response::Error {
+            message: format!("{:#}", err),
+            exited,
+        });
+        writeln!(writer, "{}", serde_json::to_string(&response)?)?;
+        writer.flush()?;
+        if exited {
+            return Err(anyhow!(AccessError::ProcessGone { pid: game.pid }));
+        }
+    }
+    Ok(())
+}
+
+/// The game, read through an agent.
+pub(super) struct Remote {
+    addr: usize,
+    connection: Mutex<Connection>,
+    exited: AtomicBool,
+    /// The command the agent runs in, if we started it.
+    _child: Option<Child>,
+}
+
+struct Connection {
+    writer: Box<dyn Write + Send>,
+    /// The agent's lines, read on a thread of their own so requests can time out whatever the
+    /// connection is.
+    lines: Receiver<std::io::Result<String>>,
+}
+
+impl Remote {
+    /// Connect to the agent at `addr`, returning what it says about the game.
+    pub(super) fn connect(addr: &str) -> Result<(Remote, Hello)> {
+        let addrs: Vec<_> = match addr.to_socket_addrs() {
+            Ok(addrs) => addrs.collect(),
+            Err(_) => (addr, DEFAULT_PORT)
+                .to_socket_addrs()
+                .with_context(|| format!("invalid agent address {:?}", addr))?
+                .collect(),
+        };
+        let stream = addrs
+            .into_iter()
+            .find_map(|addr| TcpStream::connect_timeout(&addr, TIMEOUT).ok())
+            .with_context(|| format!("could not connect to the agent at {}", addr))?;
+        stream.set_nodelay(true)?;
+        let reader = BufReader::new(stream.try_clone()?);
+        Remote::start(Box::new(stream), reader, None)
+            .with_context(|| format!("the agent at {}", addr))
+    }
+
+    /// Run `agent`, a command that runs `vitellary agent --stdio` (e.g. on another machine over
+    /// SSH), returning what it says about the game.
+    pub(super) fn spawn(mut agent: Command) -> Result<(Remote, Hello)> {
+        let program = agent.get_program().to_string_lossy().into_owned();
+        let mut child = agent
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .with_context(|| format!("failed to run {}", program))?;
+        let stdin = child.stdin.take().context("the agent has no stdin")?;
+        let stdout = BufReader::new(child.stdout.take().context("the agent has no stdout")?);
+        Remote::start(Box::new(stdin), stdout, Some(child))
+            .with_context(|| format!("the agent run by {}", program))
+    }
+
+    /// Start reading the agent's lines from `reader`, and wait for its hello.
+    fn start(
+        writer: Box<dyn Write + Send>,
+        reader: impl BufRead + Send + 'static,
+        child: Option<Child>,
+    ) -> Result<(Remote, Hello)> {
+        let (sender, lines) = mpsc::channel();
+        std::thread::spawn(move || {
+            for line in reader.lines() {
+                if sender.send(line).is_err() {
+                    return;
+                }
+            }
+        });
+        // The agent only answers once it's attached to the game, which may take a while.
+        let Ok(line) = lines.recv() else {
+            bail!("disconnected");
+        };
+        let hello: Hello = serde_json::from_str(&line?).context("sent an invalid hello")?;
+        Ok((
+            Remote {
+                addr: hello.address,
+                connection: Mutex::new(Connection { writer, lines }),
+                exited: AtomicBool::new(false),
+                _child: child,
+            },
+            hello,
+        ))
+    }
+
+    fn request(&self, request: &Request) -> Result<Response> {
+        let mut connection = self.connection.lock().unwrap();
+        let result = (|| -> Result<Response> {
+            writeln!(connection.writer, "{}", serde_json::to_string(request)?)?;
+            connection.writer.flush()?;
+            let line = match connection.lines.recv_timeout(TIMEOUT) {
+                Ok(line) => line?,
+                Err(RecvTimeoutError::Timeout) => bail!("the agent didn't answer in time"),
+                Err(RecvTimeoutError::Disconnected) => bail!("the agent disconnected"),
+            };
+            Ok(serde_json::from_str(&line)?)
+        })();
+        match result {
+            Ok(Response::Error { message, exited }) => {
+                self.exited.store(exited, Ordering::Relaxed);
+                bail!("agent: {}", message)
+            }
+            Ok(response) => Ok(response),
+            Err(err) => {
+                // Without the agent there's no game to read, so go through the same motions as
+                // when the game exits.
+                self.exited.store(true, Ordering::Relaxed);
+                Err(err.context("lost the connection to the agent"))
+            }
+        }
+    }
+}
+
+impl Backend for Remote {
+    fn address(&self) -> usize {
+        self.addr
+    }
+
+    fn read_game_object(&self) -> Result<(State, Timer<u32>)> {
+        match self.request(&Request::GameObject)? {
+            Response::GameObject(state, timer) => Ok((state, timer)),
+            _ => bail!("the agent sent the wrong kind of response"),
+        }
+    }
+
+    fn read_u32(&self, offset: isize) -> Result<u32> {
+        match self.request(&Request::U32(offset))? {
+            Response::U32(value) => Ok(value),
+            _ => bail!("the agent sent the wrong kind of response"),
+        }
+    }
+
+    fn exited(&self) -> bool {
+        self.exited.load(Ordering::Relaxed)
+    }
+}
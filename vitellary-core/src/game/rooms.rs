@@ -0,0 +1,71 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::Path;
+
+const BUILTIN: &str = include_str!("rooms.toml");
+
+/// Names of the main game's rooms, by map coordinates.
+#[derive(Debug, Clone, Default)]
+pub struct RoomNames(HashMap<(u32, u32), String>);
+
+#[derive(Deserialize)]
+struct File {
+    rooms: Vec<Room>,
+}
+
+#[derive(Deserialize)]
+struct Room {
+    x: u32,
+    y: u32,
+    name: String,
+}
+
+impl RoomNames {
+    /// The built-in names.
+    pub fn builtin() -> RoomNames {
+        let mut names = RoomNames::default();
+        names
+            .parse(BUILTIN, Path::new("built-in room names"))
+            .expect("built-in room names are valid");
+        names
+    }
+
+    /// Load the built-in names, overridden by any in `rooms.toml` in `config_dir`.
+    pub fn load(config_dir: Option<&Path>) -> Result<RoomNames> {
+        let mut names = RoomNames::builtin();
+        if let Some(path) = config_dir.map(|dir| dir.join("rooms.toml")) {
+            if path.exists() {
+                let data = std::fs::read_to_string(&path)
+                    .with_context(|| format!("failed to read {}", path.display()))?;
+                names.parse(&data, &path)?;
+            }
+        }
+        Ok(names)
+    }
+
+    fn parse(&mut self, data: &str, path: &Path) -> Result<()> {
+        let parsed: File =
+            toml::from_str(data).with_context(|| format!("failed to parse {}", path.display()))?;
+        self.0.extend(
+            parsed
+                .rooms
+                .into_iter()
+                .map(|room| ((room.x, room.y), room.name)),
+        );
+        Ok(())
+    }
+
+    pub fn get(&self, room: (u32, u32)) -> Option<&str> {
+        self.0.get(&room).map(String::as_str)
+    }
+
+    /// A room's name followed by its coordinates, or just the coordinates if we don't know its
+    /// name.
+    pub fn describe(&self, room: (u32, u32)) -> String {
+        match self.get(room) {
+            Some(name) => format!("{} {:?}", name, room),
+            None => format!("{:?}", room),
+        }
+    }
+}
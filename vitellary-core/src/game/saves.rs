@@ -0,0 +1,42 @@
+use crate::game::backend::Backend;
+use crate::game::string;
+use serde::Serialize;
+
+/// What the load menu shows about a save slot.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SaveSummary {
+    /// The game time of the save, like `12:34`.
+    pub time: String,
+    /// The area it was made in.
+    pub area: String,
+    pub trinkets: u32,
+}
+
+/// The save slots practicing runners load from, where the build's fields say where they are.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Saves {
+    /// The save made at the last teleporter (or rescue).
+    pub telesave: Option<SaveSummary>,
+    /// The save made from the pause menu.
+    pub quicksave: Option<SaveSummary>,
+}
+
+impl Saves {
+    pub fn is_empty(&self) -> bool {
+        self.telesave.is_none() && self.quicksave.is_none()
+    }
+}
+
+/// Read a save slot's summary from `offset`, where its game time (`game.tele_gametime` or
+/// `game.quick_gametime`) is, followed by its trinkets and its area.
+pub(super) fn read(backend: &dyn Backend, offset: isize) -> Option<SaveSummary> {
+    let (time, size) = string::read(backend, offset)?;
+    let trinkets = backend.read_u32(offset + size).ok()?;
+    // The trinket count is padded to the strings' alignment.
+    let (area, _) = string::read(backend, offset + size + 8)?;
+    Some(SaveSummary {
+        time,
+        area,
+        trinkets,
+    })
+}
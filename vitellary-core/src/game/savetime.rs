@@ -0,0 +1,91 @@
+use crate::game::backend::Backend;
+use crate::game::scan::OFFSET_SAVETIME;
+use crate::game::string;
+use crate::game::Event;
+use std::time::Duration;
+
+/// How long after a rescue cutscene's states the game can still save for it.
+const WINDOW: Duration = Duration::from_secs(10);
+
+/// Watches `game.savetime`, the game time of the last save, which changes when the game saves
+/// after rescuing a crewmate. That confirms the crewmate's split, or makes up for it when the
+/// cutscene's state change was missed (e.g. when attaching in the middle of it).
+#[derive(Debug, Default)]
+pub(super) struct SaveCheck {
+    watching: Option<Watch>,
+}
+
+#[derive(Debug)]
+struct Watch {
+    /// The split for the rescue.
+    event: Event,
+    /// When the game left the rescue cutscene's states.
+    left: Option<Duration>,
+    /// `game.savetime` when the cutscene started, if we could read it.
+    savetime: Option<String>,
+}
+
+impl SaveCheck {
+    /// Follow the game at `now` (by a monotonic clock), where `rescue` is the crewmate split whose
+    /// states the game is in, if any, and `backend` reads the game. Returns the split when the game
+    /// saves for its rescue.
+    pub(super) fn update(
+        &mut self,
+        now: Duration,
+        rescue: Option<Event>,
+        backend: &dyn Backend,
+    ) -> Option<Event> {
+        match (rescue, &mut self.watching) {
+            (Some(event), Some(watch)) if watch.event == event => watch.left = None,
+            (Some(event), _) => {
+                self.watching = Some(Watch {
+                    event,
+                    left: None,
+                    savetime: read(backend),
+                });
+                return None;
+            }
+            (None, Some(watch)) => {
+                let left = *watch.left.get_or_insert(now);
+                if now.saturating_sub(left) > WINDOW {
+                    self.watching = None;
+                    return None;
+                }
+            }
+            (None, None) => return None,
+        }
+        let watch = self.watching.as_mut()?;
+        let savetime = read(backend)?;
+        match &watch.savetime {
+            Some(before) if *before != savetime => {
+                log::debug!("game saved at {} for {:?}", savetime, watch.event);
+                self.watching.take().map(|watch| watch.event)
+            }
+            Some(_) => None,
+            None => {
+                watch.savetime = Some(savetime);
+                None
+            }
+        }
+    }
+
+    /// Stop watching, on a new game or reset.
+    pub(super) fn clear(&mut self) {
+        self.watching = None;
+    }
+}
+
+/// Read `game.savetime`, returning `None` if it doesn't look like a time.
+fn read(backend: &dyn Backend) -> Option<String> {
+    let (savetime, _) = string::read(backend, isize::try_from(OFFSET_SAVETIME).ok()?)?;
+    (savetime.contains(':') && savetime.bytes().all(|b| b.is_ascii_digit() || b == b':'))
+        .then_some(savetime)
+}
+
+/// Read `game.savetime` and `game.savearea`, which come after it and change when a save is loaded.
+pub(super) fn read_save(backend: &dyn Backend) -> Option<(String, String)> {
+    let offset = isize::try_from(OFFSET_SAVETIME).ok()?;
+    let (savetime, size) = string::read(backend, offset)?;
+    let (savearea, _) = string::read(backend, offset + size)?;
+    Some((savetime, savearea))
+}
@@ -0,0 +1,285 @@
+use regex::bytes::Regex;
+use std::ops::Range;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Offset of `game.savetime` in the game object.
+pub(super) const OFFSET_SAVETIME: usize = 0xb8;
+/// How much memory is read at a time.
+const CHUNK: usize = 4096;
+/// How much consecutive chunks overlap: 5 words, so a signature straddling a boundary between
+/// chunks is whole in one of them.
+const OVERLAP: usize = 0x28;
+
+static SCANNING: AtomicBool = AtomicBool::new(false);
+static CANCELLED: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Scan {
+    /// The address of the game object.
+    Found(usize),
+    NotFound,
+    /// Cancelled with `cancel`; scanning can be resumed from this address.
+    Cancelled(usize),
+}
+
+/// Cancel a scan in progress, returning false if there isn't one.
+pub fn cancel() -> bool {
+    CANCELLED.store(true, Ordering::Relaxed);
+    SCANNING.load(Ordering::Relaxed)
+}
+
+/// Scan `range` for the game object a page at a time, using `read` to copy memory (returning
+/// false for unreadable pages). Progress is logged every 10%.
+///
+/// Thanks to the [initial values][init] of `game.savetime` and `game.savearea`, and the
+/// [implementation details of short string optimizatzion][sso] in libc++, we can just search for
+/// two 3-word buffers that contain "00:00" and "nowhere" next to each other. The start of the game
+/// object is a fixed offset before the word containing "00:00".
+///
+/// [init]: https://github.com/TerryCavanagh/VVVVVV/blob/abe3eb607711909aeb6941a471225867a94510d0/desktop_version/src/Game.cpp#L227
+/// [sso]: https://joellaity.com/2020/01/31/string.html
+pub fn scan(range: Range<usize>, mut read: impl FnMut(usize, &mut [u8]) -> bool) -> Scan {
+    let regex = Regex::new(r"00:00\x00{18}.nowhere").unwrap();
+    let mut buf = [0; CHUNK];
+    let step = CHUNK - OVERLAP;
+    let len = range.len().max(1);
+    let mut readable = 0;
+    let mut reported = 0;
+
+    SCANNING.store(true, Ordering::Relaxed);
+    CANCELLED.store(false, Ordering::Relaxed);
+    let result = 'scan: {
+        for address in range.clone().step_by(step) {
+            if CANCELLED.load(Ordering::Relaxed) {
+                break 'scan Scan::Cancelled(address);
+            }
+            let percent = (address - range.start) * 100 / len;
+            if percent >= reported + 10 {
+                reported = percent - percent % 10;
+                log::info!(
+                    "scanning for game object: {}% ({} readable pages)",
+                    reported,
+                    readable
+                );
+            }
+            if read(address, &mut buf) {
+                readable += 1;
+                if let Some(m) = regex.find(&buf) {
+                    // macOS libc++ differs in `_LIBCPP_ALTERNATE_STRING_LAYOUT` between x86_64
+                    // and aarch64; on the former, the first byte contains the is_long bit. We
+                    // just want the start of the word where "00:00" showed up, which is aligned
+                    // in memory (the range we were given might not be).
+                    let found = address + m.start();
+                    break 'scan Scan::Found(found - found % 8 - OFFSET_SAVETIME);
+                }
+            }
+        }
+        Scan::NotFound
+    };
+    SCANNING.store(false, Ordering::Relaxed);
+    result
+}
+
+/// Parse a `--scan-range` argument: two hexadecimal addresses separated by `..`.
+pub fn parse_range(s: &str) -> Result<Range<usize>, String> {
+    let err = || format!("expected START..END in hexadecimal, got {:?}", s);
+    let (start, end) = s.split_once("..").ok_or_else(err)?;
+    let parse = |s: &str| {
+        usize::from_str_radix(s.trim_start_matches("0x").replace('_', "").as_str(), 16)
+            .map_err(|_| err())
+    };
+    let range = parse(start)?..parse(end)?;
+    if range.is_empty() {
+        return Err(err());
+    }
+    Ok(range)
+}
+
+/// Where a cancelled scan of `pid` left off.
+pub fn resume_point(pid: i32) -> Option<usize> {
+    let path = resume_path(pid)?;
+    let address = std::fs::read_to_string(path).ok()?.trim().parse().ok()?;
+    log::info!("resuming scan from {:#x}", address);
+    Some(address)
+}
+
+/// Remember where a scan of `pid` left off, or forget it with `None`.
+pub fn save_resume_point(pid: i32, address: Option<usize>) {
+    let Some(path) = resume_path(pid) else {
+        return;
+    };
+    let result = match address {
+        Some(address) => path
+            .parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|()| std::fs::write(&path, address.to_string())),
+        None => match std::fs::remove_file(&path) {
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            result => result,
+        },
+    };
+    if let Err(err) = result {
+        log::warn!("failed to update {}: {}", path.display(), err);
+    }
+}
+
+fn resume_path(pid: i32) -> Option<PathBuf> {
+    Some(
+        dirs::cache_dir()?
+            .join("vitellary")
+            .join(format!("scan-{}", pid)),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// How many random memory images each property is checked against. Each case is generated
+    /// from its number, so a failing one can be rerun on its own.
+    const CASES: u64 = 300;
+
+    /// SplitMix64: enough randomness for generating images, without a dependency.
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+            let mut z = self.0;
+            z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+            z ^ (z >> 31)
+        }
+
+        fn coin(&mut self) -> bool {
+            self.next().is_multiple_of(2)
+        }
+
+        /// A number in `range`.
+        fn range(&mut self, range: Range<usize>) -> usize {
+            range.start + usize::try_from(self.next() % range.len() as u64).unwrap()
+        }
+    }
+
+    /// A synthetic stretch of memory: noise, with room past its end for the last chunk's read.
+    struct Image {
+        range: Range<usize>,
+        data: Vec<u8>,
+    }
+
+    impl Image {
+        fn new(rng: &mut Rng, range: Range<usize>) -> Image {
+            let data = (0..range.len() + CHUNK)
+                .map(|_| rng.next().to_le_bytes()[0])
+                .collect();
+            Image { range, data }
+        }
+
+        /// Put the strings of a game object at `object`, in either of libc++'s short string
+        /// layouts: with the length (shifted past the is_long bit) first, or with the alternate
+        /// layout, last (and unshifted).
+        fn place(&mut self, object: usize, alternate: bool) {
+            let at = object - self.range.start + OFFSET_SAVETIME;
+            let strings = &mut self.data[at..at + 48];
+            strings.fill(0);
+            for (string, s) in strings.chunks_mut(24).zip(["00:00", "nowhere"]) {
+                let len = u8::try_from(s.len()).unwrap();
+                let (len, at, data) = if alternate {
+                    (len, 23, 0)
+                } else {
+                    (len << 1, 0, 1)
+                };
+                string[at] = len;
+                string[data..data + s.len()].copy_from_slice(s.as_bytes());
+            }
+        }
+
+        fn scan(&self) -> Scan {
+            scan(self.range.clone(), |address, buf| {
+                let at = address - self.range.start;
+                buf.copy_from_slice(&self.data[at..at + buf.len()]);
+                true
+            })
+        }
+    }
+
+    /// The address of the first chunk read that starts at or after `address`.
+    fn chunk_after(range: &Range<usize>, address: usize) -> usize {
+        let step = CHUNK - OVERLAP;
+        range.start + (address - range.start).div_ceil(step) * step
+    }
+
+    #[test]
+    fn finds_objects_anywhere() {
+        for case in 0..CASES {
+            let mut rng = Rng(case);
+            // Ranges given with --scan-range needn't be aligned.
+            let start = rng.range(0x1000..0x10_0000);
+            let range = start..start + rng.range(0x100..0x8000);
+            let object = (rng.range(range.start..range.end - OFFSET_SAVETIME - 48) & !7)
+                .max((range.start + 7) & !7);
+            let alternate = rng.coin();
+            let mut image = Image::new(&mut rng, range.clone());
+            image.place(object, alternate);
+            assert_eq!(
+                image.scan(),
+                Scan::Found(object),
+                "case {}: {:#x}..{:#x}, alternate layout {}",
+                case,
+                range.start,
+                range.end,
+                alternate
+            );
+        }
+    }
+
+    #[test]
+    fn finds_objects_straddling_chunks() {
+        for case in 0..CASES {
+            let mut rng = Rng(case);
+            let start = rng.range(0x1000..0x10_0000);
+            let range = start..start + rng.range(4 * CHUNK..8 * CHUNK);
+            // Put the signature within an overlap's length of where a chunk starts or ends, so
+            // it's cut off in one chunk (or only just fits) and has to be found in the other.
+            let chunk = chunk_after(
+                &range,
+                rng.range(range.start + CHUNK..range.end - 3 * CHUNK),
+            );
+            let boundary = if rng.coin() { chunk } else { chunk + CHUNK };
+            let signature = rng.range(boundary - OVERLAP..boundary + OVERLAP);
+            let alternate = rng.coin();
+            let object = (signature - OFFSET_SAVETIME - usize::from(!alternate)) & !7;
+            let mut image = Image::new(&mut rng, range.clone());
+            image.place(object, alternate);
+            assert_eq!(
+                image.scan(),
+                Scan::Found(object),
+                "case {}: {:#x}..{:#x}, signature at {:#x}, alternate layout {}",
+                case,
+                range.start,
+                range.end,
+                signature,
+                alternate
+            );
+        }
+    }
+
+    #[test]
+    fn finds_nothing_without_the_signature() {
+        for case in 0..CASES {
+            let mut rng = Rng(case);
+            let start = rng.range(0x1000..0x10_0000);
+            let range = start..start + rng.range(0x100..0x8000);
+            let object = (rng.range(range.start..range.end - OFFSET_SAVETIME - 48) & !7)
+                .max((range.start + 7) & !7);
+            let alternate = rng.coin();
+            let mut image = Image::new(&mut rng, range.clone());
+            image.place(object, alternate);
+            // Spoil a letter of "nowhere", like an object that isn't the game's.
+            let letter = rng.range(0..7) + usize::from(!alternate);
+            image.data[object - range.start + OFFSET_SAVETIME + 24 + letter] = b'?';
+            assert_eq!(image.scan(), Scan::NotFound, "case {}", case);
+        }
+    }
+}
@@ -0,0 +1,63 @@
+use crate::game::backend::Backend;
+
+/// The longest string read from the heap, since a garbage length would otherwise mean a huge read.
+const MAX_LEN: usize = 64;
+
+/// Read the `std::string` `offset` bytes from the game object, returning it and how big a
+/// `std::string` is in the build's standard library: 24 bytes in libc++, 32 in libstdc++. Strings
+/// kept on the heap are only readable with backends that can read that far from the game object.
+pub(super) fn read(backend: &dyn Backend, offset: isize) -> Option<(String, isize)> {
+    let bytes = read_bytes(backend, offset, 32)?;
+    let word = |i: usize| u64::from_ne_bytes(bytes[i * 8..i * 8 + 8].try_into().unwrap());
+    let here = backend.address().checked_add_signed(offset)?;
+    // libstdc++ keeps short strings in a buffer after the pointer to the characters and the
+    // length.
+    if usize::try_from(word(0)).ok() == here.checked_add(16) {
+        let len = usize::try_from(word(1)).ok().filter(|&len| len < 16)?;
+        return text(&bytes[16..16 + len]).map(|s| (s, 32));
+    }
+    // libc++ keeps short strings in the object itself: in the alternate layout the characters
+    // come first with the length in the last byte; otherwise (macOS on x86_64) the length, shifted
+    // left by one, comes first.
+    let len = usize::from(bytes[23]);
+    if len < 23 && bytes[len] == 0 {
+        if let Some(s) = text(&bytes[..len]) {
+            return Some((s, 24));
+        }
+    }
+    let len = usize::from(bytes[0] >> 1);
+    if bytes[0] & 1 == 0 && len < 23 && bytes[len + 1] == 0 {
+        if let Some(s) = text(&bytes[1..=len]) {
+            return Some((s, 24));
+        }
+    }
+    // Longer strings are on the heap, with the pointer to them first and then the length (in
+    // libstdc++ and libc++'s alternate layout, where the capacity's top bit marks a long string).
+    let len = usize::try_from(word(1))
+        .ok()
+        .filter(|&len| len > 0 && len <= MAX_LEN)?;
+    let at = isize::try_from(word(0)).ok()? - isize::try_from(backend.address()).ok()?;
+    let size = if bytes[23] & 0x80 == 0 { 32 } else { 24 };
+    text(&read_bytes(backend, at, len)?).map(|s| (s, size))
+}
+
+/// Read `len` bytes `offset` bytes from the game object.
+fn read_bytes(backend: &dyn Backend, offset: isize, len: usize) -> Option<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(len + 3);
+    for at in (0..len).step_by(4) {
+        let value = backend
+            .read_u32(offset.checked_add(isize::try_from(at).ok()?)?)
+            .ok()?;
+        bytes.extend_from_slice(&value.to_ne_bytes());
+    }
+    bytes.truncate(len);
+    Some(bytes)
+}
+
+/// `bytes` as a string, if they look like one.
+fn text(bytes: &[u8]) -> Option<String> {
+    std::str::from_utf8(bytes)
+        .ok()
+        .filter(|s| !s.chars().any(char::is_control))
+        .map(str::to_owned)
+}
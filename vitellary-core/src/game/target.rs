@@ -0,0 +1,70 @@
+use crate::game::fingerprint::{self, Build};
+use crate::game::version::Version;
+use crate::game::Fingerprints;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// The game executable we attached to, as far as we could identify it. It's logged, reported to
+/// outputs, and recorded with each run, so it can back up a leaderboard submission.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Target {
+    pub path: Option<PathBuf>,
+    /// The version the title screen shows, found in the executable.
+    pub version: Option<Version>,
+    /// The executable's fingerprint, as used in `fingerprints.toml`.
+    pub fingerprint: Option<String>,
+    /// The architecture the executable was compiled for.
+    pub arch: Option<String>,
+    /// The name of the build, if the fingerprint database knows it.
+    pub build: Option<String>,
+}
+
+impl Target {
+    /// Identify the executable at `path`, returning its record in `fingerprints` too, if there is
+    /// one.
+    pub(super) fn inspect(path: PathBuf, fingerprints: &Fingerprints) -> (Target, Option<&Build>) {
+        log::info!("executable: {}", path.display());
+        let version = match Version::detect(&path) {
+            Ok(Some(version)) => {
+                log::info!("detected VVVVVV {}", version);
+                Some(version)
+            }
+            Ok(None) => {
+                log::warn!("could not detect VVVVVV version; assuming 2.3");
+                None
+            }
+            Err(err) => {
+                log::warn!("could not detect VVVVVV version: {:#}", err);
+                None
+            }
+        };
+        let fingerprint = match fingerprint::fingerprint(&path) {
+            Ok(fingerprint) => fingerprint,
+            Err(err) => {
+                log::warn!("could not fingerprint executable: {:#}", err);
+                None
+            }
+        };
+        let build = fingerprint.as_ref().and_then(|fingerprint| {
+            let build = fingerprints.lookup(fingerprint);
+            match build {
+                Some(build) if build.is_fallback() => {
+                    log::info!("unknown build {}; assuming {}", fingerprint, build.name);
+                }
+                Some(build) => log::info!("recognized build {} ({})", build.name, fingerprint),
+                None => log::info!("unknown build {}", fingerprint),
+            }
+            build
+        });
+        let target = Target {
+            path: Some(path),
+            version,
+            fingerprint: fingerprint.as_ref().map(|f| f.id().to_owned()),
+            arch: fingerprint.as_ref().map(|f| f.arch.to_owned()),
+            build: build
+                .filter(|build| !build.is_fallback())
+                .map(|build| build.name.clone()),
+        };
+        (target, build)
+    }
+}
@@ -0,0 +1,68 @@
+use serde::Serialize;
+use std::ops::RangeInclusive;
+use std::time::Duration;
+
+/// The states of the time trial results screen. The game fills in the results in state 82, so
+/// they're only read once it's moved on from there.
+pub(super) const RESULT_STATES: RangeInclusive<u32> = 83..=89;
+
+/// The number of `int` fields from `timetrialresulttime` on, in the order the game declares them:
+/// `timetrialresulttime`, `timetrialresultframes`, `timetrialrank`,
+/// `timetrialresultshinytarget`, `timetrialresulttrinkets`, `timetrialresultpar`, and
+/// `timetrialresultdeaths`.
+pub(super) const RESULT_FIELDS: usize = 7;
+
+/// The rank the game awards for a time trial.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum Rank {
+    B,
+    A,
+    S,
+    V,
+}
+
+impl Rank {
+    fn from_game(rank: u32) -> Option<Rank> {
+        Some(match rank {
+            0 => Rank::B,
+            1 => Rank::A,
+            2 => Rank::S,
+            3 => Rank::V,
+            _ => return None,
+        })
+    }
+}
+
+/// The results of a completed time trial. Where the build's fields don't map the game's own
+/// results, the time and deaths come from the game's timer and death counter instead, and the
+/// rest are `None`.
+#[derive(Debug, Clone, Serialize)]
+pub struct TimeTrial {
+    /// `timetriallevel`: which time trial, from 0 (Space Station 1) to 5 (The Final Level).
+    pub level: Option<u32>,
+    #[serde(serialize_with = "super::serialize_secs")]
+    pub time: Duration,
+    pub deaths: u32,
+    pub trinkets: Option<u32>,
+    /// The trinkets the trial has.
+    pub trinket_target: Option<u32>,
+    /// The par time in seconds.
+    pub par: Option<u32>,
+    pub rank: Option<Rank>,
+}
+
+impl TimeTrial {
+    /// The results from the game's own fields, starting at `timetrialresulttime`.
+    pub(super) fn from_results(level: Option<u32>, results: [u32; RESULT_FIELDS]) -> TimeTrial {
+        let [secs, frames, rank, trinket_target, trinkets, par, deaths] = results;
+        TimeTrial {
+            level,
+            time: Duration::from_secs(secs.into()) + Duration::from_secs(frames.into()) / 30,
+            deaths,
+            trinkets: Some(trinkets),
+            trinket_target: Some(trinket_target),
+            par: Some(par),
+            rank: Rank::from_game(rank),
+        }
+    }
+}
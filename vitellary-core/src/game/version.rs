@@ -0,0 +1,74 @@
+use anyhow::{Context, Result};
+use regex::bytes::Regex;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+/// A VVVVVV release version, e.g. 2.3.6.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Version {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+
+impl Version {
+    /// The first release where the game object is a global, which is what the backends look for.
+    pub const V2_3: Version = Version {
+        major: 2,
+        minor: 3,
+        patch: 0,
+    };
+
+    /// Find the version string the title screen prints (e.g. "v2.3.6") in the game executable.
+    pub fn detect(executable: &Path) -> Result<Option<Version>> {
+        let data = std::fs::read(executable)
+            .with_context(|| format!("failed to read {}", executable.display()))?;
+        let regex = Regex::new(r"\x00v(2\.[0-9]+(?:\.[0-9]+)?)\x00").unwrap();
+        Ok(regex
+            .captures(&data)
+            .and_then(|c| std::str::from_utf8(&c[1]).ok()?.parse().ok()))
+    }
+}
+
+impl FromStr for Version {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Version, String> {
+        let err = || format!("invalid version {:?}", s);
+        let mut parts = s.trim_start_matches('v').split('.');
+        let mut next = || -> Result<Option<u32>, String> {
+            parts.next().map(str::parse).transpose().map_err(|_| err())
+        };
+        let version = Version {
+            major: next()?.ok_or_else(err)?,
+            minor: next()?.ok_or_else(err)?,
+            patch: next()?.unwrap_or(0),
+        };
+        if next()?.is_some() {
+            return Err(err());
+        }
+        Ok(version)
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+impl Serialize for Version {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Version {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Version, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
@@ -0,0 +1,29 @@
+//! Reading VVVVVV's memory and turning what it finds into splits.
+
+#![warn(clippy::pedantic)]
+#![allow(
+    clippy::assertions_on_constants,
+    clippy::doc_markdown,
+    clippy::missing_errors_doc,
+    clippy::missing_panics_doc,
+    clippy::module_name_repetitions,
+    clippy::must_use_candidate,
+    clippy::struct_field_names,
+    clippy::uninlined_format_args
+)]
+
+pub mod game;
+
+use std::time::Duration;
+
+/// Format a time as `H:MM:SS.CC`.
+pub fn format_time(time: Duration) -> String {
+    let secs = time.as_secs();
+    format!(
+        "{}:{:02}:{:02}.{:02}",
+        secs / 3600,
+        secs / 60 % 60,
+        secs % 60,
+        time.subsec_nanos() / 10_000_000
+    )
+}
@@ -0,0 +1,52 @@
+//! Query a history file (as written by `vitellary --history`) for one segment's practice: how
+//! often it's been attempted and reset, and each personal best, oldest first.
+//!
+//! ```sh
+//! cargo run -p vitellary-protocols --example history -- history.jsonl vermilion
+//! ```
+
+use anyhow::{bail, Context, Result};
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+use vitellary_core::format_time;
+use vitellary_core::game::Event;
+use vitellary_protocols::stats;
+
+fn main() -> Result<()> {
+    let mut args = std::env::args().skip(1);
+    let (Some(path), Some(segment)) = (args.next(), args.next()) else {
+        bail!("usage: history PATH SEGMENT");
+    };
+    let path = PathBuf::from(path);
+    let segment: Event = segment.parse().map_err(anyhow::Error::msg)?;
+
+    let report = stats::report(&path)?;
+    let practiced = report
+        .segments
+        .iter()
+        .find(|practiced| practiced.segment == segment)
+        .with_context(|| format!("{:?} hasn't been practiced in {}", segment, path.display()))?;
+
+    println!(
+        "{:?}: {} attempts, {} reset",
+        segment, practiced.attempts, practiced.resets
+    );
+    if let (Some(best), Some(average)) = (practiced.best, practiced.average) {
+        println!(
+            "best {}, average {}",
+            format_time(Duration::from_secs_f64(best)),
+            format_time(Duration::from_secs_f64(average))
+        );
+    }
+    for pb in &practiced.personal_bests {
+        let ago = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH + Duration::from_secs(pb.started))
+            .unwrap_or_default();
+        println!(
+            "{:>12}  {} days ago",
+            format_time(Duration::from_secs_f64(pb.time)),
+            ago.as_secs() / (24 * 60 * 60)
+        );
+    }
+    Ok(())
+}
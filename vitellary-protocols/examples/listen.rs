@@ -0,0 +1,70 @@
+//! The smallest useful client: follow vitellary's JSON feed and print each event as it happens.
+//!
+//! ```sh
+//! cargo run -p vitellary-protocols --example listen -- ws://127.0.0.1:5555/json
+//! ```
+
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use std::time::Duration;
+use tungstenite::Message as WsMessage;
+use vitellary_core::format_time;
+use vitellary_core::game::Event;
+use vitellary_protocols::server::PROTOCOL_VERSION;
+
+/// The parts of the feed's messages we need. The feed has more message types and fields than
+/// these, and may gain others, so everything else is ignored.
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+enum Message {
+    Hello {
+        version: u32,
+        server: String,
+    },
+    Update {
+        time: f64,
+        event: Option<Event>,
+        room: (u32, u32),
+    },
+    #[serde(other)]
+    Other,
+}
+
+fn main() -> Result<()> {
+    let url = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| "ws://127.0.0.1:5555/json".to_owned());
+    let (mut websocket, _) =
+        tungstenite::connect(&url).with_context(|| format!("failed to connect to {}", url))?;
+
+    loop {
+        let WsMessage::Text(text) = websocket.read_message()? else {
+            continue;
+        };
+        match serde_json::from_str(&text)? {
+            Message::Hello { version, server } => {
+                if version != PROTOCOL_VERSION {
+                    bail!(
+                        "vitellary {} speaks protocol version {}, not {}",
+                        server,
+                        version,
+                        PROTOCOL_VERSION
+                    );
+                }
+                eprintln!("connected to vitellary {} at {}", server, url);
+            }
+            Message::Update {
+                time,
+                event: Some(event),
+                room,
+            } => println!(
+                "{:>12} {:?} in room {},{}",
+                format_time(Duration::from_secs_f64(time)),
+                event,
+                room.0,
+                room.1
+            ),
+            Message::Update { .. } | Message::Other => {}
+        }
+    }
+}
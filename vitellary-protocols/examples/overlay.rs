@@ -0,0 +1,80 @@
+//! Generate a page to add to OBS as a browser source: the game time and the latest splits, live
+//! from vitellary's JSON feed, on a transparent background.
+//!
+//! ```sh
+//! cargo run -p vitellary-protocols --example overlay -- ws://127.0.0.1:5555/json > overlay.html
+//! ```
+//!
+//! Then add `overlay.html` as a local file browser source. The page reconnects on its own, so it
+//! can stay in the scene while vitellary restarts.
+
+use vitellary_protocols::server::PROTOCOL_VERSION;
+
+/// How many splits the overlay lists.
+const SPLITS: usize = 5;
+
+const PAGE: &str = r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<style>
+  body { margin: 0; background: transparent; color: #fff; font: 28px monospace; text-shadow: 0 0 4px #000; }
+  #time { font-size: 2.5em; }
+  #splits div { display: flex; justify-content: space-between; width: 12em; }
+</style>
+</head>
+<body>
+<div id="time">0.00</div>
+<div id="splits"></div>
+<script>
+"use strict";
+
+const URL = {url};
+const VERSION = {version};
+const SPLITS = {splits};
+
+function secs(time) {
+  const minutes = Math.floor(time / 60);
+  const seconds = (time % 60).toFixed(2).padStart(5, "0");
+  return minutes > 0 ? `${minutes}:${seconds}` : seconds;
+}
+
+function connect() {
+  const socket = new WebSocket(URL);
+  const splits = document.getElementById("splits");
+  socket.onmessage = (message) => {
+    const data = JSON.parse(message.data);
+    if (data.type === "hello" && data.version !== VERSION) {
+      console.warn(`vitellary speaks protocol version ${data.version}, not ${VERSION}`);
+    }
+    if (data.type !== "update") return;
+    document.getElementById("time").textContent = secs(data.time);
+    if (data.event === "new-game") splits.replaceChildren();
+    if (data.split) {
+      const row = document.createElement("div");
+      row.innerHTML = `<span></span><span>${secs(data.split.igt)}</span>`;
+      row.firstChild.textContent = data.event;
+      splits.append(row);
+      while (splits.children.length > SPLITS) splits.firstChild.remove();
+    }
+  };
+  socket.onclose = () => setTimeout(connect, 2000);
+}
+
+connect();
+</script>
+</body>
+</html>
+"#;
+
+fn main() {
+    let url = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| "ws://127.0.0.1:5555/json".to_owned());
+    print!(
+        "{}",
+        PAGE.replace("{url}", &serde_json::to_string(&url).unwrap())
+            .replace("{version}", &PROTOCOL_VERSION.to_string())
+            .replace("{splits}", &SPLITS.to_string())
+    );
+}
@@ -0,0 +1,76 @@
+//! A starting point for a multiworld or randomizer client: follow vitellary's randomizer feed
+//! (`vitellary --randomizer`) and report each location checked once per run.
+//!
+//! ```sh
+//! cargo run -p vitellary-protocols --example randomizer -- ws://127.0.0.1:5555/randomizer
+//! ```
+//!
+//! A real client would send each check on to its multiworld server instead of printing it.
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::HashSet;
+use tungstenite::Message;
+
+/// The parts of a check we need. Other fields (and kinds) may be added to the feed, so this
+/// ignores what it doesn't know.
+#[derive(Deserialize)]
+struct Check {
+    kind: String,
+    run_id: String,
+    time: f64,
+    room: (u32, u32),
+    trinkets: u32,
+    crewmate: Option<String>,
+}
+
+fn main() -> Result<()> {
+    let url = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| "ws://127.0.0.1:5555/randomizer".to_owned());
+    let (mut websocket, _) =
+        tungstenite::connect(&url).with_context(|| format!("failed to connect to {}", url))?;
+    eprintln!("connected to {}", url);
+
+    let mut run = String::new();
+    let mut checked = HashSet::new();
+    loop {
+        let Message::Text(text) = websocket.read_message()? else {
+            continue;
+        };
+        let value: serde_json::Value = serde_json::from_str(&text)?;
+        if value["type"] == "hello" {
+            continue;
+        }
+        let check: Check = serde_json::from_value(value)?;
+        if check.run_id != run {
+            // Checks made so far are replayed on connecting, so a client that restarts mid-run
+            // picks up where it was.
+            run.clone_from(&check.run_id);
+            checked.clear();
+        }
+        let location = match check.kind.as_str() {
+            "trinket" => format!("trinket in room {},{}", check.room.0, check.room.1),
+            "rescue" => match &check.crewmate {
+                Some(crewmate) => format!("rescue of {}", crewmate),
+                None => format!("rescue in room {},{}", check.room.0, check.room.1),
+            },
+            "complete" => "the end of the game".to_owned(),
+            "start" => {
+                println!("{:9.2} new run {}", check.time, check.run_id);
+                continue;
+            }
+            "reset" => {
+                println!("{:9.2} run reset", check.time);
+                continue;
+            }
+            _ => continue,
+        };
+        if checked.insert(location.clone()) {
+            println!(
+                "{:9.2} checked {} ({} trinkets)",
+                check.time, location, check.trinkets
+            );
+        }
+    }
+}
@@ -0,0 +1,86 @@
+//! A compact binary encoding of updates, for clients like hardware displays that want every update
+//! without parsing JSON. Clients ask for it with the `vitellary.binary.v1` WebSocket subprotocol.
+//!
+//! Each update is one 32-byte binary message, all fields little-endian:
+//!
+//! | offset | type | field                                             |
+//! |--------|------|---------------------------------------------------|
+//! | 0      | u64  | sequence number                                   |
+//! | 8      | u64  | in-game time in nanoseconds                       |
+//! | 16     | u16  | event code (see [`event_code`]), 0 for none       |
+//! | 18     | u16  | event argument: the split index for custom splits |
+//! | 20     | u32  | room x                                            |
+//! | 24     | u32  | room y                                            |
+//! | 28     | u32  | deaths                                            |
+
+use tungstenite::handshake::server::{Request, Response};
+use tungstenite::http::HeaderValue;
+use vitellary_core::game::{Command, Event, Setting, Update};
+
+pub const SUBPROTOCOL: &str = "vitellary.binary.v1";
+pub const LEN: usize = 32;
+
+/// Accept the binary subprotocol if the client asked for it, adding it to `response`.
+pub(crate) fn negotiate(request: &Request, response: &mut Response) -> bool {
+    let requested = request
+        .headers()
+        .get_all("Sec-WebSocket-Protocol")
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|protocol| protocol.trim() == SUBPROTOCOL);
+    if requested {
+        response.headers_mut().insert(
+            "Sec-WebSocket-Protocol",
+            HeaderValue::from_static(SUBPROTOCOL),
+        );
+    }
+    requested
+}
+
+/// The code for an event, and its argument.
+pub fn event_code(event: Event) -> (u16, u16) {
+    match event {
+        Event::NewGame => (1, 0),
+        Event::Verdigris => (2, 0),
+        Event::Vermilion => (3, 0),
+        Event::Victoria => (4, 0),
+        Event::Violet => (5, 0),
+        Event::Vitellary => (6, 0),
+        Event::IntermissionOne => (7, 0),
+        Event::IntermissionTwo => (8, 0),
+        Event::GameComplete => (9, 0),
+        Event::Teleport => (10, 0),
+        Event::Reset => (11, 0),
+        Event::Manual(Command::Split) => (12, 0),
+        Event::Manual(Command::SkipSplit) => (13, 0),
+        Event::Manual(Command::Undo) => (14, 0),
+        Event::Manual(Command::Reset) => (15, 0),
+        Event::Custom(index) => (16, index),
+        Event::Correction => (17, 0),
+        Event::Pause => (18, 0),
+        Event::Resume => (19, 0),
+        Event::Warning(Setting::Invincibility) => (20, 0),
+        Event::Warning(Setting::Slowdown) => (20, 1),
+        Event::TimeTrialComplete => (21, 0),
+        Event::GravitronAttempt => (22, 0),
+        Event::TimerDrift(percent) => (23, percent),
+    }
+}
+
+pub fn encode(update: &Update) -> [u8; LEN] {
+    let (code, arg) = update.event.map_or((0, 0), event_code);
+    let mut buf = [0; LEN];
+    buf[0..8].copy_from_slice(&update.seq.to_le_bytes());
+    buf[8..16].copy_from_slice(
+        &u64::try_from(update.time.as_nanos())
+            .unwrap_or(u64::MAX)
+            .to_le_bytes(),
+    );
+    buf[16..18].copy_from_slice(&code.to_le_bytes());
+    buf[18..20].copy_from_slice(&arg.to_le_bytes());
+    buf[20..24].copy_from_slice(&update.state.room.0.to_le_bytes());
+    buf[24..28].copy_from_slice(&update.state.room.1.to_le_bytes());
+    buf[28..32].copy_from_slice(&update.state.deathcounts.to_le_bytes());
+    buf
+}
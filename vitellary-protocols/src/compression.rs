@@ -0,0 +1,297 @@
+//! The permessage-deflate WebSocket extension ([RFC 7692]).
+//!
+//! tungstenite doesn't implement any extensions, so we negotiate it in the handshake callback,
+//! write compressed frames ourselves, and inflate the client's compressed frames in [`Inflate`]
+//! before tungstenite reads them (it rejects frames with RSV1 set). Every message we send is
+//! compressed on its own (`server_no_context_takeover`), so no state is kept between them; the
+//! client may keep its context, so we do too.
+//!
+//! [RFC 7692]: https://www.rfc-editor.org/rfc/rfc7692
+
+use flate2::{Compress, Compression, Decompress, FlushCompress, FlushDecompress};
+use std::io::{self, Read, Write};
+use tungstenite::handshake::server::{Request, Response};
+use tungstenite::http::HeaderValue;
+use tungstenite::protocol::frame::coding::{Data, OpCode};
+use tungstenite::protocol::frame::Frame;
+
+const RESPONSE: &str = "permessage-deflate; server_no_context_takeover";
+
+/// The most a client frame can hold, compressed or not. Clients only send short commands and
+/// answers.
+const MAX_FRAME: usize = 1 << 20;
+
+/// What the extension leaves off the end of each compressed message.
+const TAIL: [u8; 4] = [0, 0, 0xff, 0xff];
+
+/// Accept permessage-deflate if the client offered it, adding our response to `response`. Offers
+/// that limit our window size are declined, since the deflate backend always uses a full window.
+pub(crate) fn negotiate(request: &Request, response: &mut Response) -> bool {
+    let offered = request
+        .headers()
+        .get_all("Sec-WebSocket-Extensions")
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|offer| {
+            let mut params = offer.split(';').map(str::trim);
+            params.next() == Some("permessage-deflate")
+                && params.all(|param| {
+                    !param.starts_with("server_max_window_bits=")
+                        || param.ends_with("=15")
+                        || param.ends_with("=\"15\"")
+                })
+        });
+    if offered {
+        response.headers_mut().insert(
+            "Sec-WebSocket-Extensions",
+            HeaderValue::from_static(RESPONSE),
+        );
+    }
+    offered
+}
+
+/// A compressed message, as a frame with RSV1 set.
+pub(crate) fn frame(message: &[u8], opcode: Data) -> Frame {
+    let mut compress = Compress::new(Compression::default(), false);
+    let mut data = Vec::with_capacity(message.len() + 16);
+    loop {
+        let consumed = usize::try_from(compress.total_in()).unwrap_or(usize::MAX);
+        compress
+            .compress_vec(&message[consumed..], &mut data, FlushCompress::Sync)
+            .expect("deflate can't fail on in-memory data");
+        if data.len() < data.capacity() {
+            break;
+        }
+        data.reserve(data.capacity());
+    }
+    // A sync flush ends with an empty stored block, which the extension says to leave off.
+    if data.ends_with(&[0, 0, 0xff, 0xff]) {
+        data.truncate(data.len() - 4);
+    }
+    let mut frame = Frame::message(data, OpCode::Data(opcode), true);
+    frame.header_mut().rsv1 = true;
+    frame
+}
+
+/// A stream that passes what's read from it through, until [`Inflate::start`], and after that
+/// inflates compressed client frames into plain ones (masked with a zero key, which leaves them
+/// as they are).
+pub(crate) struct Inflate<S> {
+    inner: S,
+    decompress: Option<Decompress>,
+    /// Whether the message being read is compressed, for its continuation frames.
+    compressed: bool,
+    /// Read but not yet a whole frame.
+    input: Vec<u8>,
+    /// Frames ready to be read.
+    output: Vec<u8>,
+}
+
+impl<S> Inflate<S> {
+    pub(crate) fn new(inner: S) -> Inflate<S> {
+        Inflate {
+            inner,
+            decompress: None,
+            compressed: false,
+            input: Vec::new(),
+            output: Vec::new(),
+        }
+    }
+
+    /// Start inflating, once the client has accepted the extension.
+    pub(crate) fn start(&mut self) {
+        self.decompress = Some(Decompress::new(false));
+    }
+
+    pub(crate) fn get_mut(&mut self) -> &mut S {
+        &mut self.inner
+    }
+
+    /// Move every whole frame from the input to the output, inflating compressed ones.
+    fn frames(&mut self) -> io::Result<()> {
+        while let Some(Header {
+            len: header,
+            payload: len,
+            mask,
+        }) = parse_header(&self.input)?
+        {
+            if self.input.len() < header + len {
+                return Ok(());
+            }
+            let rsv1 = self.input[0] & 0x40 != 0;
+            let opcode = self.input[0] & 0x0f;
+            let fin = self.input[0] & 0x80 != 0;
+            if rsv1 && matches!(opcode, 1 | 2) {
+                self.compressed = true;
+            } else if opcode != 0 || rsv1 {
+                // Control frames, uncompressed messages, and RSV1 where it doesn't belong (which
+                // tungstenite rejects).
+                if opcode < 8 {
+                    self.compressed = false;
+                }
+                self.output.extend(self.input.drain(..header + len));
+                continue;
+            }
+            if !self.compressed {
+                self.output.extend(self.input.drain(..header + len));
+                continue;
+            }
+            let mut payload = self.input[header..header + len].to_vec();
+            if let Some(mask) = mask {
+                for (i, byte) in payload.iter_mut().enumerate() {
+                    *byte ^= mask[i % 4];
+                }
+            }
+            if fin {
+                payload.extend(TAIL);
+                self.compressed = false;
+            }
+            let data = self.inflate(&payload)?;
+            self.output.push(self.input[0] & !0x40);
+            if let Ok(len @ 0..=125) = u8::try_from(data.len()) {
+                self.output.push(0x80 | len);
+            } else if let Ok(len) = u16::try_from(data.len()) {
+                self.output.push(0x80 | 0x7e);
+                self.output.extend(len.to_be_bytes());
+            } else {
+                self.output.push(0x80 | 0x7f);
+                self.output.extend((data.len() as u64).to_be_bytes());
+            }
+            self.output.extend([0; 4]);
+            self.output.extend(data);
+            self.input.drain(..header + len);
+        }
+        Ok(())
+    }
+
+    fn inflate(&mut self, payload: &[u8]) -> io::Result<Vec<u8>> {
+        let decompress = self
+            .decompress
+            .as_mut()
+            .expect("inflating without starting");
+        let start = decompress.total_in();
+        let mut data = Vec::with_capacity(payload.len() * 4 + 16);
+        loop {
+            let consumed = usize::try_from(decompress.total_in() - start).unwrap_or(usize::MAX);
+            decompress
+                .decompress_vec(&payload[consumed..], &mut data, FlushDecompress::Sync)
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+            if data.len() < data.capacity() {
+                return Ok(data);
+            }
+            if data.len() >= MAX_FRAME {
+                return Err(too_big());
+            }
+            data.reserve(data.capacity());
+        }
+    }
+}
+
+/// The parts of a frame's header we need.
+struct Header {
+    len: usize,
+    payload: usize,
+    mask: Option<[u8; 4]>,
+}
+
+/// Parse the header `input` starts with, if it's all been read.
+fn parse_header(input: &[u8]) -> io::Result<Option<Header>> {
+    let [_, second, ..] = *input else {
+        return Ok(None);
+    };
+    let (mut header, len) = match second & 0x7f {
+        126 => (
+            4,
+            input
+                .get(2..4)
+                .map(|len| u64::from(u16::from_be_bytes([len[0], len[1]]))),
+        ),
+        0x7f => (
+            10,
+            input
+                .get(2..10)
+                .map(|len| u64::from_be_bytes(len.try_into().unwrap())),
+        ),
+        len => (2, Some(u64::from(len))),
+    };
+    let Some(len) = len else {
+        return Ok(None);
+    };
+    let len = usize::try_from(len)
+        .ok()
+        .filter(|&len| len <= MAX_FRAME)
+        .ok_or_else(too_big)?;
+    let mask = if second & 0x80 == 0 {
+        None
+    } else {
+        let Some(mask) = input.get(header..header + 4) else {
+            return Ok(None);
+        };
+        header += 4;
+        Some(mask.try_into().unwrap())
+    };
+    Ok(Some(Header {
+        len: header,
+        payload: len,
+        mask,
+    }))
+}
+
+fn too_big() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, "client frame too big")
+}
+
+impl<S: Read> Read for Inflate<S> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.decompress.is_none() {
+            return self.inner.read(buf);
+        }
+        while self.output.is_empty() {
+            let mut chunk = [0; 4096];
+            let n = self.inner.read(&mut chunk)?;
+            if n == 0 {
+                return Ok(0);
+            }
+            self.input.extend_from_slice(&chunk[..n]);
+            self.frames()?;
+        }
+        let n = buf.len().min(self.output.len());
+        buf[..n].copy_from_slice(&self.output[..n]);
+        self.output.drain(..n);
+        Ok(n)
+    }
+}
+
+impl<S: Write> Write for Inflate<S> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.inner.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn inflates_what_we_compress() {
+        let text = br#"{"success":"Forgotten Lab"}"#;
+        let mut raw = Vec::new();
+        for _ in 0..2 {
+            frame(text, Data::Text).format(&mut raw).unwrap();
+        }
+        let mut inflate = Inflate::new(Cursor::new(raw));
+        inflate.start();
+        let mut plain = Vec::new();
+        inflate.read_to_end(&mut plain).unwrap();
+
+        let mut expected = vec![0x81, 0x80 | u8::try_from(text.len()).unwrap(), 0, 0, 0, 0];
+        expected.extend(text);
+        assert_eq!(plain, [expected.clone(), expected].concat());
+    }
+}
@@ -0,0 +1,98 @@
+use crate::verification::{self, RecordedRun};
+use anyhow::Result;
+use crossbeam_channel::Receiver;
+use serde::{Serialize, Serializer};
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::Duration;
+use vitellary_core::format_time;
+use vitellary_core::game::{Event, Message, RoomNames, Run};
+
+/// A recorded run to compare against: each room it entered and the game time it entered it at.
+#[derive(Debug, Clone)]
+pub struct Ghost(RecordedRun);
+
+impl Ghost {
+    /// Load the fastest completed run in the verification log at `path`.
+    pub fn load(path: &Path) -> Result<Ghost> {
+        verification::fastest_run(path).map(Ghost)
+    }
+
+    /// When the ghost entered `room` for the `nth` time (counting from 0).
+    fn entered(&self, room: (u32, u32), nth: usize) -> Option<Duration> {
+        self.0
+            .rooms
+            .iter()
+            .filter(|(r, _)| *r == room)
+            .nth(nth)
+            .map(|(_, time)| *time)
+    }
+}
+
+/// One line of the ghost feed, sent whenever the runner enters a room.
+#[derive(Debug, Clone, Serialize)]
+pub struct Delta {
+    pub room: (u32, u32),
+    pub name: Option<String>,
+    #[serde(serialize_with = "serialize_secs")]
+    pub time: Duration,
+    /// The runner's time entering the room minus the ghost's, if the ghost entered it as many
+    /// times. Negative is ahead.
+    pub delta: Option<f64>,
+}
+
+fn serialize_secs<S: Serializer>(time: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_f64(time.as_secs_f64())
+}
+
+/// Compare each room transition in our runs against `ghost`, logging the delta. Returns the
+/// ghost feed.
+///
+/// Rooms are matched by how many times they've been entered, so a route that revisits a room
+/// is compared against the ghost's matching visit, and rooms the ghost never entered (or
+/// entered fewer times) have no delta.
+pub fn spawn(ghost: Ghost, rooms: RoomNames, receiver: Receiver<Message>) -> Receiver<Delta> {
+    log::info!(
+        "racing the ghost of run {} ({})",
+        ghost.0.run_id,
+        format_time(ghost.0.time)
+    );
+    let (sender, deltas) = crossbeam_channel::bounded(10);
+    std::thread::spawn(move || {
+        let mut room = None;
+        let mut visits: HashMap<(u32, u32), usize> = HashMap::new();
+        for message in receiver {
+            let Message::Update(update) = message else {
+                continue;
+            };
+            if update.event == Some(Event::NewGame) {
+                room = None;
+                visits.clear();
+            }
+            if update.run != Run::Running || room == Some(update.state.room) {
+                continue;
+            }
+            room = Some(update.state.room);
+            let nth = visits.entry(update.state.room).or_default();
+            let delta = ghost
+                .entered(update.state.room, *nth)
+                .map(|ghost| update.time.as_secs_f64() - ghost.as_secs_f64());
+            *nth += 1;
+            log::info!(
+                "ghost: entered {} at {}{}",
+                rooms.describe(update.state.room),
+                format_time(update.time),
+                delta.map_or_else(String::new, |delta| format!(", delta {:+.2}s", delta))
+            );
+            sender
+                .try_send(Delta {
+                    room: update.state.room,
+                    name: rooms.get(update.state.room).map(ToOwned::to_owned),
+                    time: update.time,
+                    delta,
+                })
+                .ok();
+        }
+    });
+    deltas
+}
@@ -0,0 +1,176 @@
+use anyhow::{bail, Context, Result};
+use crossbeam_channel::Receiver;
+use serde::{Deserialize, Serialize, Serializer};
+use std::collections::HashSet;
+use std::path::Path;
+use std::time::Duration;
+use uuid::Uuid;
+use vitellary_core::format_time;
+use vitellary_core::game::{Event, Message, Run, Update};
+
+/// Goals to complete during a run, like the squares of a bingo board, read from a file:
+///
+/// ```toml
+/// [[goals]]
+/// name = "Collect 5 trinkets"
+/// trinkets = 5
+///
+/// [[goals]]
+/// name = "Visit Comms Relay"
+/// room = [114, 103]
+///
+/// [[goals]]
+/// name = "Rescue Vitellary before Vermilion"
+/// event = "vitellary"
+/// before = "vermilion"
+/// ```
+///
+/// A goal is complete once everything it gives holds: `trinkets` collected this run, `room`
+/// visited, a trinket collected in `trinket-room`, and `event` (any split event) made, if given
+/// `before` the event `before`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Goals {
+    goals: Vec<Goal>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+struct Goal {
+    name: String,
+    trinkets: Option<u32>,
+    room: Option<(u32, u32)>,
+    trinket_room: Option<(u32, u32)>,
+    event: Option<Event>,
+    before: Option<Event>,
+}
+
+/// A goal completed, sent on the goal feed.
+#[derive(Debug, Clone, Serialize)]
+pub struct Completion {
+    /// The goal's position in the file, from 0.
+    pub index: usize,
+    pub name: String,
+    pub run_id: Uuid,
+    #[serde(serialize_with = "serialize_secs")]
+    pub time: Duration,
+}
+
+#[allow(clippy::trivially_copy_pass_by_ref)] // required by serde
+fn serialize_secs<S: Serializer>(time: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_f64(time.as_secs_f64())
+}
+
+impl Goals {
+    /// Load the goals in the file at `path`.
+    pub fn load(path: &Path) -> Result<Goals> {
+        let goals: Goals = toml::from_str(
+            &std::fs::read_to_string(path)
+                .with_context(|| format!("failed to read {}", path.display()))?,
+        )
+        .with_context(|| format!("failed to parse {}", path.display()))?;
+        for goal in &goals.goals {
+            if goal.trinkets.is_none()
+                && goal.room.is_none()
+                && goal.trinket_room.is_none()
+                && goal.event.is_none()
+            {
+                bail!("{}: goal {:?} has nothing to do", path.display(), goal.name);
+            }
+            if goal.before.is_some() && goal.event.is_none() {
+                bail!(
+                    "{}: goal {:?} gives `before` without `event`",
+                    path.display(),
+                    goal.name
+                );
+            }
+        }
+        Ok(goals)
+    }
+}
+
+/// What's happened so far in a run, as far as goals care.
+#[derive(Debug, Default)]
+struct Progress {
+    rooms: HashSet<(u32, u32)>,
+    trinket_rooms: HashSet<(u32, u32)>,
+    events: HashSet<Event>,
+    trinkets: u32,
+    /// The goals completed, or that can no longer be.
+    settled: HashSet<usize>,
+}
+
+impl Progress {
+    fn follow(&mut self, update: &Update) {
+        self.rooms.insert(update.state.room);
+        if update.trinkets > self.trinkets {
+            self.trinket_rooms.insert(update.state.room);
+        }
+        self.trinkets = update.trinkets;
+        if let Some(event) = update.event {
+            self.events.insert(event);
+        }
+    }
+
+    /// Whether `goal` is complete, or `None` if it can't be any more.
+    fn complete(&self, goal: &Goal) -> Option<bool> {
+        if let (Some(event), Some(before)) = (goal.event, goal.before) {
+            if self.events.contains(&before) && !self.events.contains(&event) {
+                return None;
+            }
+        }
+        Some(
+            goal.trinkets
+                .is_none_or(|trinkets| self.trinkets >= trinkets)
+                && goal.room.is_none_or(|room| self.rooms.contains(&room))
+                && goal
+                    .trinket_room
+                    .is_none_or(|room| self.trinket_rooms.contains(&room))
+                && goal.event.is_none_or(|event| self.events.contains(&event)),
+        )
+    }
+}
+
+/// Follow each run's progress through `goals`, logging each goal completed. Returns the goal
+/// feed.
+pub fn spawn(goals: Goals, receiver: Receiver<Message>) -> Receiver<Completion> {
+    log::info!("tracking {} goals", goals.goals.len());
+    let (sender, completions) = crossbeam_channel::bounded(10);
+    std::thread::spawn(move || {
+        let mut progress = Progress::default();
+        for message in receiver {
+            let Message::Update(update) = message else {
+                continue;
+            };
+            if update.event == Some(Event::NewGame) {
+                progress = Progress::default();
+            }
+            if update.run != Run::Running {
+                continue;
+            }
+            progress.follow(&update);
+            for (index, goal) in goals.goals.iter().enumerate() {
+                if progress.settled.contains(&index) {
+                    continue;
+                }
+                match progress.complete(goal) {
+                    Some(false) => continue,
+                    Some(true) => {
+                        log::info!("goal: {} at {}", goal.name, format_time(update.time));
+                        let completion = Completion {
+                            index,
+                            name: goal.name.clone(),
+                            run_id: update.run_id,
+                            time: update.time,
+                        };
+                        if sender.send(completion).is_err() {
+                            return;
+                        }
+                    }
+                    None => log::info!("goal: {} can no longer be completed", goal.name),
+                }
+                progress.settled.insert(index);
+            }
+        }
+    });
+    completions
+}
@@ -0,0 +1,321 @@
+use anyhow::{Context, Result};
+use crossbeam_channel::Receiver;
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::thread::JoinHandle;
+use std::time::{Duration, SystemTime};
+use uuid::Uuid;
+use vitellary_core::format_time;
+use vitellary_core::game::{Category, Command, Event, Message, ResetCause, Settings, Update};
+
+/// One line of the history file.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+pub(crate) enum Line {
+    Practice(Attempt),
+    Gravitron(GravitronAttempt),
+    Reset(RunReset),
+}
+
+/// A practice attempt at a segment.
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct Attempt {
+    pub(crate) segment: Event,
+    /// When the attempt started, in seconds since the Unix epoch.
+    pub(crate) started: u64,
+    /// The segment time in seconds, or `None` if the attempt was abandoned.
+    pub(crate) time: Option<f64>,
+    /// The game's settings when the attempt started.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    settings: Option<Settings>,
+    /// The category those settings make the attempt, if they could be read.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    category: Option<Category>,
+}
+
+/// A Super Gravitron attempt.
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct GravitronAttempt {
+    /// When the attempt ended, in seconds since the Unix epoch.
+    pub(crate) ended: u64,
+    /// How long the player survived, in seconds.
+    pub(crate) gravitron: f64,
+    /// Whether it beat the game's best time, if that could be read.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    personal_best: Option<bool>,
+}
+
+/// A full run that reset.
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct RunReset {
+    run_id: Uuid,
+    /// When the run reset, in seconds since the Unix epoch.
+    ended: u64,
+    pub(crate) reset: ResetCause,
+    /// The last split before the reset, or `None` if it reset in the first segment.
+    pub(crate) after: Option<Event>,
+    /// The game time of the reset, in seconds.
+    time: f64,
+    deaths: u32,
+}
+
+/// The full run in progress, to record where it dies.
+#[derive(Debug)]
+struct FullRun {
+    run_id: Uuid,
+    after: Option<Event>,
+    time: Duration,
+    deaths: u32,
+    /// When the last update came, in seconds since the Unix epoch.
+    seen: u64,
+    /// Whether the game went away during the run, which was recorded then. It may be resumed if
+    /// the game restarts.
+    crashed: bool,
+}
+
+impl FullRun {
+    /// Follow `update`, returning the run's reset if it reset.
+    fn update(run: &mut Option<FullRun>, update: &Update) -> Option<RunReset> {
+        match update.event {
+            Some(Event::NewGame) => {
+                *run = Some(FullRun {
+                    run_id: update.run_id,
+                    after: None,
+                    time: update.time,
+                    deaths: update.state.deathcounts,
+                    seen: update.clock.utc.as_secs(),
+                    crashed: false,
+                });
+                return None;
+            }
+            Some(Event::GameComplete) => {
+                *run = None;
+                return None;
+            }
+            _ => {}
+        }
+        let current = run.as_mut()?;
+        current.time = update.time;
+        current.deaths = update.state.deathcounts;
+        current.seen = update.clock.utc.as_secs();
+        match update.event {
+            Some(Event::Reset) => run
+                .take()
+                .filter(|run| !run.crashed)
+                .map(|run| run.reset(update.reset_cause.unwrap_or(ResetCause::QuitToMenu))),
+            Some(Event::Resume) => {
+                current.crashed = false;
+                None
+            }
+            Some(Event::Manual(Command::Reset)) => run
+                .take()
+                .filter(|run| !run.crashed)
+                .map(|run| run.reset(ResetCause::Manual)),
+            Some(Event::Manual(_) | Event::Correction) | None => None,
+            Some(event) => {
+                if crate::server::command(event, false) == Some("split") {
+                    current.after = Some(event);
+                }
+                None
+            }
+        }
+    }
+
+    fn reset(&self, reset: ResetCause) -> RunReset {
+        log::info!(
+            "run reset ({:?}) {} at {}",
+            reset,
+            self.after.map_or_else(
+                || "in the first segment".to_owned(),
+                |event| format!("after {:?}", event)
+            ),
+            format_time(self.time)
+        );
+        RunReset {
+            run_id: self.run_id,
+            ended: self.seen,
+            reset,
+            after: self.after,
+            time: self.time.as_secs_f64(),
+            deaths: self.deaths,
+        }
+    }
+}
+
+/// Super Gravitron attempt counts and the longest survival.
+#[derive(Debug, Default)]
+struct GravitronStats {
+    attempts: u32,
+    best: Option<Duration>,
+}
+
+impl GravitronStats {
+    fn record(&mut self, attempt: &GravitronAttempt) {
+        self.attempts += 1;
+        if let Ok(survived) = Duration::try_from_secs_f64(attempt.gravitron) {
+            self.best = self.best.max(Some(survived));
+        }
+    }
+
+    fn summary(&self) -> String {
+        format!(
+            "{} attempts, best {}",
+            self.attempts,
+            self.best.map_or_else(|| "none".to_owned(), format_time)
+        )
+    }
+}
+
+/// Attempt counts and times for one segment.
+#[derive(Debug, Default)]
+struct Stats {
+    attempts: u32,
+    completed: Vec<Duration>,
+}
+
+impl Stats {
+    fn record(&mut self, attempt: &Attempt) {
+        self.attempts += 1;
+        if let Some(time) = attempt
+            .time
+            .and_then(|t| Duration::try_from_secs_f64(t).ok())
+        {
+            self.completed.push(time);
+        }
+    }
+
+    fn summary(&self) -> String {
+        let Some(best) = self.completed.iter().min() else {
+            return format!("{} attempts, none completed", self.attempts);
+        };
+        let total: Duration = self.completed.iter().sum();
+        #[allow(clippy::cast_possible_truncation)] // nobody completes 4 billion attempts
+        let average = total / self.completed.len() as u32;
+        format!(
+            "{}/{} attempts completed, best {}, average {}",
+            self.completed.len(),
+            self.attempts,
+            format_time(*best),
+            format_time(average)
+        )
+    }
+}
+
+/// Record practice attempts at `segment` (if practicing, or else where full runs reset and why)
+/// and Super Gravitron attempts to the history file at `path` (a file of JSON lines), logging
+/// the stats after each one.
+pub fn spawn(
+    path: &Path,
+    segment: Option<Event>,
+    receiver: Receiver<Message>,
+) -> Result<JoinHandle<()>> {
+    let mut stats = Stats::default();
+    let mut gravitron = GravitronStats::default();
+    if path.exists() {
+        for line in read(path)? {
+            match line {
+                Line::Practice(attempt) if Some(attempt.segment) == segment => {
+                    stats.record(&attempt);
+                }
+                Line::Gravitron(attempt) => gravitron.record(&attempt),
+                Line::Practice(_) | Line::Reset(_) => {}
+            }
+        }
+    }
+    if let Some(segment) = segment {
+        log::info!("practicing {:?}: {}", segment, stats.summary());
+    }
+    if gravitron.attempts > 0 {
+        log::info!("super gravitron: {}", gravitron.summary());
+    }
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("failed to open {}", path.display()))?;
+
+    Ok(std::thread::spawn(move || {
+        let mut started: Option<(u64, Settings)> = None;
+        let mut run = None;
+        for message in receiver {
+            let update = match message {
+                Message::Update(update) => update,
+                Message::Gravitron(attempt) => {
+                    let attempt = GravitronAttempt {
+                        ended: now(),
+                        gravitron: attempt.survived.as_secs_f64(),
+                        personal_best: attempt.personal_best,
+                    };
+                    gravitron.record(&attempt);
+                    log::info!("super gravitron: {}", gravitron.summary());
+                    append(&mut file, &Line::Gravitron(attempt));
+                    continue;
+                }
+                Message::Detached { .. } => {
+                    if let Some(run) = run.as_mut().filter(|run: &&mut FullRun| !run.crashed) {
+                        run.crashed = true;
+                        append(&mut file, &Line::Reset(run.reset(ResetCause::Crash)));
+                    }
+                    continue;
+                }
+                _ => continue,
+            };
+            let Some(segment) = segment else {
+                if let Some(reset) = FullRun::update(&mut run, &update) {
+                    append(&mut file, &Line::Reset(reset));
+                }
+                continue;
+            };
+            let time = match update.event {
+                Some(Event::NewGame | Event::Reset) => None,
+                Some(event) if event == segment => Some(update.time),
+                _ => continue,
+            };
+            if let Some((started, settings)) = started.take() {
+                let attempt = Attempt {
+                    segment,
+                    started,
+                    time: time.map(|t| t.as_secs_f64()),
+                    category: settings.category(),
+                    settings: Some(settings),
+                };
+                stats.record(&attempt);
+                log::info!("practicing {:?}: {}", segment, stats.summary());
+                append(&mut file, &Line::Practice(attempt));
+            }
+            if update.event == Some(Event::NewGame) {
+                started = Some((update.clock.utc.as_secs(), update.settings));
+            }
+        }
+    }))
+}
+
+/// Read the history file at `path`.
+pub(crate) fn read(path: &Path) -> Result<Vec<Line>> {
+    let file = File::open(path).with_context(|| format!("failed to open {}", path.display()))?;
+    BufReader::new(file)
+        .lines()
+        .map(|line| {
+            serde_json::from_str(&line?)
+                .with_context(|| format!("failed to parse {}", path.display()))
+        })
+        .collect()
+}
+
+/// Seconds since the Unix epoch.
+pub(crate) fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+fn append(file: &mut File, line: &Line) {
+    let result = serde_json::to_string(line)
+        .map_err(std::io::Error::from)
+        .and_then(|line| writeln!(file, "{}", line));
+    if let Err(err) = result {
+        log::warn!("failed to write history: {}", err);
+    }
+}
@@ -0,0 +1,117 @@
+use crate::state::SharedState;
+use crate::stats;
+use anyhow::Result;
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+const DASHBOARD: &str = include_str!("dashboard.html");
+
+/// The largest control request the dashboard accepts.
+const MAX_BODY: usize = 64 * 1024;
+
+/// What the dashboard needs beyond the run state.
+pub struct Dashboard {
+    /// The history file, for segment charts.
+    pub history: Option<PathBuf>,
+    /// Carries out a JSON-RPC call from the dashboard's controls (with the same methods as the
+    /// control API), returning the response.
+    pub control: Box<dyn Fn(&str) -> Value + Send + Sync>,
+}
+
+/// Serve the run state as JSON over HTTP on `listener`, at `/` (or `/status`), for status pages
+/// and scripts that would rather poll than hold a WebSocket open.
+///
+/// With a `dashboard`, `/` is instead a web page showing the run state, charts of each segment's
+/// personal bests from the history file (served at `/history`), and buttons for the control API
+/// (whose calls are posted to `/rpc`), for running vitellary without a terminal to look at.
+pub fn spawn(listener: TcpListener, state: SharedState, dashboard: Option<Dashboard>) {
+    let dashboard = dashboard.map(Arc::new);
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let state = state.clone();
+            let dashboard = dashboard.clone();
+            std::thread::spawn(move || -> Result<()> {
+                serve(stream?, &state, dashboard.as_deref())
+            });
+        }
+    });
+}
+
+fn serve(stream: TcpStream, state: &SharedState, dashboard: Option<&Dashboard>) -> Result<()> {
+    let mut writer = stream.try_clone()?;
+    let mut reader = BufReader::new(stream);
+    let mut request = String::new();
+    reader.read_line(&mut request)?;
+    // Only the body's length and type matter for anything we serve.
+    let mut length = 0;
+    let mut json_body = false;
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                length = value.trim().parse().unwrap_or(0);
+            } else if name.eq_ignore_ascii_case("content-type") {
+                json_body = value.trim().starts_with("application/json");
+            }
+        }
+        header.clear();
+    }
+    let mut parts = request.split_whitespace();
+    let (status, content_type, body) = match (parts.next(), parts.next(), dashboard) {
+        (Some("GET"), Some("/"), Some(_)) => ("200 OK", "text/html", DASHBOARD.to_owned()),
+        (Some("GET"), Some("/" | "/status"), _) => (
+            "200 OK",
+            "application/json",
+            state.lock().unwrap().status().to_string(),
+        ),
+        (Some("GET"), Some("/history"), Some(dashboard)) => match history(dashboard) {
+            Ok(report) => ("200 OK", "application/json", report.to_string()),
+            Err(err) => error("500 Internal Server Error", &format!("{:#}", err)),
+        },
+        (Some("POST"), Some("/rpc"), Some(dashboard)) => {
+            // Browsers only let other sites post JSON after asking first, which we don't answer,
+            // so requiring it keeps web pages from using the controls.
+            if !json_body {
+                error("415 Unsupported Media Type", "expected application/json")
+            } else if length > MAX_BODY {
+                error("413 Payload Too Large", "request too large")
+            } else {
+                let mut body = vec![0; length];
+                reader.read_exact(&mut body)?;
+                let response = (dashboard.control)(&String::from_utf8_lossy(&body));
+                ("200 OK", "application/json", response.to_string())
+            }
+        }
+        (Some("GET"), Some(_), _) => error("404 Not Found", "not found"),
+        _ => error("405 Method Not Allowed", "method not allowed"),
+    };
+    write!(
+        writer,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\n\
+         Access-Control-Allow-Origin: *\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    )?;
+    Ok(())
+}
+
+fn error(status: &'static str, message: &str) -> (&'static str, &'static str, String) {
+    (
+        status,
+        "application/json",
+        json!({ "error": message }).to_string(),
+    )
+}
+
+/// The history file's stats report, or null if there's no history file.
+fn history(dashboard: &Dashboard) -> Result<Value> {
+    Ok(match &dashboard.history {
+        Some(path) if path.exists() => serde_json::to_value(stats::report(path)?)?,
+        _ => Value::Null,
+    })
+}
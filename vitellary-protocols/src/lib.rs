@@ -0,0 +1,34 @@
+//! Sinks that send what the splitter sees to timers, files, and services.
+
+#![warn(clippy::pedantic)]
+#![allow(
+    clippy::doc_markdown,
+    clippy::missing_errors_doc,
+    clippy::missing_panics_doc,
+    clippy::module_name_repetitions,
+    clippy::must_use_candidate,
+    clippy::uninlined_format_args
+)]
+
+pub mod binary;
+mod compression;
+pub mod ghost;
+pub mod goals;
+pub mod history;
+pub mod http;
+pub mod livesplit;
+pub mod markers;
+pub mod queue;
+pub mod race;
+pub mod racetime;
+pub mod randomizer;
+pub mod route;
+pub mod server;
+pub mod splits;
+pub mod state;
+pub mod stats;
+pub mod stdout;
+mod stream;
+pub mod trace;
+pub mod twitch;
+pub mod verification;
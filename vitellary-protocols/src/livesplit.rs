@@ -0,0 +1,91 @@
+use crate::server;
+use crate::state::SharedState;
+use anyhow::Result;
+use crossbeam_channel::Receiver;
+use std::io::Write;
+use std::net::TcpStream;
+use std::time::Duration;
+use vitellary_core::game::{Message, Update};
+
+/// How long to wait before reconnecting to a LiveSplit that went away.
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// Drive the LiveSplit Server component listening at `address` (usually port 16834): send it
+/// the game time with every update and a command for every event. Whenever it (re)connects, it's
+/// brought up to date from `state` first.
+pub fn spawn(
+    address: String,
+    state: SharedState,
+    split_on_teleport: bool,
+    receiver: Receiver<Message>,
+) {
+    std::thread::spawn(move || {
+        let mut unsent = None;
+        loop {
+            if let Err(err) = follow(&address, &state, split_on_teleport, &receiver, &mut unsent) {
+                log::warn!("LiveSplit server {}: {:#}", address, err);
+            }
+            std::thread::sleep(RECONNECT_DELAY);
+        }
+    });
+}
+
+/// Follow the run until the connection fails. An update taken from `receiver` that couldn't be
+/// sent is left in `unsent`, to send after the next replay if the replay doesn't cover it.
+fn follow(
+    address: &str,
+    state: &SharedState,
+    split_on_teleport: bool,
+    receiver: &Receiver<Message>,
+    unsent: &mut Option<Update>,
+) -> Result<()> {
+    let mut stream = TcpStream::connect(address)?;
+    log::info!("connected to LiveSplit server {}", address);
+    // Updates up to `caught_up` are covered by the replay, even if they're still queued. Sequence
+    // numbers start again when we reattach, so that ends it.
+    let (commands, mut caught_up) = {
+        let state = state.lock().unwrap();
+        (
+            server::replay(&state.splits),
+            state.update.as_ref().map(|update| update.seq),
+        )
+    };
+    for command in commands {
+        write!(stream, "{}\r\n", translate(&command))?;
+    }
+    let messages = unsent.take().map(Message::Update).into_iter();
+    for message in messages.chain(receiver) {
+        let update = match message {
+            Message::Update(update) => update,
+            Message::Attached { .. } => {
+                caught_up = None;
+                continue;
+            }
+            _ => continue,
+        };
+        if caught_up.is_some_and(|seq| update.seq <= seq) {
+            continue;
+        }
+        // Send the update's commands together, so a failure can't leave half of them sent
+        // without the rest being sent again after reconnecting.
+        let mut commands = String::new();
+        for command in server::commands(&update, update.time, split_on_teleport) {
+            commands.push_str(&translate(&command));
+            commands.push_str("\r\n");
+        }
+        if let Err(err) = stream.write_all(commands.as_bytes()) {
+            *unsent = Some(update);
+            return Err(err.into());
+        }
+    }
+    Ok(())
+}
+
+/// The LiveSplit Server component's name for a LiveSplit One server command. Game time is kept
+/// paused so it only moves when we set it.
+fn translate(command: &str) -> String {
+    match command {
+        "start" => "starttimer\r\ninitgametime\r\npausegametime".to_owned(),
+        command => command.to_owned(),
+    }
+}
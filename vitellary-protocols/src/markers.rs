@@ -0,0 +1,74 @@
+use crate::server;
+use anyhow::{Context, Result};
+use crossbeam_channel::Receiver;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+use std::thread::JoinHandle;
+use std::time::Duration;
+use vitellary_core::game::Message;
+
+const HEADER: &str = "timestamp,timecode,run_id,event,igt";
+
+/// Append a marker to the CSV file at `path` for every event a timer acts on (starts, splits,
+/// resets and so on), so video editors can line recordings up with the run.
+///
+/// `timestamp` is when the game was read, in seconds since the Unix epoch, and `timecode` the
+/// same moment as an LTC-style UTC time of day (`HH:MM:SS:FF`) at `fps` frames per second, to match
+/// against a recording's timecode track; `igt` is the game time in seconds.
+pub fn spawn(
+    path: &Path,
+    fps: u32,
+    split_on_teleport: bool,
+    receiver: Receiver<Message>,
+) -> Result<JoinHandle<()>> {
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("failed to open {}", path.display()))?;
+    if file.metadata()?.len() == 0 {
+        writeln!(file, "{}", HEADER)?;
+    }
+
+    Ok(std::thread::spawn(move || {
+        for message in receiver {
+            let Message::Update(update) = message else {
+                continue;
+            };
+            let Some(event) = update
+                .event
+                .filter(|event| server::command(*event, split_on_teleport).is_some())
+            else {
+                continue;
+            };
+            let now = update.clock.utc;
+            let result = writeln!(
+                file,
+                "{:.3},{},{},{:?},{:.3}",
+                now.as_secs_f64(),
+                timecode(now, fps),
+                update.run_id,
+                event,
+                update.time.as_secs_f64()
+            )
+            .and_then(|()| file.flush());
+            if let Err(err) = result {
+                log::warn!("failed to write marker: {}", err);
+            }
+        }
+    }))
+}
+
+/// The UTC time of day `since_epoch` falls on, as `HH:MM:SS:FF` at `fps` frames per second.
+fn timecode(since_epoch: Duration, fps: u32) -> String {
+    let secs = since_epoch.as_secs() % (24 * 60 * 60);
+    let frame = u64::from(since_epoch.subsec_nanos()) * u64::from(fps) / 1_000_000_000;
+    format!(
+        "{:02}:{:02}:{:02}:{:02}",
+        secs / 3600,
+        secs / 60 % 60,
+        secs % 60,
+        frame
+    )
+}
@@ -0,0 +1,86 @@
+use crossbeam_channel::TrySendError;
+use std::collections::VecDeque;
+
+/// The most items that wait for room in a channel before even those are dropped.
+const MAX_BACKLOG: usize = 1000;
+
+/// The receiver hung up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Disconnected;
+
+/// A sender to a bounded channel that never blocks: when the channel is full, items that can be
+/// missed are dropped (and counted), and the rest wait in line until there's room, ahead of
+/// anything sent after them.
+#[derive(Debug)]
+pub struct Sender<T> {
+    channel: crossbeam_channel::Sender<T>,
+    backlog: VecDeque<T>,
+    dropped: u64,
+}
+
+impl<T> Sender<T> {
+    pub fn new(sender: crossbeam_channel::Sender<T>) -> Sender<T> {
+        Sender {
+            channel: sender,
+            backlog: VecDeque::new(),
+            dropped: 0,
+        }
+    }
+
+    /// Send `item`, dropping it if there's no room and it's `droppable`.
+    pub fn send(&mut self, item: T, droppable: bool) -> Result<(), Disconnected> {
+        self.flush()?;
+        let item = if self.backlog.is_empty() {
+            match self.channel.try_send(item) {
+                Ok(()) => return Ok(()),
+                Err(TrySendError::Full(item)) => item,
+                Err(TrySendError::Disconnected(_)) => return Err(Disconnected),
+            }
+        } else {
+            item
+        };
+        if droppable {
+            self.dropped += 1;
+        } else if self.backlog.len() < MAX_BACKLOG {
+            self.backlog.push_back(item);
+        } else {
+            log::warn!("an output is {} messages behind; dropping one", MAX_BACKLOG);
+            self.dropped += 1;
+        }
+        Ok(())
+    }
+
+    /// Send as much of what's waiting as there's room for. Senders that may go quiet should do
+    /// this now and then, so nothing waits on the next item.
+    pub fn flush(&mut self) -> Result<(), Disconnected> {
+        while let Some(waiting) = self.backlog.pop_front() {
+            match self.channel.try_send(waiting) {
+                Ok(()) => {}
+                Err(TrySendError::Full(waiting)) => {
+                    self.backlog.push_front(waiting);
+                    break;
+                }
+                Err(TrySendError::Disconnected(_)) => return Err(Disconnected),
+            }
+        }
+        Ok(())
+    }
+
+    /// Send `item` and everything waiting, waiting for room as long as it takes.
+    pub fn send_blocking(&mut self, item: T) -> Result<(), Disconnected> {
+        for waiting in self.backlog.drain(..).chain([item]) {
+            self.channel.send(waiting).map_err(|_| Disconnected)?;
+        }
+        Ok(())
+    }
+
+    /// How many items have been dropped.
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+
+    /// Whether the channel is full.
+    pub fn is_full(&self) -> bool {
+        self.channel.is_full()
+    }
+}
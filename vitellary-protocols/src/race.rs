@@ -0,0 +1,156 @@
+use crate::splits::Splits;
+use anyhow::Result;
+use crossbeam_channel::{Receiver, Sender};
+use serde::{Deserialize, Serialize, Serializer};
+use std::time::Duration;
+use tungstenite::Message as WsMessage;
+use uuid::Uuid;
+use vitellary_core::game::{Event, Message};
+
+/// How long to wait before reconnecting to a peer that went away.
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Side {
+    Local,
+    Peer,
+}
+
+/// One line of the race feed, sent whenever either runner splits.
+#[derive(Debug, Clone, Serialize)]
+pub struct Comparison {
+    pub runner: Side,
+    /// Which split this is in the run, counting from 0.
+    pub index: usize,
+    pub split: Event,
+    #[serde(serialize_with = "serialize_secs")]
+    pub time: Duration,
+    /// The local runner's time at this split minus the peer's, once both have reached it.
+    pub delta: Option<f64>,
+    pub ahead: Option<Side>,
+}
+
+fn serialize_secs<S: Serializer>(time: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_f64(time.as_secs_f64())
+}
+
+/// The parts of a peer's `/json` feed we need.
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+enum PeerMessage {
+    Update {
+        seq: u64,
+        run_id: Uuid,
+        time: f64,
+        event: Option<Event>,
+    },
+    #[serde(other)]
+    Other,
+}
+
+/// Compare splits live with the vitellary instance whose JSON feed is at `peer`, reading our own
+/// updates from `local`. Returns the comparison feed.
+pub fn spawn(peer: String, local: Receiver<Message>) -> Receiver<Comparison> {
+    let (peer_sender, peer_receiver) = crossbeam_channel::unbounded();
+    std::thread::spawn(move || loop {
+        if let Err(err) = follow(&peer, &peer_sender) {
+            log::warn!("race peer {}: {:#}", peer, err);
+        }
+        std::thread::sleep(RECONNECT_DELAY);
+    });
+
+    let (sender, receiver) = crossbeam_channel::bounded(10);
+    std::thread::spawn(move || {
+        let mut local_splits = Splits::default();
+        let mut peer_splits = Splits::default();
+        loop {
+            let (side, event, time) = crossbeam_channel::select! {
+                recv(local) -> message => match message {
+                    Ok(Message::Update(update)) => match update.event {
+                        Some(event) => (Side::Local, event, update.time),
+                        None => continue,
+                    },
+                    Ok(_) => continue,
+                    Err(_) => return,
+                },
+                recv(peer_receiver) -> message => match message {
+                    Ok((event, time)) => (Side::Peer, event, time),
+                    Err(_) => return,
+                },
+            };
+            let (splits, other) = match side {
+                Side::Local => (&mut local_splits, &peer_splits),
+                Side::Peer => (&mut peer_splits, &local_splits),
+            };
+            let Some(index) = splits.apply(event, time, false) else {
+                continue;
+            };
+            let delta = other.times().get(index).copied().flatten().map(|other| {
+                let (local, peer) = match side {
+                    Side::Local => (time, other),
+                    Side::Peer => (other, time),
+                };
+                local.as_secs_f64() - peer.as_secs_f64()
+            });
+            let comparison = Comparison {
+                runner: side,
+                index,
+                split: event,
+                time,
+                delta,
+                ahead: delta.and_then(|delta| {
+                    if delta < 0.0 {
+                        Some(Side::Local)
+                    } else if delta > 0.0 {
+                        Some(Side::Peer)
+                    } else {
+                        None
+                    }
+                }),
+            };
+            log::info!(
+                "race: {:?} split {} ({:?}){}",
+                side,
+                index,
+                event,
+                delta.map_or_else(String::new, |delta| format!(", delta {:+.2}s", delta))
+            );
+            sender.try_send(comparison).ok();
+        }
+    });
+    receiver
+}
+
+/// Read events from a peer's JSON feed until the connection drops.
+fn follow(peer: &str, sender: &Sender<(Event, Duration)>) -> Result<()> {
+    let (mut websocket, _) = tungstenite::connect(peer)?;
+    log::info!("connected to race peer {}", peer);
+    let mut last: Option<(Uuid, u64)> = None;
+    loop {
+        let WsMessage::Text(text) = websocket.read_message()? else {
+            continue;
+        };
+        let PeerMessage::Update {
+            seq,
+            run_id,
+            time,
+            event,
+        } = serde_json::from_str(&text)?
+        else {
+            continue;
+        };
+        if let Some((last_run, last_seq)) = last {
+            if last_run == run_id && seq > last_seq + 1 {
+                log::warn!(
+                    "missed {} updates from race peer; splits may be out of step",
+                    seq - last_seq - 1
+                );
+            }
+        }
+        last = Some((run_id, seq));
+        if let (Some(event), Ok(time)) = (event, Duration::try_from_secs_f64(time)) {
+            sender.send((event, time))?;
+        }
+    }
+}
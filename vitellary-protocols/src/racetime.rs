@@ -0,0 +1,100 @@
+use anyhow::Result;
+use crossbeam_channel::Receiver;
+use std::path::Path;
+use std::thread::JoinHandle;
+use vitellary_core::game::Message;
+
+/// Credentials for acting in a race, read from a JSON file with a `token` key: an OAuth2 access
+/// token for racetime.gg with the `race_action` scope.
+#[cfg(feature = "racetime")]
+#[derive(serde::Deserialize)]
+struct Credentials {
+    token: String,
+}
+
+/// Mark the runner done in the racetime.gg race `room` (e.g. `vvvvvv/clever-banana-1234`) when the
+/// game is completed, and forfeit when a run in progress is reset. A correction of the final split
+/// undoes the finish.
+#[cfg(feature = "racetime")]
+pub fn spawn(
+    room: &str,
+    credentials: &Path,
+    receiver: Receiver<Message>,
+) -> Result<JoinHandle<()>> {
+    use anyhow::Context;
+    use vitellary_core::game::{Command, Event};
+
+    let credentials: Credentials = serde_json::from_slice(
+        &std::fs::read(credentials)
+            .with_context(|| format!("failed to read {}", credentials.display()))?,
+    )
+    .context("failed to parse racetime.gg credentials")?;
+    let room = room
+        .trim_start_matches("https://racetime.gg/")
+        .trim_matches('/')
+        .to_owned();
+    let url = format!(
+        "wss://racetime.gg/ws/o/race/{}?token={}",
+        room, credentials.token
+    );
+
+    Ok(std::thread::spawn(move || {
+        let mut running = false;
+        let mut done = false;
+        for message in receiver {
+            let Message::Update(update) = message else {
+                continue;
+            };
+            let action = match update.event {
+                Some(Event::NewGame) => {
+                    running = true;
+                    done = false;
+                    continue;
+                }
+                Some(Event::GameComplete) if running => {
+                    done = true;
+                    "done"
+                }
+                Some(Event::Correction | Event::Manual(Command::Undo)) if done => {
+                    done = false;
+                    "undone"
+                }
+                Some(Event::Reset | Event::Manual(Command::Reset)) if running && !done => {
+                    running = false;
+                    "forfeit"
+                }
+                _ => continue,
+            };
+            match send(&url, action) {
+                Ok(()) => log::info!("racetime.gg: sent {} to {}", action, room),
+                Err(err) => log::warn!("racetime.gg: failed to send {}: {:#}", action, err),
+            }
+        }
+    }))
+}
+
+/// Connect to the race room, send one action, and hang up. Actions are rare enough that holding
+/// the connection open isn't worth handling the room's chatter.
+#[cfg(feature = "racetime")]
+fn send(url: &str, action: &str) -> Result<()> {
+    use tungstenite::Message as WsMessage;
+
+    let (mut websocket, _) = tungstenite::connect(url)?;
+    websocket.write_message(WsMessage::Text(
+        serde_json::json!({ "action": action }).to_string(),
+    ))?;
+    websocket.close(None)?;
+    while websocket.read_message().is_ok() {}
+    Ok(())
+}
+
+#[cfg(not(feature = "racetime"))]
+pub fn spawn(
+    _room: &str,
+    _credentials: &Path,
+    _receiver: Receiver<Message>,
+) -> Result<JoinHandle<()>> {
+    anyhow::bail!(
+        "this build of vitellary does not support racetime.gg (enable the `racetime` feature)"
+    )
+}
@@ -0,0 +1,124 @@
+use crossbeam_channel::Receiver;
+use serde::{Serialize, Serializer};
+use std::time::Duration;
+use uuid::Uuid;
+use vitellary_core::game::{Event, Message, Run, Update};
+
+/// What happened, in a [`Check`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Kind {
+    /// A run started.
+    Start,
+    /// The player entered a room.
+    Room,
+    /// The player collected a trinket.
+    Trinket,
+    /// The player rescued a crewmate.
+    Rescue,
+    /// The run reached the end of the game.
+    Complete,
+    /// The run reset.
+    Reset,
+}
+
+/// One line of the randomizer feed: something a multiworld or randomizer client may want to act
+/// on, with where the player is and what they've collected when it happened.
+///
+/// This is kept stable for clients outside this repository: fields and kinds may be added, but
+/// not removed or changed, so clients should ignore what they don't know.
+#[derive(Debug, Clone, Serialize)]
+pub struct Check {
+    pub kind: Kind,
+    pub run_id: Uuid,
+    #[serde(serialize_with = "serialize_secs")]
+    pub time: Duration,
+    /// The room the player is in.
+    pub room: (u32, u32),
+    /// Trinkets collected this run.
+    pub trinkets: u32,
+    /// Crewmates rescued this run, including Viridian.
+    pub crew: u32,
+    /// The campaign split the rescue made, with [`Kind::Rescue`] (`null` in custom levels).
+    pub crewmate: Option<Event>,
+}
+
+#[allow(clippy::trivially_copy_pass_by_ref)] // required by serde
+fn serialize_secs<S: Serializer>(time: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_f64(time.as_secs_f64())
+}
+
+/// The checks an update makes, given the one before it in the run.
+fn checks(last: Option<&Update>, update: &Update) -> Vec<Kind> {
+    match update.event {
+        Some(Event::NewGame) => return vec![Kind::Start],
+        Some(Event::Reset) => return vec![Kind::Reset],
+        Some(Event::GameComplete) => return vec![Kind::Complete],
+        _ => {}
+    }
+    if update.run != Run::Running {
+        return Vec::new();
+    }
+    let Some(last) = last else {
+        return vec![Kind::Room];
+    };
+    let mut kinds = Vec::new();
+    if update.state.room != last.state.room {
+        kinds.push(Kind::Room);
+    }
+    if update.trinkets > last.trinkets {
+        kinds.push(Kind::Trinket);
+    }
+    if update.crew > last.crew {
+        kinds.push(Kind::Rescue);
+    }
+    kinds
+}
+
+/// Turn each run's updates into checks for multiworld and randomizer clients. Returns the
+/// randomizer feed.
+pub fn spawn(receiver: Receiver<Message>) -> Receiver<Check> {
+    let (sender, feed) = crossbeam_channel::bounded(10);
+    std::thread::spawn(move || {
+        let mut last: Option<Update> = None;
+        for message in receiver {
+            let Message::Update(update) = message else {
+                continue;
+            };
+            if last
+                .as_ref()
+                .is_some_and(|last| last.run_id != update.run_id)
+            {
+                last = None;
+            }
+            for kind in checks(last.as_ref(), &update) {
+                let crewmate = update.event.filter(|event| {
+                    kind == Kind::Rescue
+                        && matches!(
+                            event,
+                            Event::Verdigris
+                                | Event::Vermilion
+                                | Event::Victoria
+                                | Event::Violet
+                                | Event::Vitellary
+                        )
+                });
+                let check = Check {
+                    kind,
+                    run_id: update.run_id,
+                    time: update.time,
+                    room: update.state.room,
+                    trinkets: update.trinkets,
+                    crew: update.crew,
+                    crewmate,
+                };
+                log::debug!("randomizer: {:?} in {:?}", check.kind, check.room);
+                if sender.send(check).is_err() {
+                    return;
+                }
+            }
+            last = (update.run == Run::Running).then_some(update);
+        }
+    });
+    feed
+}
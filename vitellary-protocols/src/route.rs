@@ -0,0 +1,214 @@
+use anyhow::{bail, Context, Result};
+use crossbeam_channel::Receiver;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::thread::JoinHandle;
+use std::time::Duration;
+use uuid::Uuid;
+use vitellary_core::game::{Event, Message, RoomNames, Run, Summary};
+
+/// How to write a run's route.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Json,
+    Csv,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Format, String> {
+        match s {
+            "json" => Ok(Format::Json),
+            "csv" => Ok(Format::Csv),
+            _ => Err(format!(
+                "unknown route format {:?} (expected json or csv)",
+                s
+            )),
+        }
+    }
+}
+
+/// A stay in one room.
+#[derive(Debug, Clone, Serialize)]
+struct Visit {
+    x: u32,
+    y: u32,
+    name: Option<String>,
+    /// The game time the room was entered, in seconds.
+    entered: f64,
+    /// How long the player stayed, in seconds.
+    duration: f64,
+}
+
+#[derive(Serialize)]
+struct Route<'a> {
+    run_id: Uuid,
+    time: f64,
+    visits: &'a [Visit],
+}
+
+/// Track the rooms visited in each run, and when a run is completed, write them with the game time
+/// each was entered to `route-RUN_ID.json` or `.csv` in `dir`. With `heatmap`, also draw the map
+/// with each room shaded by the time spent in it, as `route-RUN_ID.svg`.
+pub fn spawn(
+    dir: &Path,
+    format: Format,
+    heatmap: bool,
+    rooms: RoomNames,
+    receiver: Receiver<Message>,
+) -> Result<JoinHandle<()>> {
+    if !dir.is_dir() {
+        bail!("{} is not a directory", dir.display());
+    }
+    let dir = dir.to_owned();
+    Ok(std::thread::spawn(move || {
+        let mut visits: Vec<((u32, u32), Duration)> = Vec::new();
+        for message in receiver {
+            match message {
+                Message::Update(update) => {
+                    if update.event == Some(Event::NewGame) {
+                        visits.clear();
+                    }
+                    if update.run != Run::Running {
+                        continue;
+                    }
+                    if visits
+                        .last()
+                        .is_none_or(|(room, _)| *room != update.state.room)
+                    {
+                        visits.push((update.state.room, update.time));
+                    }
+                }
+                Message::Summary(summary) => {
+                    let visits = finish(&visits, &summary, &rooms);
+                    if let Err(err) = write(&dir, format, heatmap, &summary, &visits) {
+                        log::warn!("failed to write route: {:#}", err);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }))
+}
+
+/// Work out how long each visit lasted, the last one ending when the run did.
+fn finish(visits: &[((u32, u32), Duration)], summary: &Summary, rooms: &RoomNames) -> Vec<Visit> {
+    visits
+        .iter()
+        .enumerate()
+        .map(|(i, &(room, entered))| {
+            let left = visits.get(i + 1).map_or(summary.time, |(_, time)| *time);
+            Visit {
+                x: room.0,
+                y: room.1,
+                name: rooms.get(room).map(ToOwned::to_owned),
+                entered: entered.as_secs_f64(),
+                duration: left.saturating_sub(entered).as_secs_f64(),
+            }
+        })
+        .collect()
+}
+
+fn write(
+    dir: &Path,
+    format: Format,
+    heatmap: bool,
+    summary: &Summary,
+    visits: &[Visit],
+) -> Result<()> {
+    let file = |extension: &str| -> PathBuf {
+        dir.join(format!("route-{}.{}", summary.run_id, extension))
+    };
+    let (path, data) = match format {
+        Format::Json => (
+            file("json"),
+            serde_json::to_string_pretty(&Route {
+                run_id: summary.run_id,
+                time: summary.time.as_secs_f64(),
+                visits,
+            })?,
+        ),
+        Format::Csv => {
+            let mut csv = "x,y,name,entered,duration\n".to_owned();
+            for visit in visits {
+                writeln!(
+                    csv,
+                    "{},{},{},{:.3},{:.3}",
+                    visit.x,
+                    visit.y,
+                    csv_field(visit.name.as_deref().unwrap_or_default()),
+                    visit.entered,
+                    visit.duration
+                )?;
+            }
+            (file("csv"), csv)
+        }
+    };
+    std::fs::write(&path, data).with_context(|| format!("failed to write {}", path.display()))?;
+    log::info!("wrote route to {}", path.display());
+    if heatmap {
+        let path = file("svg");
+        std::fs::write(&path, svg(visits))
+            .with_context(|| format!("failed to write {}", path.display()))?;
+    }
+    Ok(())
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+/// Draw the rooms visited on a grid, darker the longer was spent in each.
+fn svg(visits: &[Visit]) -> String {
+    const CELL: u32 = 24;
+
+    let mut totals: HashMap<(u32, u32), f64> = HashMap::new();
+    for visit in visits {
+        *totals.entry((visit.x, visit.y)).or_default() += visit.duration;
+    }
+    let (Some(min_x), Some(max_x), Some(min_y), Some(max_y)) = (
+        totals.keys().map(|room| room.0).min(),
+        totals.keys().map(|room| room.0).max(),
+        totals.keys().map(|room| room.1).min(),
+        totals.keys().map(|room| room.1).max(),
+    ) else {
+        return "<svg xmlns=\"http://www.w3.org/2000/svg\"/>\n".to_owned();
+    };
+    let longest = totals.values().copied().fold(0.0, f64::max);
+
+    let mut svg = String::new();
+    let _ = writeln!(
+        svg,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\">",
+        (max_x - min_x + 1) * CELL,
+        (max_y - min_y + 1) * CELL
+    );
+    let mut rooms: Vec<_> = totals.into_iter().collect();
+    rooms.sort_by_key(|(room, _)| *room);
+    for ((x, y), total) in rooms {
+        let opacity = if longest > 0.0 { total / longest } else { 1.0 };
+        let _ = writeln!(
+            svg,
+            "  <rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"#c00\" \
+             fill-opacity=\"{:.3}\" stroke=\"#888\"><title>({}, {}): {:.2}s</title></rect>",
+            (x - min_x) * CELL,
+            (y - min_y) * CELL,
+            CELL,
+            CELL,
+            opacity.max(0.05),
+            x,
+            y,
+            total
+        );
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
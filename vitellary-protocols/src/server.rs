@@ -0,0 +1,1120 @@
+use crate::ghost::Delta;
+use crate::goals::Completion;
+use crate::race::Comparison;
+use crate::randomizer::Check;
+use crate::splits::Splits;
+use crate::state::RunState;
+use crate::stream::{Stdio, Stream};
+use crate::{binary, compression, queue};
+use anyhow::{bail, Result};
+use crossbeam_channel::{Receiver, RecvTimeoutError};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::VecDeque;
+use std::net::TcpListener;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tungstenite::handshake::server::Request;
+use tungstenite::http::HeaderValue;
+use tungstenite::protocol::frame::coding::Data;
+use tungstenite::{Message as WsMessage, WebSocket};
+use uuid::Uuid;
+use vitellary_core::game::{Command, Event, Message, Timestamp, Update};
+
+/// The version of the JSON protocol: the messages `/json` clients receive and can send. It only
+/// goes up for changes that would break existing clients; features added alongside are listed in
+/// the capabilities instead.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// What the server tells clients about itself, so they can check for features without breaking
+/// on servers that lack them.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename = "hello")]
+struct Hello {
+    version: u32,
+    /// The version of vitellary.
+    server: &'static str,
+    /// The binary subprotocol, for clients that would rather have that.
+    binary: &'static str,
+    capabilities: Vec<&'static str>,
+}
+
+impl Hello {
+    fn new(feeds: &FeedSubscribers, options: Options) -> Hello {
+        let mut capabilities = vec![
+            "json",
+            "binary",
+            "split-info",
+            "time-sync",
+            "health",
+            "frame-hints",
+        ];
+        for (capability, on) in [
+            ("race", feeds.race.is_some()),
+            ("ghost", feeds.ghost.is_some()),
+            ("goals", feeds.goals.is_some()),
+            ("randomizer", feeds.randomizer.is_some()),
+            ("deflate", options.compress),
+            ("latency-compensation", options.compensate_latency),
+            ("split-check", options.split_count.is_some()),
+        ] {
+            if on {
+                capabilities.push(capability);
+            }
+        }
+        Hello {
+            version: PROTOCOL_VERSION,
+            server: env!("CARGO_PKG_VERSION"),
+            binary: binary::SUBPROTOCOL,
+            capabilities,
+        }
+    }
+
+    /// Advertise the protocol version and capabilities in a handshake response's headers.
+    fn headers(&self, response: &mut tungstenite::handshake::server::Response) {
+        let headers = response.headers_mut();
+        headers.insert("Vitellary-Protocol", HeaderValue::from(self.version));
+        if let Ok(capabilities) = HeaderValue::from_str(&self.capabilities.join(", ")) {
+            headers.insert("Vitellary-Capabilities", capabilities);
+        }
+    }
+}
+
+/// Clients subscribed to a feed. Each gets its own copy of every item; clients that fall behind
+/// miss the items that can be missed rather than holding up the others.
+///
+/// The feed also keeps some state `S` built up from every item, which new subscribers get a copy
+/// of as of the first item they'll receive, and the items that can't be missed that came while
+/// nobody was subscribed, which the next subscriber gets if they're recent enough.
+struct Subscribers<T, S>(Arc<Mutex<Feed<T, S>>>);
+
+struct Feed<T, S> {
+    senders: Vec<queue::Sender<T>>,
+    state: S,
+    /// Items that can't be missed from while nobody was subscribed, with when they came.
+    missed: VecDeque<(Instant, T)>,
+    /// How long to keep them.
+    window: Duration,
+}
+
+impl<T, S> Clone for Subscribers<T, S> {
+    fn clone(&self) -> Subscribers<T, S> {
+        Subscribers(Arc::clone(&self.0))
+    }
+}
+
+impl<T: Clone + Send + 'static, S: Clone + Send + 'static> Subscribers<T, S> {
+    /// Start copying items from `receiver` to every subscriber, applying each to the state with
+    /// `apply` first. Subscribers that are behind miss the items `droppable` says they can; the
+    /// others are kept for `window` while nobody is subscribed.
+    fn spawn(
+        receiver: Receiver<T>,
+        state: S,
+        apply: impl Fn(&mut S, &T) + Send + 'static,
+        droppable: impl Fn(&T) -> bool + Send + 'static,
+        window: Duration,
+    ) -> Subscribers<T, S> {
+        let feed = Arc::new(Mutex::new(Feed {
+            senders: Vec::new(),
+            state,
+            missed: VecDeque::new(),
+            window,
+        }));
+        std::thread::spawn({
+            let feed = Arc::clone(&feed);
+            move || loop {
+                let item = match receiver.recv_timeout(POLL_INTERVAL) {
+                    Ok(item) => item,
+                    // Let anything a subscriber is waiting for through while the feed is quiet.
+                    Err(RecvTimeoutError::Timeout) => {
+                        let mut feed = feed.lock().unwrap();
+                        feed.senders.retain_mut(|sender| sender.flush().is_ok());
+                        continue;
+                    }
+                    Err(RecvTimeoutError::Disconnected) => return,
+                };
+                let mut feed = feed.lock().unwrap();
+                apply(&mut feed.state, &item);
+                let droppable = droppable(&item);
+                if feed.senders.is_empty() && !droppable && !feed.window.is_zero() {
+                    let window = feed.window;
+                    feed.missed.retain(|(at, _)| at.elapsed() <= window);
+                    if feed.missed.len() < MAX_MISSED {
+                        feed.missed.push_back((Instant::now(), item));
+                    }
+                    continue;
+                }
+                feed.senders.retain_mut(|sender| {
+                    let sent = sender.send(item.clone(), droppable);
+                    if sent.is_err() && sender.dropped() > 0 {
+                        log::debug!("a client missed {} updates", sender.dropped());
+                    }
+                    sent.is_ok()
+                });
+            }
+        });
+        Subscribers(feed)
+    }
+
+    /// Subscribe, returning the items to receive, the state as of the first of them, and what
+    /// was missed while nobody was subscribed (which the state already includes).
+    fn subscribe(&self) -> (Receiver<T>, S, Vec<T>) {
+        let (sender, receiver) = crossbeam_channel::bounded(10);
+        let mut feed = self.0.lock().unwrap();
+        feed.senders.push(queue::Sender::new(sender));
+        let window = feed.window;
+        let missed = feed
+            .missed
+            .drain(..)
+            .filter(|(at, _)| at.elapsed() <= window)
+            .map(|(_, item)| item)
+            .collect();
+        (receiver, feed.state.clone(), missed)
+    }
+}
+
+/// The most messages kept for the next client while none are connected.
+const MAX_MISSED: usize = 1000;
+
+/// How long a connection waits for a message before checking what its client sent.
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// How often to ping clients to measure latency.
+const PING_INTERVAL: Duration = Duration::from_secs(2);
+
+/// How long a client can go without answering a ping, or take to accept a message, before we
+/// drop it as dead.
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long a LiveSplit One has to answer the queries counting its segments before we stop
+/// waiting for it.
+const SPLIT_CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How many accepts in a row can fail before the server gives up on its socket.
+const ACCEPT_FAILURES: u32 = 10;
+
+/// How the server is doing, for the watchdog.
+#[derive(Debug, Clone, Default)]
+pub struct Status {
+    clients: Arc<AtomicUsize>,
+    failed: Arc<AtomicBool>,
+    finished: Arc<AtomicBool>,
+}
+
+/// Where the server's clients come from.
+pub enum Listener {
+    /// Accept clients on a socket.
+    Tcp(TcpListener),
+    /// Serve one client over stdin and stdout, as started by inetd or over SSH, then finish.
+    Stdio,
+}
+
+impl Status {
+    /// How many clients are connected.
+    pub fn clients(&self) -> usize {
+        self.clients.load(Ordering::Relaxed)
+    }
+
+    /// Whether the server stopped accepting clients because its socket kept failing, so it needs
+    /// binding again.
+    pub fn failed(&self) -> bool {
+        self.failed.load(Ordering::Relaxed)
+    }
+
+    /// Whether the server's only client, over stdin and stdout, has disconnected.
+    pub fn finished(&self) -> bool {
+        self.finished.load(Ordering::Relaxed)
+    }
+}
+
+/// Counts a client as connected while it's alive.
+struct Connected(Arc<AtomicUsize>);
+
+impl Connected {
+    fn new(clients: &Arc<AtomicUsize>) -> Connected {
+        clients.fetch_add(1, Ordering::Relaxed);
+        Connected(Arc::clone(clients))
+    }
+}
+
+impl Drop for Connected {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Server options that apply to every client.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Options {
+    /// Also send a split to LiveSplit One when a teleporter is used.
+    pub split_on_teleport: bool,
+    /// Compress messages to clients that offer permessage-deflate.
+    pub compress: bool,
+    /// Add half the measured round trip time to the game time sent to LiveSplit One while the
+    /// timer is running, so it isn't always behind by the network latency.
+    pub compensate_latency: bool,
+    /// How many splits a run makes, if known, to check each LiveSplit One's splits against.
+    pub split_count: Option<usize>,
+    /// How long to keep messages that can't be missed (like updates with events) while no client
+    /// is connected, for the next client that connects.
+    pub replay_window: Duration,
+}
+
+/// The feeds besides the splitter's messages that clients can ask for, each there if it's in use.
+#[derive(Default)]
+pub struct Feeds {
+    pub race: Option<Receiver<Comparison>>,
+    pub ghost: Option<Receiver<Delta>>,
+    pub goals: Option<Receiver<Completion>>,
+    pub randomizer: Option<Receiver<Check>>,
+}
+
+/// The feeds as clients subscribe to them. Goal completions and randomizer checks so far are kept
+/// for new subscribers (until the next run's first); the others' items are only of interest as
+/// they come.
+#[derive(Clone)]
+struct FeedSubscribers {
+    race: Option<Subscribers<Comparison, Vec<Comparison>>>,
+    ghost: Option<Subscribers<Delta, Vec<Delta>>>,
+    goals: Option<Subscribers<Completion, Vec<Completion>>>,
+    randomizer: Option<Subscribers<Check, Vec<Check>>>,
+}
+
+impl FeedSubscribers {
+    fn spawn(feeds: Feeds) -> FeedSubscribers {
+        FeedSubscribers {
+            race: feeds.race.map(|race| {
+                Subscribers::spawn(race, Vec::new(), |_, _| {}, |_| true, Duration::ZERO)
+            }),
+            ghost: feeds.ghost.map(|ghost| {
+                Subscribers::spawn(ghost, Vec::new(), |_, _| {}, |_| true, Duration::ZERO)
+            }),
+            goals: feeds.goals.map(|goals| {
+                Subscribers::spawn(
+                    goals,
+                    Vec::new(),
+                    keep_run(|completion: &Completion| completion.run_id),
+                    |_| false,
+                    Duration::ZERO,
+                )
+            }),
+            randomizer: feeds.randomizer.map(|randomizer| {
+                Subscribers::spawn(
+                    randomizer,
+                    Vec::new(),
+                    keep_run(|check: &Check| check.run_id),
+                    |_| false,
+                    Duration::ZERO,
+                )
+            }),
+        }
+    }
+}
+
+/// Keep the items of the latest run, by `run_id`.
+fn keep_run<T: Clone>(run_id: fn(&T) -> Uuid) -> impl Fn(&mut Vec<T>, &T) {
+    move |kept, item| {
+        if kept
+            .first()
+            .is_some_and(|first| run_id(first) != run_id(item))
+        {
+            kept.clear();
+        }
+        kept.push(item.clone());
+    }
+}
+
+/// Accept WebSocket clients from `server`, sending each of them messages from `receiver`.
+///
+/// Clients connecting to `/json` receive every message as a JSON object. Updates whose event makes
+/// a split also have a `split` object with the split's `index` in the run, its `segment` time, and
+/// the cumulative game time `igt` (in seconds). Clients connecting to `/race` receive the race
+/// comparison feed (if racing), clients connecting to `/ghost` the room deltas (if comparing
+/// against a ghost), clients connecting to `/goals` the goals completed this run and then each as
+/// it's completed (if tracking goals), and clients connecting to `/randomizer` the [`Check`]s made
+/// this run and then each as it's made (if serving them), as JSON objects; all other clients are
+/// assumed to be LiveSplit One and receive its server commands for each update. A LiveSplit One
+/// connecting to `/category/NAME` follows the splits of the category `NAME` tracked alongside the
+/// run (see [`Game::track_category`](vitellary_core::game::Game::track_category)) instead of the
+/// run's own.
+///
+/// A LiveSplit One that connects in the middle of a run is brought up to date first: it's started,
+/// and the splits made so far are replayed at the game times they were made. If the split count is
+/// known, its segments are counted with `getSegmentName` queries (which LiveSplit One versions
+/// with the JSON server protocol answer), and a warning is logged if they don't match, or aren't
+/// all answered within 5 seconds. The protocol has no way to replace a layout's segments, so
+/// fixing them is left to the runner.
+///
+/// Clients that ask for the `vitellary.binary.v1` subprotocol receive updates in the compact
+/// binary format described in [`binary`] instead, whatever the path.
+///
+/// Every handshake response has a `Vitellary-Protocol` header with [`PROTOCOL_VERSION`] and a
+/// `Vitellary-Capabilities` header listing the optional features this server has (e.g. `race`,
+/// `ghost`, `deflate`). JSON clients (on `/json`, `/race`, `/ghost`, `/goals` and `/randomizer`)
+/// also receive the same as a first `{"type": "hello", "version": ..., "server": ..., "binary":
+/// ..., "capabilities": [...]}` message, where `server` is vitellary's version and `binary` the
+/// binary subprotocol. Clients should ignore capabilities and message types they don't know.
+///
+/// Every client is pinged regularly to measure its latency. JSON clients can also send
+/// `{"type": "ping", "client_time": ...}` to sync their clocks; the reply is
+/// `{"type": "pong", "client_time": ..., "seq": ..., "time": ..., "age": ..., "latency": ...,
+/// "monotonic": ...}`, where `time` is the game time of the latest update, `age` is how many
+/// seconds ago this connection received it, `latency` is the one-way latency we measured, if any,
+/// and `monotonic` is the server's clock (all in seconds).
+///
+/// To keep a timer counting smoothly between the game's logic frames, JSON updates carry a
+/// `frame` while the game is playing: `{"index": ..., "rate": ..., "started": ..., "timestep":
+/// ...}`, where `started` is when the frame was first read by the server's clock and `timestep`
+/// how long frames have been lasting. A client that maps the server's clock onto its own with
+/// pings can add `(now - started) / timestep` frames (up to one) of `1 / rate` seconds to the
+/// update's `time`.
+///
+/// Clients that don't answer pings or accept messages for 10 seconds are dropped. The server
+/// stops if accepting clients fails 10 times in a row, which the returned [`Status`] shows, as it
+/// does when a [`Listener::Stdio`] client disconnects.
+#[allow(clippy::result_large_err)] // tungstenite's handshake callback signature
+pub fn spawn(
+    server: Listener,
+    receiver: Receiver<Message>,
+    feeds: Feeds,
+    options: Options,
+) -> Status {
+    let messages = Subscribers::spawn(
+        receiver,
+        RunState::default(),
+        move |state, message| {
+            state.apply(message, options.split_on_teleport);
+        },
+        Message::droppable,
+        options.replay_window,
+    );
+    let feeds = FeedSubscribers::spawn(feeds);
+    let hello = Arc::new(Hello::new(&feeds, options));
+    let status = Status::default();
+    let server = match server {
+        Listener::Tcp(server) => server,
+        Listener::Stdio => {
+            std::thread::spawn({
+                let status = status.clone();
+                move || {
+                    let connected = Connected::new(&status.clients);
+                    let stream = Stream::Stdio(Stdio::new());
+                    if let Err(err) = serve(stream, &messages, &feeds, &hello, options) {
+                        log::debug!("the client on stdin and stdout: {:#}", err);
+                    }
+                    drop(connected);
+                    status.finished.store(true, Ordering::Relaxed);
+                }
+            });
+            return status;
+        }
+    };
+    std::thread::spawn({
+        let status = status.clone();
+        move || {
+            let mut failures = 0;
+            for stream in server.incoming() {
+                let stream = match stream {
+                    Ok(stream) => {
+                        failures = 0;
+                        stream
+                    }
+                    Err(err) => {
+                        log::warn!("failed to accept a WebSocket client: {}", err);
+                        failures += 1;
+                        if failures == ACCEPT_FAILURES {
+                            status.failed.store(true, Ordering::Relaxed);
+                            return;
+                        }
+                        continue;
+                    }
+                };
+                let messages = messages.clone();
+                let feeds = feeds.clone();
+                let hello = Arc::clone(&hello);
+                let connected = Connected::new(&status.clients);
+                std::thread::spawn(move || -> Result<()> {
+                    let _connected = connected;
+                    serve(Stream::Tcp(stream), &messages, &feeds, &hello, options)
+                });
+            }
+        }
+    });
+    status
+}
+
+/// Serve one client.
+#[allow(clippy::result_large_err)] // tungstenite's handshake callback signature
+fn serve(
+    stream: Stream,
+    messages: &Subscribers<Message, RunState>,
+    feeds: &FeedSubscribers,
+    hello: &Arc<Hello>,
+    options: Options,
+) -> Result<()> {
+    let handshake = Arc::new(Mutex::new(Handshake::default()));
+    stream.set_write_timeout(Some(CLIENT_TIMEOUT))?;
+    let mut websocket = tungstenite::accept_hdr(compression::Inflate::new(stream), {
+        let handshake = Arc::clone(&handshake);
+        let hello = Arc::clone(hello);
+        move |request: &Request, mut response| {
+            hello.headers(&mut response);
+            let mut handshake = handshake.lock().unwrap();
+            request.uri().path().clone_into(&mut handshake.path);
+            handshake.deflate = options.compress && compression::negotiate(request, &mut response);
+            handshake.binary = binary::negotiate(request, &mut response);
+            Ok(response)
+        }
+    })?;
+    let handshake = handshake.lock().unwrap().clone();
+    if handshake.deflate {
+        websocket.get_mut().start();
+    }
+    websocket
+        .get_mut()
+        .get_mut()
+        .set_read_timeout(Some(Duration::from_millis(1)))?;
+    let mut client = Client::new(websocket, handshake.deflate);
+    if handshake.binary {
+        let (receiver, _, missed) = messages.subscribe();
+        for message in missed {
+            if let Message::Update(update) = message {
+                client.send_binary(&binary::encode(&update))?;
+            }
+        }
+        return client.serve(&receiver, |client, message| match message {
+            Message::Update(update) => {
+                client.track(&update);
+                client.send_binary(&binary::encode(&update))
+            }
+            _ => Ok(()),
+        });
+    }
+    if matches!(
+        handshake.path.as_str(),
+        "/json" | "/race" | "/ghost" | "/goals" | "/randomizer"
+    ) {
+        client.send(&serde_json::to_string(&**hello)?)?;
+    }
+    match handshake.path.as_str() {
+        "/json" => {
+            let (receiver, mut state, missed) = messages.subscribe();
+            for message in missed {
+                let mut value = serde_json::to_value(&message)?;
+                value["replayed"] = true.into();
+                client.send(&value.to_string())?;
+            }
+            client.serve(&receiver, |client, message| {
+                let mut value = serde_json::to_value(&message)?;
+                let index = state.apply(&message, options.split_on_teleport);
+                if let Message::Update(update) = &message {
+                    client.track(update);
+                    if let Some(index) = index {
+                        value["split"] = json!({
+                            "index": index,
+                            "segment": state
+                                .splits
+                                .segment(index)
+                                .map(|s| s.as_secs_f64()),
+                            "igt": update.time.as_secs_f64(),
+                        });
+                    }
+                }
+                client.send(&value.to_string())
+            })
+        }
+        "/race" => client.serve_feed(feeds.race.as_ref()),
+        "/ghost" => client.serve_feed(feeds.ghost.as_ref()),
+        "/goals" => client.serve_feed(feeds.goals.as_ref()),
+        "/randomizer" => client.serve_feed(feeds.randomizer.as_ref()),
+        path => {
+            let category = path.strip_prefix("/category/").map(str::to_owned);
+            client.serve_livesplit(messages, options, category.as_deref())
+        }
+    }
+}
+
+/// What a client asked for in its handshake.
+#[derive(Clone, Default)]
+struct Handshake {
+    path: String,
+    deflate: bool,
+    binary: bool,
+}
+
+/// A time sync request from a JSON client.
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+enum SyncRequest {
+    Ping { client_time: serde_json::Value },
+}
+
+/// A connected client.
+struct Client {
+    websocket: WebSocket<compression::Inflate<Stream>>,
+    /// Whether it accepted compression.
+    deflate: bool,
+    /// When the connection started, which ping payloads count from.
+    epoch: Instant,
+    last_ping: Option<Instant>,
+    /// When the client last answered a ping, or connected.
+    last_pong: Instant,
+    /// Half the round trip time, smoothed.
+    latency: Option<Duration>,
+    /// The latest update sent, and when it arrived.
+    last_update: Option<(u64, Duration, Instant)>,
+    /// Whether the game time advanced between the last two updates.
+    timer_running: bool,
+    split_check: Option<SplitCheck>,
+}
+
+/// Counting a LiveSplit One's segments: we ask for the name of each of the `expected` segments and
+/// one more, and the first that fails is the count.
+struct SplitCheck {
+    expected: usize,
+    answered: usize,
+    started: Instant,
+}
+
+/// What counting a LiveSplit One's segments found.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SplitCount {
+    /// It has this many segments, up to the expected number.
+    Found(usize),
+    /// It has more segments than expected.
+    More,
+    /// It didn't answer every query within [`SPLIT_CHECK_TIMEOUT`].
+    Silent,
+}
+
+impl SplitCheck {
+    fn new(expected: usize) -> SplitCheck {
+        SplitCheck {
+            expected,
+            answered: 0,
+            started: Instant::now(),
+        }
+    }
+
+    /// Count an answer to a `getSegmentName` query: whether the segment exists. Returns the
+    /// count once it's known.
+    fn answer(&mut self, exists: bool) -> Option<SplitCount> {
+        if !exists {
+            return Some(SplitCount::Found(self.answered));
+        }
+        self.answered += 1;
+        (self.answered > self.expected).then_some(SplitCount::More)
+    }
+
+    /// Give up on a client that still hasn't answered every query at `now`.
+    fn expire(&self, now: Instant) -> Option<SplitCount> {
+        (now.saturating_duration_since(self.started) >= SPLIT_CHECK_TIMEOUT)
+            .then_some(SplitCount::Silent)
+    }
+}
+
+impl Client {
+    fn new(websocket: WebSocket<compression::Inflate<Stream>>, deflate: bool) -> Client {
+        Client {
+            websocket,
+            deflate,
+            epoch: Instant::now(),
+            last_ping: None,
+            last_pong: Instant::now(),
+            latency: None,
+            last_update: None,
+            timer_running: false,
+            split_check: None,
+        }
+    }
+
+    /// Start counting the client's segments, to warn if it doesn't have `expected` of them.
+    fn check_splits(&mut self, expected: usize) -> Result<()> {
+        for index in 0..=expected {
+            let query = json!({"command": "getSegmentName", "index": index, "relative": false});
+            self.send(&query.to_string())?;
+        }
+        self.split_check = Some(SplitCheck::new(expected));
+        Ok(())
+    }
+
+    /// Count an answer to a `getSegmentName` query: whether the segment exists.
+    fn segment_answer(&mut self, exists: bool) {
+        if let Some(count) = self
+            .split_check
+            .as_mut()
+            .and_then(|check| check.answer(exists))
+        {
+            self.finish_split_check(count);
+        }
+    }
+
+    /// Warn if counting the client's segments found the wrong number, or it didn't answer.
+    fn finish_split_check(&mut self, count: SplitCount) {
+        let Some(check) = self.split_check.take() else {
+            return;
+        };
+        let expected = check.expected;
+        match count {
+            SplitCount::Found(found) if found == expected => {
+                log::debug!("LiveSplit One has the expected {} splits", expected);
+            }
+            SplitCount::Found(found) => log::warn!(
+                "LiveSplit One has {} splits, but vitellary will make {}; fix the splits before \
+                 running, or they will be out of sync",
+                found,
+                expected
+            ),
+            SplitCount::More => log::warn!(
+                "LiveSplit One has more than {} splits, but vitellary will only make {}; fix the \
+                 splits before running, or they will be out of sync",
+                expected,
+                expected
+            ),
+            SplitCount::Silent => log::warn!(
+                "LiveSplit One didn't answer when asked how many splits it has; check it has {} \
+                 before running",
+                expected
+            ),
+        }
+    }
+
+    /// Send everything from `receiver` with `send`, pinging the client and answering what it
+    /// sends in between, until either side hangs up.
+    fn serve<T>(
+        &mut self,
+        receiver: &Receiver<T>,
+        mut send: impl FnMut(&mut Client, T) -> Result<()>,
+    ) -> Result<()> {
+        loop {
+            match receiver.recv_timeout(POLL_INTERVAL) {
+                Ok(item) => send(self, item)?,
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => return Ok(()),
+            }
+            self.service()?;
+        }
+    }
+
+    /// Note an update about to be sent, for time sync and latency compensation.
+    fn track(&mut self, update: &Update) {
+        self.timer_running = self
+            .last_update
+            .is_some_and(|(_, time, _)| update.time > time);
+        self.last_update = Some((update.seq, update.time, Instant::now()));
+    }
+
+    /// Ping the client if it's time, and handle anything it sent.
+    fn service(&mut self) -> Result<()> {
+        if self.last_pong.elapsed() >= CLIENT_TIMEOUT {
+            bail!("client stopped answering pings");
+        }
+        let expired = self.split_check.as_ref();
+        if let Some(count) = expired.and_then(|check| check.expire(Instant::now())) {
+            self.finish_split_check(count);
+        }
+        if self
+            .last_ping
+            .is_none_or(|at| at.elapsed() >= PING_INTERVAL)
+        {
+            let payload = u64::try_from(self.epoch.elapsed().as_nanos()).unwrap_or(u64::MAX);
+            self.websocket
+                .write_message(WsMessage::Ping(payload.to_le_bytes().to_vec()))?;
+            self.last_ping = Some(Instant::now());
+        }
+        loop {
+            let message = match self.websocket.read_message() {
+                Ok(message) => message,
+                Err(tungstenite::Error::Io(err))
+                    if matches!(
+                        err.kind(),
+                        std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+                    ) =>
+                {
+                    return Ok(());
+                }
+                Err(err) => return Err(err.into()),
+            };
+            match message {
+                WsMessage::Pong(payload) => {
+                    self.last_pong = Instant::now();
+                    let Ok(sent) = <[u8; 8]>::try_from(payload.as_slice()) else {
+                        continue;
+                    };
+                    let sent = Duration::from_nanos(u64::from_le_bytes(sent));
+                    let Some(rtt) = self.epoch.elapsed().checked_sub(sent) else {
+                        continue;
+                    };
+                    let sample = rtt / 2;
+                    self.latency = Some(
+                        self.latency
+                            .map_or(sample, |latency| (latency * 7 + sample) / 8),
+                    );
+                }
+                WsMessage::Text(text) => {
+                    let Ok(value) = serde_json::from_str::<serde_json::Value>(&text) else {
+                        continue;
+                    };
+                    // LiveSplit One answers each command in turn with a `success` or `error`; a
+                    // segment's name is the only string.
+                    if value.get("success").is_some() || value.get("error").is_some() {
+                        self.segment_answer(value["success"].is_string());
+                        continue;
+                    }
+                    let Ok(SyncRequest::Ping { client_time }) = serde_json::from_value(value)
+                    else {
+                        continue;
+                    };
+                    let reply = json!({
+                        "type": "pong",
+                        "client_time": client_time,
+                        "seq": self.last_update.map(|(seq, _, _)| seq),
+                        "time": self.last_update.map(|(_, time, _)| time.as_secs_f64()),
+                        "age": self.last_update.map(|(_, _, at)| at.elapsed().as_secs_f64()),
+                        "latency": self.latency.map(|latency| latency.as_secs_f64()),
+                        "monotonic": Timestamp::now().monotonic.as_secs_f64(),
+                    });
+                    self.send(&reply.to_string())?;
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Bring a LiveSplit One up to date, then send it the server commands for each update, as
+    /// the run's own splits or `category`'s.
+    fn serve_livesplit(
+        &mut self,
+        messages: &Subscribers<Message, RunState>,
+        options: Options,
+        category: Option<&str>,
+    ) -> Result<()> {
+        // The run state covers anything missed while no client was connected.
+        let (receiver, state, _) = messages.subscribe();
+        let splits = match category {
+            Some(category) => state
+                .categories
+                .get(category)
+                .cloned()
+                .unwrap_or_else(|| state.splits.fresh()),
+            None => state.splits,
+        };
+        // Before anything else, so the first answers are to the queries.
+        if let Some(expected) = options.split_count.filter(|_| category.is_none()) {
+            self.check_splits(expected)?;
+        }
+        for command in replay(&splits) {
+            self.send(&command)?;
+        }
+        self.serve(&receiver, |client, message| {
+            let Message::Update(mut update) = message else {
+                return Ok(());
+            };
+            if let Some(category) = category {
+                update = update.in_category(category);
+            }
+            client.track(&update);
+            let mut time = update.time;
+            if options.compensate_latency && client.timer_running {
+                time += client.latency.unwrap_or_default();
+            }
+            for command in commands(&update, time, options.split_on_teleport) {
+                client.send(&command)?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Send each item of an optional feed as JSON, starting with the ones it keeps for new
+    /// subscribers, or close the connection if there's no feed.
+    fn serve_feed<T: Serialize + Clone + Send + 'static>(
+        &mut self,
+        feed: Option<&Subscribers<T, Vec<T>>>,
+    ) -> Result<()> {
+        let Some(feed) = feed else {
+            return Ok(self.websocket.close(None)?);
+        };
+        let (receiver, kept, _) = feed.subscribe();
+        for item in kept {
+            self.send(&serde_json::to_string(&item)?)?;
+        }
+        self.serve(&receiver, |client, item| {
+            client.send(&serde_json::to_string(&item)?)
+        })
+    }
+
+    fn send(&mut self, text: &str) -> Result<()> {
+        self.websocket.write_message(if self.deflate {
+            WsMessage::Frame(compression::frame(text.as_bytes(), Data::Text))
+        } else {
+            WsMessage::Text(text.to_owned())
+        })?;
+        Ok(())
+    }
+
+    fn send_binary(&mut self, data: &[u8]) -> Result<()> {
+        self.websocket.write_message(if self.deflate {
+            WsMessage::Frame(compression::frame(data, Data::Binary))
+        } else {
+            WsMessage::Binary(data.to_vec())
+        })?;
+        Ok(())
+    }
+}
+
+/// The commands that bring a newly connected LiveSplit One up to date with a run in progress:
+/// start it, and make each split so far at the game time it was made.
+pub(crate) fn replay(splits: &Splits) -> Vec<String> {
+    if !splits.started() {
+        return Vec::new();
+    }
+    let mut commands = vec!["reset".to_owned(), "start".to_owned()];
+    for time in splits.times() {
+        match time {
+            Some(time) => {
+                commands.push(setgametime(*time));
+                commands.push("split".to_owned());
+            }
+            None => commands.push("skipsplit".to_owned()),
+        }
+    }
+    if splits.paused() {
+        commands.push("pause".to_owned());
+    }
+    commands
+}
+
+/// The commands that tell a LiveSplit One about `update`, with the game time given as `time`: the
+/// game time always comes first, so a split or start it carries happens at that time.
+pub(crate) fn commands(update: &Update, time: Duration, split_on_teleport: bool) -> Vec<String> {
+    let mut commands = vec![setgametime(time)];
+    commands.extend(
+        update
+            .event
+            .and_then(|event| command(event, split_on_teleport))
+            .map(str::to_owned),
+    );
+    commands
+}
+
+pub(crate) fn setgametime(time: Duration) -> String {
+    format!(
+        "setgametime {}.{:02}",
+        time.as_secs(),
+        time.subsec_nanos() / 10_000_000
+    )
+}
+
+/// The LiveSplit One server command corresponding to an event, if any.
+pub(crate) fn command(event: Event, split_on_teleport: bool) -> Option<&'static str> {
+    Some(match event {
+        Event::NewGame => "start",
+        Event::Verdigris
+        | Event::Vermilion
+        | Event::Victoria
+        | Event::Violet
+        | Event::Vitellary
+        | Event::IntermissionOne
+        | Event::IntermissionTwo
+        | Event::GameComplete
+        | Event::Custom(_)
+        | Event::Manual(Command::Split) => "split",
+        Event::Manual(Command::SkipSplit) => "skipsplit",
+        Event::Manual(Command::Undo) | Event::Correction => "unsplit",
+        Event::Reset | Event::Manual(Command::Reset) => "reset",
+        Event::Pause => "pause",
+        Event::Resume => "resume",
+        Event::Teleport if split_on_teleport => "split",
+        Event::Teleport
+        | Event::Warning(_)
+        | Event::TimeTrialComplete
+        | Event::GravitronAttempt
+        | Event::TimerDrift(_) => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vitellary_core::game::{Game, Mock};
+
+    /// An update at `seq` and game time `secs`, carrying `event`.
+    fn update(seq: u64, secs: u64, event: Option<Event>) -> Update {
+        let mut game = Game::with_backend(0, Box::new(Mock::new()));
+        let mut update = game.update().unwrap().unwrap();
+        update.seq = seq;
+        update.time = Duration::from_secs(secs);
+        update.event = event;
+        update
+    }
+
+    #[test]
+    fn game_time_comes_before_the_event() {
+        for (event, command) in [
+            (Event::NewGame, "start"),
+            (Event::Verdigris, "split"),
+            (Event::Manual(Command::SkipSplit), "skipsplit"),
+            (Event::Correction, "unsplit"),
+            (Event::Reset, "reset"),
+        ] {
+            let update = update(1, 12, Some(event));
+            assert_eq!(
+                commands(&update, update.time, false),
+                ["setgametime 12.00", command],
+                "{:?}",
+                event
+            );
+        }
+        let update = update(1, 12, None);
+        assert_eq!(commands(&update, update.time, false), ["setgametime 12.00"]);
+    }
+
+    #[test]
+    fn replay_makes_each_split_at_its_time() {
+        let mut state = RunState::default();
+        for (seq, secs, event) in [
+            (1, 0, Event::NewGame),
+            (2, 10, Event::Verdigris),
+            (3, 20, Event::Manual(Command::SkipSplit)),
+            (4, 30, Event::Vermilion),
+            (5, 35, Event::Victoria),
+            (6, 36, Event::Correction),
+            (7, 40, Event::Pause),
+        ] {
+            state.apply(&Message::Update(update(seq, secs, Some(event))), false);
+        }
+        assert_eq!(
+            replay(&state.splits),
+            [
+                "reset",
+                "start",
+                "setgametime 10.00",
+                "split",
+                "skipsplit",
+                "setgametime 30.00",
+                "split",
+                "pause",
+            ]
+        );
+    }
+
+    #[test]
+    fn replay_is_empty_after_a_reset() {
+        let mut state = RunState::default();
+        for (seq, event) in [
+            (1, Event::NewGame),
+            (2, Event::Verdigris),
+            (3, Event::Reset),
+        ] {
+            state.apply(&Message::Update(update(seq, seq, Some(event))), false);
+        }
+        assert!(replay(&state.splits).is_empty());
+    }
+
+    #[test]
+    fn slow_subscribers_get_every_event_in_order() {
+        let (sender, receiver) = crossbeam_channel::unbounded();
+        let feed = Subscribers::spawn(
+            receiver,
+            RunState::default(),
+            |state, message| {
+                state.apply(message, false);
+            },
+            Message::droppable,
+            Duration::ZERO,
+        );
+        let (subscriber, _, _) = feed.subscribe();
+        let events = [
+            Event::NewGame,
+            Event::Verdigris,
+            Event::Vermilion,
+            Event::Victoria,
+            Event::Violet,
+            Event::Vitellary,
+            Event::IntermissionOne,
+            Event::IntermissionTwo,
+            Event::GameComplete,
+        ];
+        let mut seq = 0;
+        for event in events {
+            for _ in 0..10 {
+                seq += 1;
+                sender
+                    .send(Message::Update(update(seq, seq, None)))
+                    .unwrap();
+            }
+            seq += 1;
+            sender
+                .send(Message::Update(update(seq, seq, Some(event))))
+                .unwrap();
+        }
+
+        // Only read once the feed has had to queue or drop most of them.
+        std::thread::sleep(POLL_INTERVAL * 4);
+        let mut got = Vec::new();
+        while let Ok(Message::Update(update)) = subscriber.recv_timeout(POLL_INTERVAL * 10) {
+            got.push(update);
+        }
+        assert!(got.len() < usize::try_from(seq).unwrap());
+        assert!(got.windows(2).all(|pair| pair[0].seq < pair[1].seq));
+        assert_eq!(
+            got.iter()
+                .filter_map(|update| update.event)
+                .collect::<Vec<_>>(),
+            events
+        );
+    }
+
+    #[test]
+    fn new_subscribers_start_from_the_state_before_their_first_item() {
+        let (sender, receiver) = crossbeam_channel::unbounded();
+        let feed = Subscribers::spawn(
+            receiver,
+            RunState::default(),
+            |state, message| {
+                state.apply(message, false);
+            },
+            Message::droppable,
+            Duration::ZERO,
+        );
+        sender
+            .send(Message::Update(update(1, 0, Some(Event::NewGame))))
+            .unwrap();
+        sender
+            .send(Message::Update(update(2, 10, Some(Event::Verdigris))))
+            .unwrap();
+        std::thread::sleep(POLL_INTERVAL * 4);
+        let (subscriber, state, _) = feed.subscribe();
+        sender
+            .send(Message::Update(update(3, 20, Some(Event::Vermilion))))
+            .unwrap();
+        assert_eq!(state.splits.times(), [Some(Duration::from_secs(10))]);
+        let Ok(Message::Update(next)) = subscriber.recv_timeout(POLL_INTERVAL * 10) else {
+            panic!("no update");
+        };
+        assert_eq!(next.seq, 3);
+    }
+
+    #[test]
+    fn split_check_counts_segments() {
+        let mut check = SplitCheck::new(3);
+        assert_eq!(check.answer(true), None);
+        assert_eq!(check.answer(true), None);
+        assert_eq!(check.answer(false), Some(SplitCount::Found(2)));
+
+        let mut check = SplitCheck::new(1);
+        assert_eq!(check.answer(true), None);
+        assert_eq!(check.answer(true), Some(SplitCount::More));
+    }
+
+    #[test]
+    fn split_check_gives_up_on_a_silent_client() {
+        let check = SplitCheck::new(3);
+        assert_eq!(check.expire(check.started), None);
+        assert_eq!(
+            check.expire(check.started + SPLIT_CHECK_TIMEOUT),
+            Some(SplitCount::Silent)
+        );
+    }
+}
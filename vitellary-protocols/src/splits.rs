@@ -0,0 +1,84 @@
+use crate::server;
+use std::time::Duration;
+use vitellary_core::game::Event;
+
+/// A run as a LiveSplit One timer following our events sees it, built up by applying the same
+/// server commands.
+#[derive(Debug, Clone, Default)]
+pub struct Splits {
+    started: bool,
+    paused: bool,
+    /// The game time of each split so far; skipped splits are `None`.
+    times: Vec<Option<Duration>>,
+}
+
+impl Splits {
+    /// Apply an event at `time`, returning the index of the split it made, if any.
+    pub fn apply(
+        &mut self,
+        event: Event,
+        time: Duration,
+        split_on_teleport: bool,
+    ) -> Option<usize> {
+        match server::command(event, split_on_teleport)? {
+            "start" => {
+                self.times.clear();
+                self.started = true;
+                self.paused = false;
+            }
+            "reset" => {
+                self.times.clear();
+                self.started = false;
+                self.paused = false;
+            }
+            "split" => {
+                self.times.push(Some(time));
+                return Some(self.times.len() - 1);
+            }
+            "skipsplit" => self.times.push(None),
+            "unsplit" => {
+                self.times.pop();
+            }
+            "pause" => self.paused = true,
+            "resume" => self.paused = false,
+            _ => {}
+        }
+        None
+    }
+
+    /// An empty copy, started and paused like this one, for a category first seen partway through
+    /// a run.
+    #[must_use]
+    pub fn fresh(&self) -> Splits {
+        Splits {
+            started: self.started,
+            paused: self.paused,
+            times: Vec::new(),
+        }
+    }
+
+    /// Whether the timer has been started and not reset.
+    pub fn started(&self) -> bool {
+        self.started
+    }
+
+    pub fn paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn times(&self) -> &[Option<Duration>] {
+        &self.times
+    }
+
+    /// The game time split `index` took: since the last split before it that wasn't skipped, or
+    /// since the start.
+    pub fn segment(&self, index: usize) -> Option<Duration> {
+        let time = (*self.times.get(index)?)?;
+        let previous = self.times[..index]
+            .iter()
+            .rev()
+            .find_map(|time| *time)
+            .unwrap_or_default();
+        Some(time.saturating_sub(previous))
+    }
+}
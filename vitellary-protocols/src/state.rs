@@ -0,0 +1,119 @@
+use crate::splits::Splits;
+use crossbeam_channel::Receiver;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use vitellary_core::game::{Gravitron, Health, Message, Summary, Target, TimeTrial, Update};
+
+/// Everything the outputs know about the run, built up from the splitter's messages, so outputs
+/// that start (or reconnect) partway through can catch up.
+#[derive(Debug, Clone, Default)]
+pub struct RunState {
+    /// The pid of the VVVVVV process we're attached to.
+    pub pid: Option<i32>,
+    /// The executable of that process.
+    pub target: Option<Target>,
+    /// The latest update.
+    pub update: Option<Update>,
+    /// The run's splits, as a timer following our events sees them.
+    pub splits: Splits,
+    /// The splits of each category followed alongside the run, as a timer following its events
+    /// sees them.
+    pub categories: BTreeMap<String, Splits>,
+    /// The summary of the last completed run.
+    pub summary: Option<Summary>,
+    /// The results of the last completed time trial.
+    pub time_trial: Option<TimeTrial>,
+    /// The last Super Gravitron attempt.
+    pub gravitron: Option<Gravitron>,
+    /// The latest health status from the watchdog.
+    pub health: Option<Health>,
+}
+
+/// A run state shared between outputs, kept up to date by [`spawn`].
+pub type SharedState = Arc<Mutex<RunState>>;
+
+impl RunState {
+    /// Apply a message, returning the index of the split it made, if any.
+    pub fn apply(&mut self, message: &Message, split_on_teleport: bool) -> Option<usize> {
+        match message {
+            Message::Attached { pid, target, .. } => {
+                self.pid = Some(*pid);
+                self.target = Some(target.clone());
+            }
+            Message::Detached { .. } => {
+                self.pid = None;
+                self.target = None;
+            }
+            Message::Update(update) => {
+                self.update = Some(update.clone());
+                for category in update.categories.keys() {
+                    if !self.categories.contains_key(category) {
+                        self.categories
+                            .insert(category.clone(), self.splits.fresh());
+                    }
+                }
+                for (category, splits) in &mut self.categories {
+                    if let Some(event) = update.category_event(category) {
+                        splits.apply(event, update.time, false);
+                    }
+                }
+                return update
+                    .event
+                    .and_then(|event| self.splits.apply(event, update.time, split_on_teleport));
+            }
+            Message::Summary(summary) => self.summary = Some(summary.clone()),
+            Message::TimeTrial(trial) => self.time_trial = Some(trial.clone()),
+            Message::Gravitron(attempt) => self.gravitron = Some(attempt.clone()),
+            Message::Health(health) => self.health = Some(health.clone()),
+        }
+        None
+    }
+
+    /// The state as a JSON object, for status pages.
+    pub fn status(&self) -> Value {
+        json!({
+            "pid": self.pid,
+            "target": self.target,
+            "update": self.update,
+            "splits": self
+                .splits
+                .times()
+                .iter()
+                .map(|time| time.map(|time| time.as_secs_f64()))
+                .collect::<Vec<_>>(),
+            "started": self.splits.started(),
+            "paused": self.splits.paused(),
+            "categories": self
+                .categories
+                .iter()
+                .map(|(category, splits)| {
+                    let times = splits
+                        .times()
+                        .iter()
+                        .map(|time| time.map(|time| time.as_secs_f64()))
+                        .collect::<Vec<_>>();
+                    (category.clone(), times)
+                })
+                .collect::<BTreeMap<_, _>>(),
+            "summary": self.summary,
+            "time_trial": self.time_trial,
+            "gravitron": self.gravitron,
+            "health": self.health,
+        })
+    }
+}
+
+/// Keep a shared run state up to date with the messages from `receiver`.
+pub fn spawn(split_on_teleport: bool, receiver: Receiver<Message>) -> SharedState {
+    let state = SharedState::default();
+    std::thread::spawn({
+        let state = Arc::clone(&state);
+        move || {
+            for message in receiver {
+                state.lock().unwrap().apply(&message, split_on_teleport);
+            }
+        }
+    });
+    state
+}
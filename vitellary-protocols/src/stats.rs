@@ -0,0 +1,233 @@
+use crate::history::{self, Line};
+use anyhow::Result;
+use serde::Serialize;
+use std::fmt::Write as _;
+use std::path::Path;
+use std::str::FromStr;
+use vitellary_core::game::{Event, ResetCause};
+
+const DAY: u64 = 24 * 60 * 60;
+
+/// How to print a history report.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Text,
+    Json,
+    Csv,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Format, String> {
+        match s {
+            "text" => Ok(Format::Text),
+            "json" => Ok(Format::Json),
+            "csv" => Ok(Format::Csv),
+            _ => Err(format!(
+                "unknown stats format {:?} (expected text, json or csv)",
+                s
+            )),
+        }
+    }
+}
+
+/// Statistics over every session in a history file.
+#[derive(Debug, Default, Serialize)]
+pub struct Report {
+    /// Practice attempts at any segment.
+    pub attempts: u32,
+    /// Practice attempts started today (UTC).
+    pub attempts_today: u32,
+    /// Each segment practiced, in the order they were first practiced.
+    pub segments: Vec<Segment>,
+    /// Super Gravitron attempts.
+    pub gravitron_attempts: u32,
+    /// The longest Super Gravitron survival, in seconds.
+    pub gravitron_best: Option<f64>,
+    /// Where full runs reset and why, most common first.
+    pub run_resets: Vec<RunResets>,
+}
+
+/// How many full runs reset after the same split for the same reason.
+#[derive(Debug, Serialize)]
+pub struct RunResets {
+    /// The last split before the reset, or `None` for the first segment.
+    pub after: Option<Event>,
+    pub cause: ResetCause,
+    pub count: u32,
+}
+
+/// Statistics for one segment. Times are in seconds.
+#[derive(Debug, Serialize)]
+pub struct Segment {
+    pub segment: Event,
+    pub attempts: u32,
+    /// Attempts abandoned by resetting or starting a new game.
+    pub resets: u32,
+    pub average: Option<f64>,
+    pub best: Option<f64>,
+    /// Each attempt that beat every one before it.
+    pub personal_bests: Vec<PersonalBest>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct PersonalBest {
+    /// When the attempt started, in seconds since the Unix epoch.
+    pub started: u64,
+    pub time: f64,
+}
+
+impl Segment {
+    fn new(segment: Event) -> Segment {
+        Segment {
+            segment,
+            attempts: 0,
+            resets: 0,
+            average: None,
+            best: None,
+            personal_bests: Vec::new(),
+        }
+    }
+}
+
+/// Aggregate the history file at `path`.
+pub fn report(path: &Path) -> Result<Report> {
+    let today = history::now() / DAY;
+    let mut report = Report::default();
+    let mut totals: Vec<(f64, u32)> = Vec::new();
+    for line in history::read(path)? {
+        let attempt = match line {
+            Line::Practice(attempt) => attempt,
+            Line::Gravitron(attempt) => {
+                report.gravitron_attempts += 1;
+                report.gravitron_best = Some(
+                    report
+                        .gravitron_best
+                        .map_or(attempt.gravitron, |best| best.max(attempt.gravitron)),
+                );
+                continue;
+            }
+            Line::Reset(reset) => {
+                match report
+                    .run_resets
+                    .iter_mut()
+                    .find(|resets| resets.after == reset.after && resets.cause == reset.reset)
+                {
+                    Some(resets) => resets.count += 1,
+                    None => report.run_resets.push(RunResets {
+                        after: reset.after,
+                        cause: reset.reset,
+                        count: 1,
+                    }),
+                }
+                continue;
+            }
+        };
+        report.attempts += 1;
+        if attempt.started / DAY == today {
+            report.attempts_today += 1;
+        }
+        let index = report
+            .segments
+            .iter()
+            .position(|segment| segment.segment == attempt.segment)
+            .unwrap_or_else(|| {
+                report.segments.push(Segment::new(attempt.segment));
+                totals.push((0.0, 0));
+                report.segments.len() - 1
+            });
+        let segment = &mut report.segments[index];
+        segment.attempts += 1;
+        let Some(time) = attempt.time else {
+            segment.resets += 1;
+            continue;
+        };
+        let (total, completed) = &mut totals[index];
+        *total += time;
+        *completed += 1;
+        segment.average = Some(*total / f64::from(*completed));
+        if segment.best.is_none_or(|best| time < best) {
+            segment.best = Some(time);
+            segment.personal_bests.push(PersonalBest {
+                started: attempt.started,
+                time,
+            });
+        }
+    }
+    report
+        .run_resets
+        .sort_by_key(|resets| std::cmp::Reverse(resets.count));
+    Ok(report)
+}
+
+impl Report {
+    /// The report as `format`. CSV has the personal best progression of every segment, for
+    /// charting.
+    pub fn render(&self, format: Format) -> Result<String> {
+        Ok(match format {
+            Format::Text => self.text(),
+            Format::Json => serde_json::to_string_pretty(self)? + "\n",
+            Format::Csv => {
+                let mut csv = "segment,started,time\n".to_owned();
+                for segment in &self.segments {
+                    for pb in &segment.personal_bests {
+                        writeln!(csv, "{:?},{},{:.3}", segment.segment, pb.started, pb.time)?;
+                    }
+                }
+                csv
+            }
+        })
+    }
+
+    fn text(&self) -> String {
+        let secs =
+            |time: Option<f64>| time.map_or_else(|| "-".to_owned(), |t| format!("{:.2}s", t));
+        let mut text = String::new();
+        let _ = writeln!(
+            text,
+            "{} practice attempts, {} today",
+            self.attempts, self.attempts_today
+        );
+        let resets: u32 = self.segments.iter().map(|segment| segment.resets).sum();
+        for segment in &self.segments {
+            let _ = writeln!(
+                text,
+                "{:?}: {} attempts, {} resets ({:.0}% of all resets), average {}, best {}, \
+                 {} personal bests",
+                segment.segment,
+                segment.attempts,
+                segment.resets,
+                if resets > 0 {
+                    f64::from(segment.resets) / f64::from(resets) * 100.0
+                } else {
+                    0.0
+                },
+                secs(segment.average),
+                secs(segment.best),
+                segment.personal_bests.len()
+            );
+        }
+        if self.gravitron_attempts > 0 {
+            let _ = writeln!(
+                text,
+                "super gravitron: {} attempts, best {}",
+                self.gravitron_attempts,
+                secs(self.gravitron_best)
+            );
+        }
+        for resets in &self.run_resets {
+            let _ = writeln!(
+                text,
+                "runs reset {} ({:?}): {}",
+                resets.after.map_or_else(
+                    || "in the first segment".to_owned(),
+                    |event| format!("after {:?}", event)
+                ),
+                resets.cause,
+                resets.count
+            );
+        }
+        text
+    }
+}
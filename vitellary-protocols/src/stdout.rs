@@ -0,0 +1,21 @@
+use crossbeam_channel::Receiver;
+use std::io::Write;
+use std::thread::JoinHandle;
+use vitellary_core::game::Message;
+
+/// Print every message to stdout as a JSON object on its own line, for piping into other tools.
+pub fn spawn(receiver: Receiver<Message>) -> JoinHandle<()> {
+    std::thread::spawn(move || {
+        let mut stdout = std::io::stdout().lock();
+        for message in receiver {
+            let result = serde_json::to_string(&message)
+                .map_err(std::io::Error::from)
+                .and_then(|line| writeln!(stdout, "{}", line))
+                .and_then(|()| stdout.flush());
+            if let Err(err) = result {
+                log::warn!("failed to write to stdout: {}", err);
+                return;
+            }
+        }
+    })
+}
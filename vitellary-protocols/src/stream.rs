@@ -0,0 +1,129 @@
+use crossbeam_channel::{Receiver, RecvTimeoutError};
+use std::io::{self, Read, Write};
+use std::net::TcpStream;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+/// How much of stdin the reader thread reads at a time.
+const CHUNK: usize = 4096;
+
+/// A connection a client is served over.
+pub(crate) enum Stream {
+    Tcp(TcpStream),
+    /// Stdin and stdout, for a client that connected to whatever started us (inetd, systemd with
+    /// `Accept=yes`, or SSH).
+    Stdio(Stdio),
+}
+
+impl Stream {
+    /// Make reads give up with [`io::ErrorKind::WouldBlock`] or [`io::ErrorKind::TimedOut`] after
+    /// `timeout`, or never if `None`.
+    pub(crate) fn set_read_timeout(&mut self, timeout: Option<Duration>) -> io::Result<()> {
+        match self {
+            Stream::Tcp(stream) => stream.set_read_timeout(timeout),
+            Stream::Stdio(stdio) => {
+                stdio.timeout = timeout;
+                Ok(())
+            }
+        }
+    }
+
+    /// Make writes give up after `timeout`, where that's possible. Stdout can't time out, but
+    /// whatever holds its other end is in charge of hanging up.
+    pub(crate) fn set_write_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        match self {
+            Stream::Tcp(stream) => stream.set_write_timeout(timeout),
+            Stream::Stdio(_) => Ok(()),
+        }
+    }
+}
+
+impl Read for Stream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Stream::Tcp(stream) => stream.read(buf),
+            Stream::Stdio(stdio) => stdio.read(buf),
+        }
+    }
+}
+
+impl Write for Stream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Stream::Tcp(stream) => stream.write(buf),
+            Stream::Stdio(_) => io::stdout().lock().write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Stream::Tcp(stream) => stream.flush(),
+            Stream::Stdio(_) => io::stdout().lock().flush(),
+        }
+    }
+}
+
+/// Stdin, read on a thread of its own so reads can time out like a socket's.
+pub(crate) struct Stdio {
+    chunks: Receiver<Vec<u8>>,
+    /// What's left of the last chunk.
+    pending: Vec<u8>,
+    timeout: Option<Duration>,
+}
+
+impl Stdio {
+    /// Start reading stdin. There's only one, so every `Stdio` shares the same reader thread,
+    /// which stops at the end of stdin.
+    pub(crate) fn new() -> Stdio {
+        static CHUNKS: OnceLock<Receiver<Vec<u8>>> = OnceLock::new();
+        let chunks = CHUNKS.get_or_init(|| {
+            let (sender, receiver) = crossbeam_channel::bounded(16);
+            std::thread::spawn(move || {
+                let mut stdin = io::stdin().lock();
+                let mut buf = [0; CHUNK];
+                loop {
+                    match stdin.read(&mut buf) {
+                        Ok(0) => return,
+                        Ok(n) => {
+                            if sender.send(buf[..n].to_vec()).is_err() {
+                                return;
+                            }
+                        }
+                        Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                        Err(err) => {
+                            log::warn!("failed to read stdin: {}", err);
+                            return;
+                        }
+                    }
+                }
+            });
+            receiver
+        });
+        Stdio {
+            chunks: chunks.clone(),
+            pending: Vec::new(),
+            timeout: None,
+        }
+    }
+
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.pending.is_empty() {
+            let chunk = match self.timeout {
+                Some(timeout) => self.chunks.recv_timeout(timeout),
+                None => self
+                    .chunks
+                    .recv()
+                    .map_err(|_| RecvTimeoutError::Disconnected),
+            };
+            self.pending = match chunk {
+                Ok(chunk) => chunk,
+                Err(RecvTimeoutError::Timeout) => return Err(io::ErrorKind::WouldBlock.into()),
+                Err(RecvTimeoutError::Disconnected) => return Ok(0),
+            };
+        }
+        let n = buf.len().min(self.pending.len());
+        buf[..n].copy_from_slice(&self.pending[..n]);
+        self.pending.drain(..n);
+        Ok(n)
+    }
+}
@@ -0,0 +1,55 @@
+use anyhow::{Context, Result};
+use crossbeam_channel::Receiver;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+use std::thread::JoinHandle;
+use vitellary_core::game::Message;
+
+const HEADER: &str = "timestamp,seq,room_x,room_y,gamestate,state,gravitycontrol,deathcounts,\
+                      frames,time,event,monotonic";
+
+/// Append every state read from the game to the CSV file at `path`, one row per update, for
+/// analysis in a spreadsheet. `timestamp` is when the game was read, in seconds since the Unix
+/// epoch, and `monotonic` the same by vitellary's monotonic clock; `frames` is the game timer's
+/// frame counter, and `time` the game time in seconds.
+pub fn spawn(path: &Path, receiver: Receiver<Message>) -> Result<JoinHandle<()>> {
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("failed to open {}", path.display()))?;
+    let new = file.metadata()?.len() == 0;
+    if new {
+        writeln!(file, "{}", HEADER)?;
+    }
+
+    Ok(std::thread::spawn(move || {
+        for message in receiver {
+            let Message::Update(update) = message else {
+                continue;
+            };
+            let result = writeln!(
+                file,
+                "{:.3},{},{},{},{},{},{},{},{},{:.3},{},{:.3}",
+                update.clock.utc.as_secs_f64(),
+                update.seq,
+                update.state.room.0,
+                update.state.room.1,
+                update.state.gamestate,
+                update.state.state,
+                update.state.gravitycontrol,
+                update.state.deathcounts,
+                update.timer.frames,
+                update.time.as_secs_f64(),
+                update
+                    .event
+                    .map_or_else(String::new, |event| format!("{:?}", event)),
+                update.clock.monotonic.as_secs_f64()
+            );
+            if let Err(err) = result {
+                log::warn!("failed to write state trace: {}", err);
+            }
+        }
+    }))
+}
@@ -0,0 +1,58 @@
+use anyhow::Result;
+use crossbeam_channel::Receiver;
+use std::path::Path;
+use std::thread::JoinHandle;
+use vitellary_core::game::Message;
+
+/// Credentials for creating stream markers, read from a JSON file with `client_id`, `token`
+/// (a user access token with the `channel:manage:broadcast` scope), and `user_id` keys.
+#[cfg(feature = "twitch")]
+#[derive(serde::Deserialize)]
+struct Credentials {
+    client_id: String,
+    token: String,
+    user_id: String,
+}
+
+/// Create a Twitch stream marker for every event, labelled with the event and in-game time, so
+/// attempts can be found in the VOD later.
+#[cfg(feature = "twitch")]
+pub fn spawn(credentials: &Path, receiver: Receiver<Message>) -> Result<JoinHandle<()>> {
+    use anyhow::Context;
+
+    let credentials: Credentials = serde_json::from_slice(
+        &std::fs::read(credentials)
+            .with_context(|| format!("failed to read {}", credentials.display()))?,
+    )
+    .context("failed to parse Twitch credentials")?;
+
+    Ok(std::thread::spawn(move || {
+        for message in receiver {
+            let Message::Update(update) = message else {
+                continue;
+            };
+            let Some(event) = update.event else {
+                continue;
+            };
+            let description = format!("{:?} @ {}", event, vitellary_core::format_time(update.time));
+            let result = ureq::post("https://api.twitch.tv/helix/streams/markers")
+                .set("Client-Id", &credentials.client_id)
+                .set("Authorization", &format!("Bearer {}", credentials.token))
+                .send_json(serde_json::json!({
+                    "user_id": credentials.user_id,
+                    "description": description,
+                }));
+            match result {
+                Ok(_) => log::debug!("created stream marker {:?}", description),
+                Err(err) => log::warn!("failed to create stream marker: {}", err),
+            }
+        }
+    }))
+}
+
+#[cfg(not(feature = "twitch"))]
+pub fn spawn(_credentials: &Path, _receiver: Receiver<Message>) -> Result<JoinHandle<()>> {
+    anyhow::bail!(
+        "this build of vitellary does not support Twitch markers (enable the `twitch` feature)"
+    )
+}
@@ -0,0 +1,624 @@
+use anyhow::{bail, Context, Result};
+use crossbeam_channel::Receiver;
+use serde::{Deserialize, Serialize};
+use serde_json::value::RawValue;
+use sha2::{Digest, Sha256};
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::str::FromStr;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant, SystemTime};
+use uuid::Uuid;
+use vitellary_core::format_time;
+use vitellary_core::game::{
+    Backend, Event, Message, Settings, State, Target, Timer, Timestamp, Update,
+};
+
+const VERSION: u32 = 1;
+
+/// How often to record a sample when nothing changes, so the timer's progress can be checked
+/// against the wall clock.
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How far the game's timer may get ahead of the wall clock between two samples before it's
+/// reported, allowing for the sink running behind.
+const DRIFT_TOLERANCE: Duration = Duration::from_secs(1);
+
+/// One line of a verification log: an entry, and the SHA-256 of the previous line's hash followed
+/// by the entry's JSON exactly as written.
+///
+/// The chain catches a log that was edited after the fact, but not one that was forged from
+/// scratch, since anyone can compute the hashes. It's meant to be read alongside the VOD.
+#[derive(Serialize, Deserialize)]
+struct Line<'a> {
+    #[serde(borrow)]
+    entry: &'a RawValue,
+    hash: String,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum Entry {
+    Header {
+        version: u32,
+        /// The `--frame-rate` the log was recorded with.
+        frame_rate: u32,
+        /// When recording started, in seconds since the Unix epoch.
+        started: u64,
+    },
+    Attached {
+        pid: i32,
+        address: usize,
+        /// The executable, so moderators can check the game version.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        target: Option<Target>,
+    },
+    Sample {
+        seq: u64,
+        run_id: Uuid,
+        /// Seconds since recording started, by the wall clock.
+        elapsed: f64,
+        /// When the game was read, in seconds since the Unix epoch.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        utc: Option<f64>,
+        /// The in-game time in seconds, as computed by the splitter.
+        time: f64,
+        timer: Timer<u32>,
+        event: Option<Event>,
+        state: State,
+        /// The game's settings, recorded with each new game.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        settings: Option<Settings>,
+    },
+    /// The splitter started counting the game's timer at another frame rate, having seen it count
+    /// faster than the last one.
+    FrameRate {
+        frame_rate: u32,
+    },
+    Detached {
+        reason: String,
+    },
+}
+
+fn chain(prev: &str, entry: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(prev);
+    hasher.update(entry);
+    format!("{:x}", hasher.finalize())
+}
+
+/// Record a verification log to `path`, which must not exist yet: a sample of the game's state
+/// whenever it changes, an event fires, or a second passes, hash-chained so moderators can check
+/// it with `verify`.
+pub fn spawn(path: &Path, frame_rate: u32, receiver: Receiver<Message>) -> Result<JoinHandle<()>> {
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(path)
+        .with_context(|| format!("failed to create {}", path.display()))?;
+    let started = Instant::now();
+    let mut hash = String::new();
+    let mut write = move |entry: &Entry| -> Result<()> {
+        let entry = serde_json::to_string(entry)?;
+        hash = chain(&hash, &entry);
+        let line = serde_json::to_string(&Line {
+            entry: &RawValue::from_string(entry)?,
+            hash: hash.clone(),
+        })?;
+        writeln!(file, "{}", line)?;
+        Ok(())
+    };
+    write(&Entry::Header {
+        version: VERSION,
+        frame_rate,
+        started: SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs()),
+    })?;
+
+    Ok(std::thread::spawn(move || {
+        let mut last: Option<(Update, Instant)> = None;
+        let mut rate = frame_rate;
+        for message in receiver {
+            let entry = match message {
+                Message::Attached {
+                    pid,
+                    address,
+                    target,
+                } => Entry::Attached {
+                    pid,
+                    address,
+                    target: Some(target),
+                },
+                Message::Detached { reason, .. } => Entry::Detached { reason },
+                Message::Update(update) => {
+                    if let Some(frame) = update.frame.filter(|frame| frame.rate != rate) {
+                        rate = frame.rate;
+                        if let Err(err) = write(&Entry::FrameRate { frame_rate: rate }) {
+                            log::warn!("failed to write verification log: {:#}", err);
+                        }
+                    }
+                    let now = Instant::now();
+                    if let Some((prev, at)) = &last {
+                        if update.event.is_none()
+                            && update.state == prev.state
+                            && update.run_id == prev.run_id
+                            && now.duration_since(*at) < SAMPLE_INTERVAL
+                        {
+                            continue;
+                        }
+                    }
+                    let entry = Entry::Sample {
+                        seq: update.seq,
+                        run_id: update.run_id,
+                        elapsed: now.duration_since(started).as_secs_f64(),
+                        utc: Some(update.clock.utc.as_secs_f64()),
+                        time: update.time.as_secs_f64(),
+                        timer: update.timer,
+                        event: update.event,
+                        state: update.state.clone(),
+                        settings: (update.event == Some(Event::NewGame))
+                            .then(|| update.settings.clone()),
+                    };
+                    last = Some((update, now));
+                    entry
+                }
+                Message::Summary(_)
+                | Message::TimeTrial(_)
+                | Message::Gravitron(_)
+                | Message::Health(_) => continue,
+            };
+            if let Err(err) = write(&entry) {
+                log::warn!("failed to write verification log: {:#}", err);
+            }
+        }
+    }))
+}
+
+/// What `verify` found in a log whose chain is intact.
+#[derive(Debug, Default)]
+pub struct Report {
+    pub entries: usize,
+    /// Completed runs and their final times, recomputed from the game's timer.
+    pub completed: Vec<(Uuid, Duration)>,
+    /// Things a moderator should look at, even though the chain is intact.
+    pub warnings: Vec<String>,
+}
+
+/// Check the hash chain of the verification log at `path`, failing at the first broken link, and
+/// recompute the final time of every completed run from the recorded timer values.
+pub fn verify(path: &Path) -> Result<Report> {
+    let file = File::open(path).with_context(|| format!("failed to open {}", path.display()))?;
+    let mut report = Report::default();
+    let mut hash = String::new();
+    let mut frame_rate = None;
+    // The last sample of each run: its seq, wall clock and game time.
+    let mut runs: HashMap<Uuid, (u64, f64, Duration)> = HashMap::new();
+    for (i, line) in BufReader::new(file).lines().enumerate() {
+        let n = i + 1;
+        let line = line?;
+        let line: Line =
+            serde_json::from_str(&line).with_context(|| format!("line {}: not a log line", n))?;
+        let expected = chain(&hash, line.entry.get());
+        if line.hash != expected {
+            bail!(
+                "line {}: hash mismatch; the log was modified at or before this line",
+                n
+            );
+        }
+        hash = line.hash;
+        report.entries += 1;
+
+        match serde_json::from_str(line.entry.get())
+            .with_context(|| format!("line {}: unknown entry", n))?
+        {
+            Entry::Header {
+                version,
+                frame_rate: rate,
+                ..
+            } => {
+                if n != 1 {
+                    bail!("line {}: header in the middle of the log", n);
+                }
+                if version != VERSION {
+                    bail!("line {}: unsupported log version {}", n, version);
+                }
+                if rate == 0 {
+                    bail!("line {}: frame rate of 0", n);
+                }
+                frame_rate = Some(rate);
+            }
+            _ if frame_rate.is_none() => bail!("line {}: log doesn't start with a header", n),
+            Entry::FrameRate { frame_rate: rate } => {
+                if rate == 0 {
+                    bail!("line {}: frame rate of 0", n);
+                }
+                frame_rate = Some(rate);
+            }
+            Entry::Attached { .. } | Entry::Detached { .. } => {}
+            Entry::Sample {
+                seq,
+                run_id,
+                elapsed,
+                time,
+                timer,
+                event,
+                ..
+            } => {
+                let frame_rate = frame_rate.unwrap_or_default();
+                let igt = timer.duration(frame_rate);
+                check_timer(&mut report, n, frame_rate, &timer, time, event);
+                if let Some((last_seq, last_elapsed, last_igt)) = runs.get(&run_id) {
+                    if seq <= *last_seq {
+                        report
+                            .warnings
+                            .push(format!("line {}: sequence number went backwards", n));
+                    }
+                    let wall =
+                        Duration::try_from_secs_f64(elapsed - last_elapsed).unwrap_or_default();
+                    match igt.checked_sub(*last_igt) {
+                        None => report.warnings.push(format!(
+                            "line {}: game timer went backwards ({} -> {})",
+                            n,
+                            format_time(*last_igt),
+                            format_time(igt)
+                        )),
+                        Some(gained) if gained > wall.saturating_add(DRIFT_TOLERANCE) => {
+                            report.warnings.push(format!(
+                                "line {}: game timer gained {} in {} of wall-clock time",
+                                n,
+                                format_time(gained),
+                                format_time(wall)
+                            ));
+                        }
+                        Some(_) => {}
+                    }
+                }
+                runs.insert(run_id, (seq, elapsed, igt));
+                if event == Some(Event::GameComplete) {
+                    report.completed.push((run_id, igt));
+                }
+            }
+        }
+    }
+    if report.entries == 0 {
+        bail!("{} is empty", path.display());
+    }
+    Ok(report)
+}
+
+/// Check a sample's reported `time` against the game `timer`, and report any timer drift the
+/// game noticed while it was recorded.
+fn check_timer(
+    report: &mut Report,
+    n: usize,
+    frame_rate: u32,
+    timer: &Timer<u32>,
+    time: f64,
+    event: Option<Event>,
+) {
+    if timer.frames >= frame_rate {
+        report.warnings.push(format!(
+            "line {}: frame counter at {}, past the frame rate of {}",
+            n, timer.frames, frame_rate
+        ));
+    }
+    let igt = timer.duration(frame_rate);
+    if event != Some(Event::NewGame)
+        && (igt.as_secs_f64() - time).abs() > 1.0 / f64::from(frame_rate)
+    {
+        report.warnings.push(format!(
+            "line {}: reported time {:.2}s doesn't match the game timer ({}) at {} frames per \
+             second",
+            n,
+            time,
+            format_time(igt),
+            frame_rate
+        ));
+    }
+    if let Some(Event::TimerDrift(percent)) = event {
+        report.warnings.push(format!(
+            "line {}: game timer ran at {}% of real time during play",
+            n, percent
+        ));
+    }
+}
+
+/// A completed run read back from a verification log.
+#[derive(Debug, Clone)]
+pub struct RecordedRun {
+    pub run_id: Uuid,
+    pub time: Duration,
+    /// Each room entered, with the game time it was entered at.
+    pub rooms: Vec<((u32, u32), Duration)>,
+}
+
+/// The fastest completed run in the verification log at `path`, which must pass `verify`.
+pub fn fastest_run(path: &Path) -> Result<RecordedRun> {
+    let report = verify(path)?;
+    let Some(&(fastest, time)) = report.completed.iter().min_by_key(|(_, time)| *time) else {
+        bail!("{} has no completed runs", path.display());
+    };
+    let file = File::open(path).with_context(|| format!("failed to open {}", path.display()))?;
+    let mut frame_rate = 0;
+    let mut rooms: Vec<((u32, u32), Duration)> = Vec::new();
+    for line in BufReader::new(file).lines() {
+        let line = line?;
+        let line: Line = serde_json::from_str(&line)?;
+        match serde_json::from_str(line.entry.get())? {
+            Entry::Header {
+                frame_rate: rate, ..
+            }
+            | Entry::FrameRate { frame_rate: rate } => frame_rate = rate,
+            Entry::Sample {
+                run_id,
+                timer,
+                event,
+                state,
+                ..
+            } if run_id == fastest => {
+                if event == Some(Event::NewGame) {
+                    rooms.clear();
+                }
+                if rooms.last().is_none_or(|(room, _)| *room != state.room) {
+                    rooms.push((state.room, timer.duration(frame_rate)));
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(RecordedRun {
+        run_id: fastest,
+        time,
+        rooms,
+    })
+}
+
+/// How fast to play back a verification log.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Speed {
+    /// One sample per read, as fast as the splitter can take them.
+    Max,
+    /// A multiple of real time.
+    Factor(f64),
+}
+
+impl FromStr for Speed {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Speed, String> {
+        if s == "max" {
+            return Ok(Speed::Max);
+        }
+        match s.parse() {
+            Ok(factor) if factor > 0.0 => Ok(Speed::Factor(factor)),
+            _ => Err(format!(
+                "invalid replay speed {:?} (expected max or a positive multiple of real time)",
+                s
+            )),
+        }
+    }
+}
+
+/// The samples in a verification log played back as a game, so splitter changes can be tried
+/// against recorded runs. The hash chain isn't checked.
+///
+/// Rules that go by the wall clock, like the reset grace period, see the replay at its own speed.
+pub struct Replay {
+    /// Each sample's wall clock time in seconds since recording started, and the game's state.
+    samples: Vec<(f64, State, Timer<u32>)>,
+    /// When each sample was recorded.
+    clocks: Vec<Timestamp>,
+    speed: Speed,
+    from: f64,
+    /// The sample last read, and when playback started.
+    position: Cell<Option<(usize, Instant)>>,
+    finished: Cell<bool>,
+}
+
+impl Replay {
+    /// Load the log at `path`, to be played back at `speed` from `from` seconds into the recording.
+    pub fn open(path: &Path, speed: Speed, from: f64) -> Result<Replay> {
+        let file =
+            File::open(path).with_context(|| format!("failed to open {}", path.display()))?;
+        let mut samples = Vec::new();
+        let mut clocks = Vec::new();
+        let mut recording_started = 0;
+        for (i, line) in BufReader::new(file).lines().enumerate() {
+            let line = line?;
+            let line: Line = serde_json::from_str(&line)
+                .with_context(|| format!("line {}: not a log line", i + 1))?;
+            match serde_json::from_str(line.entry.get())
+                .with_context(|| format!("line {}: unknown entry", i + 1))?
+            {
+                Entry::Header { started, .. } => recording_started = started,
+                Entry::Sample {
+                    elapsed,
+                    utc,
+                    timer,
+                    state,
+                    ..
+                } => {
+                    // Logs from before samples had their own time only have when recording
+                    // started, to the second.
+                    let secs = |secs: f64| Duration::try_from_secs_f64(secs).unwrap_or_default();
+                    clocks.push(Timestamp {
+                        utc: utc.map_or_else(
+                            || Duration::from_secs(recording_started) + secs(elapsed),
+                            secs,
+                        ),
+                        monotonic: secs(elapsed),
+                    });
+                    samples.push((elapsed, state, timer));
+                }
+                Entry::Attached { .. } | Entry::FrameRate { .. } | Entry::Detached { .. } => {}
+            }
+        }
+        if !samples.iter().any(|(elapsed, _, _)| *elapsed >= from) {
+            bail!("{} has no samples from {:.2}s on", path.display(), from);
+        }
+        log::info!(
+            "replaying {} samples from {} at {}",
+            samples.len(),
+            path.display(),
+            match speed {
+                Speed::Max => "full speed".to_owned(),
+                Speed::Factor(factor) => format!("{}x", factor),
+            }
+        );
+        Ok(Replay {
+            samples,
+            clocks,
+            speed,
+            from,
+            position: Cell::new(None),
+            finished: Cell::new(false),
+        })
+    }
+
+    /// The next sample to play.
+    fn next(&self) -> Option<usize> {
+        let Some((index, started)) = self.position.get() else {
+            let first = self
+                .samples
+                .iter()
+                .position(|(elapsed, _, _)| *elapsed >= self.from)?;
+            self.position.set(Some((first, Instant::now())));
+            return Some(first);
+        };
+        let next = match self.speed {
+            Speed::Max => index + 1,
+            Speed::Factor(factor) => {
+                let now = self.from + started.elapsed().as_secs_f64() * factor;
+                let mut next = index;
+                while self
+                    .samples
+                    .get(next + 1)
+                    .is_some_and(|(elapsed, _, _)| *elapsed <= now)
+                {
+                    next += 1;
+                }
+                // Give the splitter a second with the last sample before ending.
+                if next + 1 == self.samples.len() && now > self.samples[next].0 + 1.0 {
+                    return None;
+                }
+                next
+            }
+        };
+        if next >= self.samples.len() {
+            return None;
+        }
+        self.position.set(Some((next, started)));
+        Some(next)
+    }
+}
+
+impl Backend for Replay {
+    fn address(&self) -> usize {
+        0
+    }
+
+    fn read_game_object(&self) -> Result<(State, Timer<u32>)> {
+        let Some(index) = self.next() else {
+            self.finished.set(true);
+            bail!("end of replay");
+        };
+        let (_, state, timer) = &self.samples[index];
+        Ok((state.clone(), *timer))
+    }
+
+    fn exited(&self) -> bool {
+        self.finished.get()
+    }
+
+    fn recorded_at(&self) -> Option<Timestamp> {
+        let (index, _) = self.position.get()?;
+        self.clocks.get(index).copied()
+    }
+
+    fn paced(&self) -> bool {
+        self.speed != Speed::Max
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The lines of a log of `entries`, chained as `spawn` writes them.
+    fn log(entries: &[Entry]) -> Vec<String> {
+        let mut hash = String::new();
+        entries
+            .iter()
+            .map(|entry| {
+                let entry = serde_json::to_string(entry).unwrap();
+                hash = chain(&hash, &entry);
+                serde_json::to_string(&Line {
+                    entry: &RawValue::from_string(entry).unwrap(),
+                    hash: hash.clone(),
+                })
+                .unwrap()
+            })
+            .collect()
+    }
+
+    /// Verify a log of `lines`, written to a file named after `name`.
+    fn verify_lines(name: &str, lines: &[String]) -> Result<Report> {
+        let path = std::env::temp_dir().join(format!(
+            "vitellary-verification-{}-{}.jsonl",
+            std::process::id(),
+            name
+        ));
+        std::fs::write(&path, lines.join("\n") + "\n").unwrap();
+        let report = verify(&path);
+        std::fs::remove_file(&path).unwrap();
+        report
+    }
+
+    fn entries() -> Vec<Entry> {
+        vec![
+            Entry::Header {
+                version: VERSION,
+                frame_rate: 30,
+                started: 0,
+            },
+            Entry::FrameRate { frame_rate: 60 },
+            Entry::Detached {
+                reason: "the game exited".to_owned(),
+            },
+        ]
+    }
+
+    #[test]
+    fn intact_log_verifies() {
+        let report = verify_lines("intact", &log(&entries())).unwrap();
+        assert_eq!(report.entries, 3);
+    }
+
+    #[test]
+    fn tampered_entry_fails() {
+        let mut lines = log(&entries());
+        lines[2] = lines[2].replace("the game exited", "the game crashed");
+        let err = verify_lines("tampered", &lines).unwrap_err();
+        assert!(
+            err.to_string().starts_with("line 3: hash mismatch"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn reordered_entries_fail() {
+        let mut lines = log(&entries());
+        lines.swap(1, 2);
+        let err = verify_lines("reordered", &lines).unwrap_err();
+        assert!(
+            err.to_string().starts_with("line 2: hash mismatch"),
+            "{}",
+            err
+        );
+    }
+}